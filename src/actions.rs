@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/*
+    The Action struct stores one digit the user entered during a game
+    row and col are the cell, digit is what was entered, and expected is the digit from the solution
+    at_ms is how far into the game (in milliseconds) the digit was entered
+    think_ms is how long the cell had been selected before the digit was entered
+*/
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Action {
+    pub row: usize,
+    pub col: usize,
+    pub digit: char,
+    pub expected: char,
+    pub at_ms: u64,
+    pub think_ms: u64,
+}
+
+impl Action {
+    // returns true if the entered digit matches the solution
+    pub fn correct(&self) -> bool {
+        self.digit == self.expected
+    }
+}
+
+// splits a game that lasted duration_ms into equal slices of time and counts the correct entries made in each slice
+    // entries at (or past) the very end of the game go in the last slice
+pub fn fills_per_slice(actions: &[Action], duration_ms: u64, slices: usize) -> Vec<u32> {
+    let mut counts = vec![0; slices];
+    if slices == 0 {
+        return counts;
+    }
+    for action in actions.iter().filter(|action| action.correct()) {
+        let slice = (action.at_ms as u128 * slices as u128 / duration_ms.max(1) as u128) as usize;
+        counts[slice.min(slices - 1)] += 1;
+    }
+    counts
+}
+
+// turns a digit char into an index from 0 to 8 (so '1' is 0), or None if it isn't a digit from 1 to 9
+pub fn digit_index(digit: char) -> Option<usize> {
+    digit.to_digit(10).filter(|&digit| digit >= 1).map(|digit| digit as usize - 1)
+}
@@ -0,0 +1,44 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/*
+    counts the heap allocations the app makes, in the dev-tools build only (cargo run --features dev-tools)
+    every allocation still goes to the system allocator, this just counts them on the way through
+    the count is taken once per frame (see end_frame), so the settings can show how much garbage a frame makes
+        instead of it being guessed at
+*/
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LAST_FRAME: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    // a reallocation counts as an allocation, since growing a string or vector usually has to move it
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// called at the start of every frame -- the allocations counted since the last call become the last frame's count
+pub fn end_frame() {
+    LAST_FRAME.store(ALLOCATIONS.swap(0, Ordering::Relaxed), Ordering::Relaxed);
+}
+
+// how many allocations the last full frame made
+pub fn last_frame() -> usize {
+    LAST_FRAME.load(Ordering::Relaxed)
+}
@@ -0,0 +1,228 @@
+// a 9x9 grid of chars, the same layout the Sudoku struct uses for starting_grid, player_grid, and solution_grid
+    // empty cells are stored as '.', filled cells as the digits '1' through '9'
+pub type Grid = [[char; 9]; 9];
+
+pub const DIGITS: [char; 9] = ['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+// the text for each digit, so drawing a digit doesn't format it into a new string first
+const DIGIT_LABELS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+// the label for a digit from 1 to 9, or an empty label for anything else (like an empty cell)
+pub fn digit_label(digit: char) -> &'static str {
+    crate::actions::digit_index(digit).map_or("", |index| DIGIT_LABELS[index])
+}
+
+// a parity mask for the even/odd variant uses the same layout as a grid
+    // 'e' marks a cell that must hold an even digit, 'o' a cell that must hold an odd digit, and '.' a cell with no requirement
+pub const NO_PARITY: Grid = [['.'; 9]; 9];
+
+// returns the 27 units of the board (9 rows, 9 columns, then 9 boxes) as lists of (row, col) coordinates
+pub fn units() -> Vec<[(usize, usize); 9]> {
+    let mut units = Vec::with_capacity(27);
+    for i in 0..9 {
+        units.push(std::array::from_fn(|j| (i, j)));
+    }
+    for i in 0..9 {
+        units.push(std::array::from_fn(|j| (j, i)));
+    }
+    for i in 0..9 {
+        // the top left corner of box i, then step through the box one cell at a time
+        let (box_row, box_col) = ((i / 3) * 3, (i % 3) * 3);
+        units.push(std::array::from_fn(|j| (box_row + j / 3, box_col + j % 3)));
+    }
+    units
+}
+
+// returns true if digit can go at (row, col) without repeating in that cell's row, column, or 3x3 box
+    // the cell itself is ignored, so this also works for checking a digit that is already placed
+pub fn is_legal(grid: &Grid, row: usize, col: usize, digit: char) -> bool {
+    let (box_row, box_col) = ((row / 3) * 3, (col / 3) * 3);
+    for i in 0..9 {
+        if i != col && grid[row][i] == digit {
+            return false;
+        }
+        if i != row && grid[i][col] == digit {
+            return false;
+        }
+        let (r, c) = (box_row + i / 3, box_col + i % 3);
+        if (r, c) != (row, col) && grid[r][c] == digit {
+            return false;
+        }
+    }
+    true
+}
+
+// returns true if digit meets the parity requirement (if any) of the cell at (row, col) in the mask
+pub fn parity_allows(mask: &Grid, row: usize, col: usize, digit: char) -> bool {
+    let even = digit.to_digit(10).is_some_and(|digit| digit % 2 == 0);
+    match mask[row][col] {
+        'e' => even,
+        'o' => !even,
+        _ => true,
+    }
+}
+
+// the parity mark for a digit -- 'e' if it is even, 'o' if it is odd
+pub fn parity_of(digit: char) -> char {
+    if digit.to_digit(10).is_some_and(|digit| digit % 2 == 0) { 'e' } else { 'o' }
+}
+
+// returns the digits that could legally go in an empty cell -- a filled cell has no candidates
+pub fn candidates(grid: &Grid, row: usize, col: usize) -> Vec<char> {
+    if grid[row][col] != '.' {
+        return Vec::new();
+    }
+    DIGITS.iter().copied().filter(|&digit| is_legal(grid, row, col, digit)).collect()
+}
+
+// counts how many times each digit is on the board -- index 0 is the digit 1
+    // if solution is given, only the digits in the cells where they match the solution are counted
+pub fn digit_counts(grid: &Grid, solution: Option<&Grid>) -> [usize; 9] {
+    let mut counts = [0; 9];
+    for row in 0..9 {
+        for col in 0..9 {
+            let digit = grid[row][col];
+            if solution.is_some_and(|solution| solution[row][col] != digit) {
+                continue;
+            }
+            if let Some(index) = crate::actions::digit_index(digit) {
+                counts[index] += 1;
+            }
+        }
+    }
+    counts
+}
+
+// returns every empty cell where digit could legally go
+pub fn placements(grid: &Grid, digit: char) -> Vec<(usize, usize)> {
+    (0..81)
+        .map(|i| (i / 9, i % 9))
+        .filter(|&(row, col)| grid[row][col] == '.' && is_legal(grid, row, col, digit))
+        .collect()
+}
+
+// returns every empty cell that is the only legal spot for digit in one of its units (its forced positions)
+    // units that already contain the digit are skipped, and each cell is only listed once
+pub fn forced_positions(grid: &Grid, digit: char) -> Vec<(usize, usize)> {
+    let mut forced = Vec::new();
+    for unit in units() {
+        if unit.iter().any(|&(row, col)| grid[row][col] == digit) {
+            continue;
+        }
+        let mut spots = unit.iter().filter(|&&(row, col)| grid[row][col] == '.' && is_legal(grid, row, col, digit));
+        if let (Some(&spot), None) = (spots.next(), spots.next()) {
+            if !forced.contains(&spot) {
+                forced.push(spot);
+            }
+        }
+    }
+    forced
+}
+
+// the Single enum is a basic deduction that is available on a board
+    // Naked is a cell with only one candidate, and Hidden is a digit with only one legal spot in a row, column, or box
+#[derive(Clone, Copy, PartialEq)]
+pub enum Single {
+    Naked,
+    Hidden,
+}
+
+// looks for a single on the board, without saying where it is -- naked singles are checked first since they are easier to spot
+    // returns None if the board can't be moved forward with singles alone
+pub fn find_single(grid: &Grid) -> Option<Single> {
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid[row][col] == '.' && candidates(grid, row, col).len() == 1 {
+                return Some(Single::Naked);
+            }
+        }
+    }
+    DIGITS.iter().any(|&digit| !forced_positions(grid, digit).is_empty()).then_some(Single::Hidden)
+}
+
+// the Unit enum is the kind of unit a conflict is in, along with which one it is (0 to 8, top to bottom and left to right)
+#[derive(Clone, Copy, PartialEq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+}
+
+impl Unit {
+    // the unit's name as the user would say it, e.g. "column 4" or "the top left box"
+    pub fn name(self) -> String {
+        const BOXES: [&str; 9] = ["top left", "top middle", "top right", "middle left", "center", "middle right", "bottom left", "bottom middle", "bottom right"];
+        match self {
+            Unit::Row(index) => format!("row {}", index + 1),
+            Unit::Column(index) => format!("column {}", index + 1),
+            Unit::Box(index) => format!("the {} box", BOXES[index]),
+        }
+    }
+}
+
+// the Conflict struct is a digit that appears twice in one unit -- cells are the two cells holding it
+#[derive(Clone, Copy, PartialEq)]
+pub struct Conflict {
+    pub unit: Unit,
+    pub digit: char,
+    pub cells: [(usize, usize); 2],
+}
+
+impl Conflict {
+    // a one line explanation of the conflict, e.g. "Two 5s in column 4"
+    pub fn explanation(&self) -> String {
+        format!("Two {}s in {}", self.digit, self.unit.name())
+    }
+}
+
+// finds every pair of cells that hold the same digit in a row, column, or box
+    // two cells in the same row and box are listed once for each unit, since both rules are broken
+pub fn conflicts(grid: &Grid) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for (index, unit) in units().into_iter().enumerate() {
+        let kind = match index / 9 {
+            0 => Unit::Row(index % 9),
+            1 => Unit::Column(index % 9),
+            _ => Unit::Box(index % 9),
+        };
+        for (i, &(row, col)) in unit.iter().enumerate() {
+            let digit = grid[row][col];
+            if digit == '.' {
+                continue;
+            }
+            for &other in &unit[i + 1..] {
+                if grid[other.0][other.1] == digit {
+                    conflicts.push(Conflict { unit: kind, digit, cells: [(row, col), other] });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+// converts an 81 character puzzle string (like the ones in the puzzle json files) into a grid
+    // the string is read row by row, so the char at index row * 9 + col goes to grid[row][col]
+    // if the string is too short, the missing cells are left empty
+pub fn string_to_grid(string: &str) -> Grid {
+    let mut grid = [['.'; 9]; 9];
+    for (index, c) in string.chars().take(81).enumerate() {
+        grid[index / 9][index % 9] = c;
+    }
+    grid
+}
+
+// converts a grid back into an 81 character string, row by row
+pub fn grid_to_string(grid: &Grid) -> String {
+    grid.iter().flatten().collect()
+}
+
+// hashes a puzzle string into a short id, for puzzles that don't come with one
+    // this is FNV-1a rather than std's hasher, because std's hasher isn't guaranteed to give the same result across rust versions
+pub fn puzzle_hash(puzzle: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in puzzle.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,49 @@
+use eframe::egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Response, Sense, Ui, Vec2, Visuals};
+
+// the gap between bars, as a fraction of a bar's width (half of it goes on each side of the bar)
+const BAR_GAP: f32 = 0.5;
+
+// draws a bar chart with one bar per value, scaled so the largest value fills the height, and returns its response
+    // each bar gets the matching label underneath it (labels can be empty), and the chart uses the live theme's colors
+pub fn draw_bar_chart(ui: &mut Ui, values: &[f32], labels: &[String], bar_width: f32, height: f32) -> Response {
+    let slot = bar_width * (1.0 + BAR_GAP);
+    let (response, painter) = ui.allocate_painter(Vec2::new(slot * values.len() as f32, height + 18.0), Sense::hover());
+    let max = values.iter().copied().fold(0.0, f32::max).max(f32::EPSILON);
+    let bar_color = ui.visuals().selection.bg_fill;
+    for (i, &value) in values.iter().enumerate() {
+        let left = response.rect.left() + i as f32 * slot + bar_width * BAR_GAP / 2.0;
+        let bar_height = value / max * height;
+        let bar = Rect::from_min_max(Pos2::new(left, response.rect.top() + height - bar_height), Pos2::new(left + bar_width, response.rect.top() + height));
+        painter.rect_filled(bar, 2.0, bar_color);
+        if let Some(label) = labels.get(i) {
+            painter.text(Pos2::new(bar.center().x, response.rect.top() + height + 9.0), Align2::CENTER_CENTER,
+                label, FontId::proportional(12.0), ui.visuals().text_color());
+        }
+    }
+    response
+}
+
+// paints one row per digit inside rect, each with a bar filling toward 9 placed digits
+    // counts are the digits on the board (index 0 is the digit 1), and a digit in complete gets a green bar once all 9 are correct
+    // it is painted rather than laid out, so it can sit beside the grid without moving it
+pub fn paint_digit_bars(painter: &Painter, rect: Rect, counts: &[usize; 9], complete: &[bool; 9], visuals: &Visuals) {
+    let row_height = rect.height() / 9.0;
+    let label_width = 20.0;
+    for (i, &count) in counts.iter().enumerate() {
+        let top = rect.top() + i as f32 * row_height;
+        let center_y = top + row_height / 2.0;
+        painter.text(Pos2::new(rect.left() + label_width / 2.0, center_y), Align2::CENTER_CENTER,
+            (i + 1).to_string(), FontId::proportional(14.0), visuals.text_color());
+        let track = Rect::from_min_max(Pos2::new(rect.left() + label_width, center_y - row_height * 0.3), Pos2::new(rect.right(), center_y + row_height * 0.3));
+        painter.rect_filled(track, 2.0, visuals.extreme_bg_color);
+        let color = if complete[i] { Color32::from_rgb(60, 170, 90) } else { visuals.selection.bg_fill };
+        let filled = Rect::from_min_size(track.min, Vec2::new(track.width() * count.min(9) as f32 / 9.0, track.height()));
+        painter.rect_filled(filled, 2.0, color);
+    }
+}
+
+// draws a vertical line across the bars of a chart drawn by draw_bar_chart, at a fraction (0 to 1) of the way along it
+pub fn draw_marker(ui: &Ui, chart: &Response, height: f32, fraction: f32, color: Color32) {
+    let x = chart.rect.left() + fraction.clamp(0.0, 1.0) * chart.rect.width();
+    ui.painter().vline(x, chart.rect.top()..=chart.rect.top() + height, (2.0, color));
+}
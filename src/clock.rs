@@ -0,0 +1,168 @@
+use std::sync::Once;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Local};
+
+/*
+    all of the app's time handling goes through this module, so the edge cases are handled in one place
+    gameplay times (the game clock, think times, toasts) are durations measured with Instant, which never goes backwards
+    wall-clock times are only used for things that are stored: the day numbers in stats, the last update check, and file names
+    recorded days are local day numbers: days since the unix epoch, counted in the user's timezone at the time, so a day in the
+        stats starts and ends at the user's own midnight -- time that was already recorded keeps its day when the user travels
+        or the clocks change, and only new time goes by the new offset (days recorded before this were UTC days, which are
+        off by at most one day near midnight)
+    none of these functions panic or fall back to zero when the clock misbehaves -- they log the problem and clamp instead
+*/
+
+// the number of seconds in a day
+pub const SECS_PER_DAY: f64 = 86400.0;
+
+// the current time in seconds since the unix epoch
+    // a system clock set before 1970 is logged and treated as the epoch itself
+pub fn unix_secs() -> f64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(time) => time.as_secs_f64(),
+        Err(err) => {
+            println!("The system clock is set before 1970 (by {:?}), using 1970 instead", err.duration());
+            0.0
+        }
+    }
+}
+
+// the current time in whole seconds since the unix epoch, which is how times are stored in settings
+pub fn unix_time() -> u64 {
+    unix_secs() as u64
+}
+
+// today's day number (days since the unix epoch, in the local timezone)
+pub fn today() -> u64 {
+    let now = unix_secs();
+    day_number(now, utc_offset_secs(now))
+}
+
+// the day number of a unix time, given how far ahead of UTC the timezone is in seconds (negative for behind)
+    // a time before the epoch is day 0, and a time too far in the future for a day number is the last day there is
+pub fn day_number(unix_secs: f64, offset_secs: i64) -> u64 {
+    // float to int casts saturate, so this clamps at both ends instead of wrapping
+    ((unix_secs + offset_secs as f64) / SECS_PER_DAY).floor() as u64
+}
+
+// how far ahead of UTC the local timezone is at a unix time, in seconds -- the offset changes with daylight saving time, so it
+    // is looked up for the time it is needed at
+    // the offset comes from the system's timezone (through chrono::Local) on every platform -- a time chrono can't represent
+    // is counted in UTC instead, which is logged the first time it happens
+pub fn utc_offset_secs(unix_secs: f64) -> i64 {
+    match DateTime::from_timestamp(unix_secs as i64, 0) {
+        Some(time) => time.with_timezone(&Local).offset().local_minus_utc() as i64,
+        None => {
+            static WARNED: Once = Once::new();
+            WARNED.call_once(|| println!("The local timezone couldn't be found for {} seconds after 1970, counting days in UTC instead", unix_secs));
+            0
+        }
+    }
+}
+
+// the short name of the weekday of a day number -- day 0 (January 1st 1970) was a Thursday
+pub fn weekday_name(day: u64) -> &'static str {
+    ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"][(day % 7) as usize]
+}
+
+// a day number as a calendar date, e.g. "2024-03-09" (see Howard Hinnant's days_from_civil, run backwards)
+pub fn date_string(day: u64) -> String {
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day_of_month)
+}
+
+// the seconds from a stored unix time until now, or None if the stored time is in the future (the clock was moved back)
+pub fn secs_since(then: u64) -> Option<u64> {
+    let now = unix_time();
+    if then > now {
+        println!("A stored time is {} seconds in the future, the system clock may have been moved back", then - now);
+        return None;
+    }
+    Some(now - then)
+}
+
+// formats a game time in seconds with tenths, e.g. 75.4 -- the tenths are cut off rather than rounded, the same as the whole
+    // seconds every record is stored in, so a time shown during the game is never more than the time recorded when it ends
+pub fn format_tenths(time: Duration) -> String {
+    format!("{}.{}", time.as_secs(), time.subsec_millis() / 100)
+}
+
+// adds a running stretch of time onto the time banked so far
+    // the sum can't realistically overflow, but if it does it is logged and clamped, so a record is never reset to zero
+pub fn add_elapsed(banked: Duration, running: Duration) -> Duration {
+    banked.checked_add(running).unwrap_or_else(|| {
+        println!("The game clock overflowed, keeping the largest time instead");
+        Duration::MAX
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-03-09 at midnight UTC
+    const MARCH_9: f64 = 19791.0 * SECS_PER_DAY;
+    const HOUR: i64 = 3600;
+
+    #[test]
+    fn day_rolls_over_at_local_midnight() {
+        // a session from half a minute before midnight to half a minute after is on two days
+        assert_eq!(day_number(MARCH_9 - 30.0, 0), 19790);
+        assert_eq!(day_number(MARCH_9 + 30.0, 0), 19791);
+        assert_eq!(date_string(day_number(MARCH_9 + 30.0, 0)), "2024-03-09");
+    }
+
+    #[test]
+    fn offset_moves_midnight() {
+        // 23:00 UTC is already tomorrow two hours east of UTC, and 01:00 UTC is still yesterday five hours west of it
+        assert_eq!(day_number(MARCH_9 - HOUR as f64, 2 * HOUR), 19791);
+        assert_eq!(day_number(MARCH_9 + HOUR as f64, -5 * HOUR), 19790);
+    }
+
+    #[test]
+    fn out_of_range_times_clamp() {
+        assert_eq!(day_number(-1000.0, 0), 0);
+        assert_eq!(day_number(0.0, -HOUR), 0);
+        assert_eq!(day_number(f64::MAX, 0), u64::MAX);
+    }
+
+    #[test]
+    fn local_offset_is_a_real_timezone() {
+        let offset = utc_offset_secs(unix_secs());
+        assert!((-14 * HOUR..=14 * HOUR).contains(&offset));
+    }
+
+    #[test]
+    fn unrepresentable_times_are_counted_in_utc() {
+        assert_eq!(utc_offset_secs(f64::MAX), 0);
+        assert_eq!(utc_offset_secs(-f64::MAX), 0);
+    }
+
+    #[test]
+    fn elapsed_time_clamps_instead_of_resetting() {
+        let near_max = Duration::MAX - Duration::from_secs(1);
+        assert_eq!(add_elapsed(near_max, Duration::from_secs(5)), Duration::MAX);
+        assert_eq!(add_elapsed(Duration::from_secs(60), Duration::from_millis(500)), Duration::from_millis(60_500));
+    }
+
+    #[test]
+    fn future_stored_time_isnt_counted() {
+        assert_eq!(secs_since(unix_time() + 1000), None);
+        assert!(secs_since(0).is_some());
+    }
+
+    #[test]
+    fn tenths_are_cut_off() {
+        assert_eq!(format_tenths(Duration::from_millis(75_499)), "75.4");
+        assert_eq!(format_tenths(Duration::from_millis(75_999)), "75.9");
+    }
+}
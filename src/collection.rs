@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::board;
+use crate::persist;
+use crate::source;
+use crate::Puzzle;
+
+// saved collections are kept in their own folder next to the puzzles folder, one json file per collection
+pub const COLLECTIONS_DIR: &str = "./collections";
+
+// the longest name a collection can have, in characters
+const MAX_NAME: usize = 40;
+
+// the names the app already uses for its own difficulties, which a collection can't take
+const RESERVED_NAMES: [&str; 6] = ["Beginner", "Intermediate", "Advanced", "Test", "Samurai", "Imported"];
+
+/*
+    The Collection struct is a named set of puzzles put together by the user, which is played like a difficulty
+    a collection file is a puzzle file with a name, so a collection can also be read by Puzzles::load, and a puzzle file
+        can be loaded as a collection
+    collection files can be shared -- loading one checks every puzzle in it first (see Collection::import)
+*/
+#[derive(Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl Collection {
+    // the file a collection with this name is saved in
+    fn path(name: &str) -> String {
+        format!("{}/{}.json", COLLECTIONS_DIR, name)
+    }
+
+    // the names of every saved collection, sorted -- none if the collections folder doesn't exist yet
+    pub fn names() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(COLLECTIONS_DIR) else { return Vec::new() };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .filter(|name| check_name(name).is_ok())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // the file to read the puzzles of a difficulty from, if the difficulty is a saved collection
+    pub fn file_for(difficulty: &str) -> Option<String> {
+        let path = Self::path(difficulty);
+        (check_name(difficulty).is_ok() && Path::new(&path).exists()).then_some(path)
+    }
+
+    // reads a saved collection, or a new empty one if there isn't one with this name yet
+    pub fn load_or_new(name: &str) -> Self {
+        persist::read_json(&Self::path(name)).unwrap_or_else(|| Self { name: name.to_string(), puzzles: Vec::new() })
+    }
+
+    // writes the collection to its file in the collections folder
+    pub fn save(&self) -> Result<(), String> {
+        check_name(&self.name)?;
+        fs::create_dir_all(COLLECTIONS_DIR).map_err(|err| format!("Failed to create {}: {}", COLLECTIONS_DIR, err))?;
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to save the collection: {}", err))?;
+        persist::write_atomic(&Self::path(&self.name), json.as_bytes())
+    }
+
+    // adds a puzzle to the collection, unless the same board is already in it -- returns false if it was already there
+    pub fn add(&mut self, puzzle: Puzzle) -> bool {
+        if self.puzzles.iter().any(|existing| existing.puzzle == puzzle.puzzle) {
+            return false;
+        }
+        self.puzzles.push(puzzle);
+        true
+    }
+
+    // reads a collection file from anywhere (e.g. one someone shared) and saves it into the collections folder
+        // every puzzle is checked first, and the ones that can't be played are left out -- the returned list says which and why
+        // a file without a name (like one of the puzzle files) is named after the file
+    pub fn import(path: &Path) -> Result<(Self, Vec<String>), String> {
+        #[derive(Deserialize)]
+        struct CollectionFile {
+            #[serde(default)]
+            name: Option<String>,
+            puzzles: Vec<Puzzle>,
+        }
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let file: CollectionFile = serde_json::from_str(&contents).map_err(|err| format!("{} isn't a puzzle collection: {}", path.display(), err))?;
+        let name = file.name
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        check_name(&name)?;
+
+        let mut collection = Self { name, puzzles: Vec::new() };
+        let mut skipped = Vec::new();
+        for (index, puzzle) in file.puzzles.into_iter().enumerate() {
+            match check_puzzle(&puzzle) {
+                Ok(()) => {
+                    if !collection.add(puzzle) {
+                        skipped.push(format!("Puzzle {}: the same board is already in the collection", index));
+                    }
+                }
+                Err(err) => skipped.push(format!("Puzzle {}: {}", index, err)),
+            }
+        }
+        if collection.puzzles.is_empty() {
+            return Err(format!("None of the puzzles in {} can be played", path.display()));
+        }
+        collection.save()?;
+        Ok((collection, skipped))
+    }
+}
+
+// checks that a collection name can be used as both a difficulty and a file name
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("The collection needs a name".to_string());
+    }
+    if name.chars().count() > MAX_NAME {
+        return Err(format!("Collection names can be at most {} characters", MAX_NAME));
+    }
+    if !name.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_') || name.trim() != name {
+        return Err("Collection names can only use letters, digits, spaces, - and _".to_string());
+    }
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name)) {
+        return Err(format!("\"{}\" is already a difficulty", name));
+    }
+    Ok(())
+}
+
+// checks that a puzzle from a collection file can be played: the boards are the right shape, the givens have exactly one
+    // solution, and it is the solution the file gives (and an even/odd mask, if there is one, fits that solution)
+fn check_puzzle(puzzle: &Puzzle) -> Result<(), String> {
+    let shaped = |string: &str, allowed: &dyn Fn(char) -> bool| string.chars().count() == 81 && string.chars().all(allowed);
+    if !shaped(&puzzle.puzzle, &|c| c == '.' || ('1'..='9').contains(&c)) {
+        return Err("the puzzle isn't 81 cells of 1-9 or .".to_string());
+    }
+    if !shaped(&puzzle.solution, &|c| ('1'..='9').contains(&c)) {
+        return Err("the solution isn't 81 digits".to_string());
+    }
+    if puzzle.parity.as_deref().is_some_and(|mask| !shaped(mask, &|c| c == '.' || c == 'e' || c == 'o')) {
+        return Err("the even/odd mask isn't 81 cells of e, o, or .".to_string());
+    }
+    let solution = source::validate(&board::string_to_grid(&puzzle.puzzle)).map_err(|err| err.to_lowercase())?;
+    if board::grid_to_string(&solution) != puzzle.solution {
+        return Err("the solution given doesn't solve the puzzle".to_string());
+    }
+    if let Some(mask) = &puzzle.parity {
+        let mask = board::string_to_grid(mask);
+        if (0..81).any(|i| !board::parity_allows(&mask, i / 9, i % 9, solution[i / 9][i % 9])) {
+            return Err("the even/odd mask doesn't match the solution".to_string());
+        }
+    }
+    Ok(())
+}
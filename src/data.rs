@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::settings::{Settings, SETTINGS_PATH};
+use crate::{clock, persist};
+use crate::stats::{Stats, STATS_PATH};
+
+// the version of the export format -- bump this whenever the bundle changes in a way older versions can't read
+pub const DATA_VERSION: u32 = 1;
+
+/*
+    The DataBundle struct holds all of the user's data in one place, so it can be exported to and imported from a single file
+    version is checked on import so a file from a newer version of the app is rejected instead of half-read
+*/
+#[derive(Serialize, Deserialize)]
+pub struct DataBundle {
+    pub version: u32,
+    pub settings: Settings,
+    pub stats: Stats,
+}
+
+impl DataBundle {
+    // bundles up a copy of the current data
+    pub fn new(settings: &Settings, stats: &Stats) -> Self {
+        Self {
+            version: DATA_VERSION,
+            settings: settings.clone(),
+            stats: stats.clone(),
+        }
+    }
+
+    // writes the bundle to path as json
+    pub fn export(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to export data: {}", err))?;
+        write_files(&[(path, json)])
+    }
+
+    // reads a bundle from path, rejecting files that are corrupt or from an unsupported version
+    pub fn read(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path, err))?;
+        // check the version on its own first, so a newer file gets a clear message instead of a parse error
+        let version = serde_json::from_str::<serde_json::Value>(&contents)
+            .ok()
+            .and_then(|value| value.get("version").and_then(|version| version.as_u64()))
+            .ok_or(format!("{} is not a Sudoku data export", path))?;
+        if version != DATA_VERSION as u64 {
+            return Err(format!("{} is from an unsupported version ({}) of the app", path, version));
+        }
+        serde_json::from_str(&contents).map_err(|err| format!("{} is corrupt: {}", path, err))
+    }
+
+    // describes what importing the bundle would bring in, so the user can check before overwriting anything
+    pub fn summary(&self) -> String {
+        let mut difficulties: Vec<_> = self.stats.difficulties.iter().collect();
+        difficulties.sort_by_key(|(difficulty, _)| difficulty.to_string());
+
+        let games: u32 = difficulties.iter().map(|(_, stats)| stats.games_played()).sum();
+        let mut summary = format!("{} games played", games);
+        for (difficulty, stats) in difficulties {
+            if let Some(best) = stats.best_time {
+                summary.push_str(&format!("\nBest {} time: {} seconds", difficulty, best));
+            }
+        }
+        summary
+    }
+
+    // overwrites the saved settings and stats with the ones in the bundle
+    pub fn apply(&self) -> Result<(), String> {
+        let settings = serde_json::to_string_pretty(&self.settings).map_err(|err| err.to_string())?;
+        let stats = serde_json::to_string_pretty(&self.stats).map_err(|err| err.to_string())?;
+        write_files(&[(SETTINGS_PATH, settings), (STATS_PATH, stats)])
+    }
+}
+
+// writes the user's finished games to path as CSV, one row per game, for opening in a spreadsheet
+    // with no games recorded yet, the file only has the header row
+pub fn export_csv(stats: &Stats, path: &str) -> Result<(), String> {
+    let mut csv = String::from("date,difficulty,puzzle,result,time_secs,strikes,hints,flawless,retry\n");
+    for game in &stats.games {
+        let puzzle = if game.variant { format!("variant of #{}", game.puzzle_id) } else { game.puzzle_id.clone() };
+        csv.push_str(&format!("{},{},{},{},{},{},{},{},{}\n",
+            clock::date_string(game.day),
+            csv_field(&game.difficulty),
+            csv_field(&puzzle),
+            if game.won { "won" } else { "lost" },
+            game.secs,
+            game.strikes,
+            game.hints,
+            game.won && game.strikes == 0,
+            game.retry,
+        ));
+    }
+    write_files(&[(path, csv)])
+}
+
+// quotes a CSV field if it contains a comma, quote, or line break, doubling any quotes inside it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+// writes several files so that either all of them are updated or none of them are
+    // every file is first staged next to itself (see persist::stage), and only once all of those succeed are they committed over the originals
+    // if a commit fails, the files already committed are put back from their backups (or deleted, if they didn't exist before)
+    // and the rest are never committed -- the files written together are all json, so every one that existed has a backup
+fn write_files(files: &[(&str, String)]) -> Result<(), String> {
+    let mut staged = Vec::new();
+    for (path, contents) in files {
+        match persist::stage(path, contents.as_bytes()) {
+            Ok(temp_path) => staged.push(temp_path),
+            Err(err) => {
+                // clean up anything that was already staged, leaving the original files untouched
+                for temp in &staged {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(err);
+            }
+        }
+    }
+    let existed: Vec<bool> = files.iter().map(|(path, _)| Path::new(path).exists()).collect();
+    for (i, (temp_path, (path, _))) in staged.iter().zip(files).enumerate() {
+        if let Err(err) = persist::commit(temp_path, path) {
+            for temp in &staged[i + 1..] {
+                let _ = fs::remove_file(temp);
+            }
+            for ((committed, _), &existed) in files[..i].iter().zip(&existed) {
+                let undone = if existed { persist::restore_backup(committed) } else { persist::remove(committed) };
+                if let Err(undo_err) = undone {
+                    println!("Failed to undo writing {}: {}", committed, undo_err);
+                }
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::GameRecord;
+
+    #[test]
+    fn csv_has_a_row_per_game_with_its_hints() {
+        let mut stats = Stats::default();
+        stats.games.push(GameRecord {
+            difficulty: "Beginner".to_string(),
+            day: 0,
+            secs: 300,
+            strikes: 1,
+            hints: 2,
+            won: true,
+            retry: false,
+            puzzle_id: "b-12".to_string(),
+            variant: false,
+        });
+        let path = persist::scratch_dir("data-csv").join("games.csv").to_string_lossy().into_owned();
+        export_csv(&stats, &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "date,difficulty,puzzle,result,time_secs,strikes,hints,flawless,retry");
+        assert_eq!(lines[1], format!("{},Beginner,b-12,won,300,1,2,false,false", clock::date_string(0)));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn csv_fields_with_commas_or_quotes_are_quoted() {
+        assert_eq!(csv_field("Beginner"), "Beginner");
+        assert_eq!(csv_field("Mine, old"), "\"Mine, old\"");
+        assert_eq!(csv_field("the \"hard\" ones"), "\"the \"\"hard\"\" ones\"");
+    }
+
+    // a path that can be staged next to but not committed over, since a file can't be renamed over a directory with things in it
+    fn blocked_path(dir: &Path) -> String {
+        let blocked = dir.join("blocked.json");
+        fs::create_dir_all(blocked.join("inside")).unwrap();
+        blocked.to_string_lossy().into_owned()
+    }
+
+    fn path_in(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn all_files_are_written_together() {
+        let dir = persist::scratch_dir("data-written");
+        let (first, second) = (path_in(&dir, "first.json"), path_in(&dir, "second.json"));
+        persist::write_atomic(&first, b"[1]").unwrap();
+        write_files(&[(&first, "[2]".to_string()), (&second, "[3]".to_string())]).unwrap();
+        assert_eq!(fs::read_to_string(&first).unwrap(), "[2]");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "[3]");
+    }
+
+    #[test]
+    fn a_failed_commit_puts_the_committed_files_back() {
+        let dir = persist::scratch_dir("data-restored");
+        let first = path_in(&dir, "first.json");
+        persist::write_atomic(&first, b"[1]").unwrap();
+        persist::write_atomic(&first, b"[2]").unwrap();
+        let blocked = blocked_path(&dir);
+        assert!(write_files(&[(&first, "[3]".to_string()), (&blocked, "[4]".to_string())]).is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "[2]");
+        assert_eq!(persist::read_json::<Vec<u32>>(&first), Some(vec![2]));
+    }
+
+    #[test]
+    fn a_failed_commit_deletes_the_files_that_were_new() {
+        let dir = persist::scratch_dir("data-deleted");
+        let first = path_in(&dir, "first.json");
+        let blocked = blocked_path(&dir);
+        assert!(write_files(&[(&first, "[1]".to_string()), (&blocked, "[2]".to_string())]).is_err());
+        assert!(!Path::new(&first).exists());
+    }
+
+    #[test]
+    fn a_failed_commit_leaves_nothing_staged() {
+        let dir = persist::scratch_dir("data-staged");
+        let (first, last) = (path_in(&dir, "first.json"), path_in(&dir, "last.json"));
+        let blocked = blocked_path(&dir);
+        assert!(write_files(&[(&first, "[1]".to_string()), (&blocked, "[2]".to_string()), (&last, "[3]".to_string())]).is_err());
+        let left: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        assert_eq!(left, vec!["blocked.json"]);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use serde::Serialize;
+use crate::persist;
+use crate::savegame::SavedGame;
+use crate::settings::Settings;
+
+// feedback reports are written next to the settings file, one file per report
+pub const FEEDBACK_DIR: &str = "./feedback";
+
+// the FeedbackCategory enum is what kind of feedback a report is, picked from the dropdown in the feedback window
+#[derive(Serialize, Clone, Copy, PartialEq)]
+pub enum FeedbackCategory {
+    Bug,
+    Suggestion,
+    Other,
+}
+
+impl FeedbackCategory {
+    pub const ALL: [FeedbackCategory; 3] = [FeedbackCategory::Bug, FeedbackCategory::Suggestion, FeedbackCategory::Other];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedbackCategory::Bug => "Bug",
+            FeedbackCategory::Suggestion => "Suggestion",
+            FeedbackCategory::Other => "Other",
+        }
+    }
+}
+
+/*
+    The FeedbackReport struct is everything that goes into a feedback file
+    app_version and os help narrow down bugs, and settings is a copy of the user's settings when the report was written
+    game is only included if the user ticked "attach current game state" -- it is the game as it would be saved (see SavedGame),
+        so a report's game has everything needed to load it back onto the board
+*/
+#[derive(Serialize)]
+pub struct FeedbackReport {
+    pub app_version: String,
+    pub os: String,
+    pub category: FeedbackCategory,
+    pub message: String,
+    pub settings: Settings,
+    pub game: Option<SavedGame>,
+}
+
+impl FeedbackReport {
+    pub fn new(category: FeedbackCategory, message: String, settings: &Settings, game: Option<SavedGame>) -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+            category,
+            message,
+            settings: settings.clone(),
+            game,
+        }
+    }
+
+    // writes the report into the feedback folder and returns the path of the new file
+    pub fn write(&self) -> Result<String, String> {
+        self.write_in(FEEDBACK_DIR)
+    }
+
+    // writes the report into dir and returns the path of the new file
+        // the file is named after the time it was written, with -2, -3, and so on added for more reports in the same second
+        // each name is claimed with create_new before the report is written into it, so reports never overwrite each other
+    fn write_in(&self, dir: &str) -> Result<String, String> {
+        fs::create_dir_all(dir).map_err(|err| format!("Failed to create {}: {}", dir, err))?;
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to write feedback: {}", err))?;
+        let time = crate::clock::unix_time();
+        let mut count = 1;
+        let path = loop {
+            let path = match count {
+                1 => format!("{}/feedback-{}.json", dir, time),
+                _ => format!("{}/feedback-{}-{}.json", dir, time, count),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => break path,
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => count += 1,
+                Err(err) => return Err(format!("Failed to write {}: {}", path, err)),
+            }
+        };
+        // the claimed file is empty, which doesn't parse, so writing over it doesn't leave a .bak copy behind (see persist::commit)
+        persist::write_atomic(&path, json.as_bytes())?;
+        Ok(path)
+    }
+}
+
+/*
+    The FeedbackForm struct stores what the user has filled in so far in the feedback window
+    result is the path of the written report, or the error if it couldn't be written
+*/
+pub struct FeedbackForm {
+    pub category: FeedbackCategory,
+    pub message: String,
+    pub attach_game: bool,
+    pub result: Option<Result<String, String>>,
+}
+
+impl FeedbackForm {
+    pub fn new(attach_game: bool) -> Self {
+        Self {
+            category: FeedbackCategory::Bug,
+            message: String::new(),
+            attach_game,
+            result: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_in_the_same_second_get_their_own_files() {
+        let dir = persist::scratch_dir("feedback-same-second").to_string_lossy().into_owned();
+        let report = |message: &str| FeedbackReport::new(FeedbackCategory::Bug, message.to_string(), &Settings::default(), None);
+        let paths: Vec<String> = ["first", "second", "third"].iter().map(|message| report(message).write_in(&dir).unwrap()).collect();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        for (path, message) in paths.iter().zip(["first", "second", "third"]) {
+            let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            assert_eq!(written["message"], message);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui::{Context, FontData, FontDefinitions, FontFamily, FontId};
+use serde::{Deserialize, Serialize};
+
+// the name of the font family used for the digits on the board, so they can have their own font without changing the rest of the ui
+const DIGIT_FAMILY: &str = "board_digits";
+
+// the fonts are compiled into the app so no font files are needed at runtime (see assets/fonts/LICENSE.txt)
+const TABULAR_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono-Bold.ttf");
+const CLASSIC_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSerif.ttf");
+
+/*
+    The DigitStyle enum is the font used for the digits on the board
+    Standard is egui's default font, Tabular is a bold monospace font where 1 and 7 are easy to tell apart, and Classic is a serif font
+*/
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum DigitStyle {
+    Standard,
+    Tabular,
+    Classic,
+}
+
+impl DigitStyle {
+    pub const ALL: [DigitStyle; 3] = [DigitStyle::Standard, DigitStyle::Tabular, DigitStyle::Classic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DigitStyle::Standard => "Standard",
+            DigitStyle::Tabular => "High legibility",
+            DigitStyle::Classic => "Classic",
+        }
+    }
+
+    // the embedded font for the style, or None for the standard style
+    fn data(self) -> Option<&'static [u8]> {
+        match self {
+            DigitStyle::Standard => None,
+            DigitStyle::Tabular => Some(TABULAR_FONT),
+            DigitStyle::Classic => Some(CLASSIC_FONT),
+        }
+    }
+}
+
+// sets up the board digit font family for a style
+    // the family falls back to egui's default fonts, so if the embedded font is unusable the digits still draw in the default font
+pub fn install(ctx: &Context, style: DigitStyle) {
+    let mut fonts = FontDefinitions::default();
+    let mut family = Vec::new();
+    if let Some(data) = style.data() {
+        if looks_like_font(data) {
+            fonts.font_data.insert("digits".to_string(), FontData::from_static(data));
+            family.push("digits".to_string());
+        }
+        else {
+            println!("The {} digit font is not a valid font file, using the default font", style.name());
+        }
+    }
+    family.extend(fonts.families[&FontFamily::Proportional].iter().cloned());
+    fonts.families.insert(FontFamily::Name(DIGIT_FAMILY.into()), family);
+    ctx.set_fonts(fonts);
+}
+
+// the font for a digit on the board
+pub fn digit_font(size: f32) -> FontId {
+    FontId::new(size, FontFamily::Name(DIGIT_FAMILY.into()))
+}
+
+// checks the font file's header, since egui would panic on a file that isn't a TrueType or OpenType font
+fn looks_like_font(data: &[u8]) -> bool {
+    matches!(data.get(0..4), Some([0, 1, 0, 0]) | Some(b"OTTO") | Some(b"true"))
+}
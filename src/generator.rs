@@ -0,0 +1,88 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::board::{self, Grid, DIGITS, NO_PARITY};
+use crate::solver;
+
+// how many cells get a parity mark in a generated even/odd puzzle
+pub const PARITY_MARKS: usize = 18;
+
+// returns how many givens a generated puzzle should be left with for each difficulty
+    // fewer givens usually means a harder puzzle
+fn target_givens(difficulty: &str) -> usize {
+    match difficulty {
+        "Beginner" => 40,
+        "Intermediate" => 32,
+        "Advanced" => 26,
+        "Test" => 80,
+        _ => 32,
+    }
+}
+
+// generates a random puzzle for the difficulty, and returns it along with its solution
+    // the puzzle always has exactly one solution
+pub fn generate(difficulty: &str) -> (Grid, Grid) {
+    let mut rng = rand::thread_rng();
+    let solution = random_solution(&mut rng);
+    let puzzle = remove_cells(&solution, &NO_PARITY, target_givens(difficulty), &mut rng);
+    (puzzle, solution)
+}
+
+// generates a random even/odd puzzle for the difficulty, and returns it along with its solution and parity mask
+    // the parity marks narrow down the cells they are on, so the puzzle can be left with a few fewer givens than usual
+pub fn generate_even_odd(difficulty: &str) -> (Grid, Grid, Grid) {
+    let mut rng = rand::thread_rng();
+    let solution = random_solution(&mut rng);
+    let mask = parity_mask(&NO_PARITY, &solution, PARITY_MARKS, &mut rng);
+    let puzzle = remove_cells(&solution, &mask, target_givens(difficulty).saturating_sub(4), &mut rng);
+    (puzzle, solution, mask)
+}
+
+// marks count random empty cells of the puzzle with the parity of their digit in the solution
+    // the marks always agree with the solution, so they never change which solution a puzzle has
+pub fn parity_mask<R: Rng>(puzzle: &Grid, solution: &Grid, count: usize, rng: &mut R) -> Grid {
+    let mut mask = NO_PARITY;
+    let cells: Vec<(usize, usize)> = (0..81).map(|index| (index / 9, index % 9)).filter(|&(row, col)| puzzle[row][col] == '.').collect();
+    for &(row, col) in cells.choose_multiple(rng, count) {
+        mask[row][col] = board::parity_of(solution[row][col]);
+    }
+    mask
+}
+
+// builds a random complete board
+pub fn random_solution<R: Rng>(rng: &mut R) -> Grid {
+    // the three boxes on the diagonal don't share any rows or columns, so they can be filled with shuffled digits
+        // independently -- solving the rest of the grid then gives a random complete board
+    let mut grid = [['.'; 9]; 9];
+    for b in 0..3 {
+        let mut digits = DIGITS;
+        digits.shuffle(rng);
+        for (i, digit) in digits.into_iter().enumerate() {
+            grid[b * 3 + i / 3][b * 3 + i % 3] = digit;
+        }
+    }
+    solver::solve(&grid).expect("a grid with only the diagonal boxes filled always has a solution")
+}
+
+// removes cells from a complete board in a random order until target givens are left
+    // a cell is put back whenever removing it would allow more than one solution (with the parity mask taken into account)
+fn remove_cells<R: Rng>(solution: &Grid, mask: &Grid, target: usize, rng: &mut R) -> Grid {
+    let mut puzzle = *solution;
+    let mut cells: Vec<(usize, usize)> = (0..81).map(|index| (index / 9, index % 9)).collect();
+    cells.shuffle(rng);
+    let mut givens = 81;
+    for (row, col) in cells {
+        if givens <= target {
+            break;
+        }
+        let digit = puzzle[row][col];
+        puzzle[row][col] = '.';
+        if solver::count_solutions_with_parity(&puzzle, mask, 2) == 1 {
+            givens -= 1;
+        }
+        else {
+            puzzle[row][col] = digit;
+        }
+    }
+
+    puzzle
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+use eframe::egui::Color32;
+use crate::stats::DifficultyStats;
+
+// a blitz countdown turns urgent for its last minute
+const URGENT_TIME: Duration = Duration::from_secs(60);
+
+/*
+    The HeaderState struct is the part of a game the header's look depends on, taken from the game every frame
+    elapsed is the game's time so far, and time_left is what is left of the blitz countdown (None outside of blitz games)
+    strikes_left is how many more strikes the game can take before it is lost (see Sudoku::strikes_left)
+*/
+pub struct HeaderState {
+    pub elapsed: Duration,
+    pub time_left: Option<Duration>,
+    pub strikes_left: u32,
+}
+
+// the Tension enum is how the clock is drawn -- Behind is once the game is slower than the personal best for the difficulty,
+    // and Urgent is the last minute of a blitz countdown
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tension {
+    Calm,
+    Behind,
+    Urgent,
+}
+
+impl Tension {
+    // the clock's color, or None to leave it in the normal text color
+    pub fn color(self) -> Option<Color32> {
+        match self {
+            Tension::Calm => None,
+            Tension::Behind => Some(Color32::from_rgb(230, 150, 20)),
+            Tension::Urgent => Some(Color32::from_rgb(255, 60, 110)),
+        }
+    }
+}
+
+// the HeaderStyle struct is how the header is drawn right now -- timer is the clock's tension, and strike_chip is set once
+    // a single mistake more would end the game, for the warning chip under the difficulty
+pub struct HeaderStyle {
+    pub timer: Tension,
+    pub strike_chip: bool,
+}
+
+// works out how the header should look from the game and the records for its difficulty (None if it has none yet)
+    // the thresholds are only decided here, so the header can't drift from the rules -- the last minute of a countdown outranks
+    // being behind pace, and everything goes back to calm as soon as its condition no longer holds (e.g. a strike won back in arcade mode)
+pub fn header_style(state: &HeaderState, records: Option<&DifficultyStats>) -> HeaderStyle {
+    let behind = records.and_then(|records| records.best_time).is_some_and(|best| state.elapsed.as_secs() > best);
+    let timer = if state.time_left.is_some_and(|left| left < URGENT_TIME) {
+        Tension::Urgent
+    }
+    else if behind {
+        Tension::Behind
+    }
+    else {
+        Tension::Calm
+    };
+    HeaderStyle {
+        timer,
+        strike_chip: state.strikes_left == 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(elapsed_secs: u64, time_left_secs: Option<u64>, strikes_left: u32) -> HeaderState {
+        HeaderState {
+            elapsed: Duration::from_secs(elapsed_secs),
+            time_left: time_left_secs.map(Duration::from_secs),
+            strikes_left,
+        }
+    }
+
+    fn best(secs: u64) -> DifficultyStats {
+        DifficultyStats { best_time: Some(secs), ..Default::default() }
+    }
+
+    #[test]
+    fn calm_without_records_or_a_countdown() {
+        let style = header_style(&state(10_000, None, 3), None);
+        assert_eq!(style.timer, Tension::Calm);
+        assert!(!style.strike_chip);
+    }
+
+    #[test]
+    fn behind_only_once_slower_than_the_best() {
+        assert_eq!(header_style(&state(300, None, 3), Some(&best(300))).timer, Tension::Calm);
+        assert_eq!(header_style(&state(301, None, 3), Some(&best(300))).timer, Tension::Behind);
+        // records without a win have no best time to be behind
+        assert_eq!(header_style(&state(301, None, 3), Some(&DifficultyStats::default())).timer, Tension::Calm);
+    }
+
+    #[test]
+    fn urgent_for_the_last_minute_of_a_countdown() {
+        assert_eq!(header_style(&state(0, Some(60), 3), None).timer, Tension::Calm);
+        assert_eq!(header_style(&state(0, Some(59), 3), None).timer, Tension::Urgent);
+        assert_eq!(header_style(&state(0, Some(0), 3), None).timer, Tension::Urgent);
+    }
+
+    #[test]
+    fn urgent_outranks_behind() {
+        assert_eq!(header_style(&state(301, Some(30), 3), Some(&best(300))).timer, Tension::Urgent);
+        assert_eq!(header_style(&state(301, Some(90), 3), Some(&best(300))).timer, Tension::Behind);
+    }
+
+    #[test]
+    fn strike_chip_only_with_one_strike_left() {
+        assert!(!header_style(&state(0, None, 2), None).strike_chip);
+        assert!(header_style(&state(0, None, 1), None).strike_chip);
+        assert!(!header_style(&state(0, None, 0), None).strike_chip);
+    }
+}
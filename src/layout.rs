@@ -0,0 +1,22 @@
+use eframe::egui::{Id, InnerResponse, Ui};
+
+// lays out a row of widgets centered in the space that is available right now
+    // the row's width is measured as it is drawn and remembered for the next frame, so nothing is centered from a hard-coded width --
+    // the row re-centers itself whenever the window or the row changes size (e.g. when a button appears, or the screen changes)
+    // id_salt has to be unique among the rows on the same screen
+pub fn centered_row<R>(ui: &mut Ui, id_salt: &str, add_contents: impl FnOnce(&mut Ui) -> R) -> InnerResponse<R> {
+    let id = Id::new(("centered_row", id_salt));
+    let remembered = ui.data(|data| data.get_temp::<f32>(id));
+    ui.horizontal(|ui| {
+        ui.add_space(((ui.available_width() - remembered.unwrap_or(0.0)) / 2.0).max(0.0));
+        let start = ui.cursor().left();
+        let inner = add_contents(ui);
+        let width = ui.min_rect().right() - start;
+        // a row that changed width is drawn again straight away, so it doesn't sit off center for a frame
+        if remembered.is_none_or(|remembered| (remembered - width).abs() > 0.5) {
+            ui.data_mut(|data| data.insert_temp(id, width));
+            ui.ctx().request_repaint();
+        }
+        inner
+    })
+}
@@ -1,17 +1,128 @@
 
+// the board code indexes several 9x9 grids with the same row and col, which reads better than zipped iterators
+#![allow(clippy::needless_range_loop)]
+
+mod actions;
+#[cfg(feature = "dev-tools")]
+mod alloc_count;
+mod board;
+mod chart;
+mod clock;
+mod collection;
+mod data;
+mod feedback;
+mod fonts;
+mod generator;
+mod header;
+mod layout;
+mod marathon;
+mod notes;
+mod onboarding;
+mod palette;
+mod persist;
+mod puzzle_index;
+mod render;
+mod samurai;
+mod sandbox;
+mod score;
+mod savegame;
+mod settings;
+mod solver;
+mod source;
+mod statistics;
+mod stats;
+mod thumbnail;
+mod transform;
+mod tutorial;
+mod update;
+mod username;
+mod verify;
+mod warmup;
+mod window;
+
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use eframe::{NativeOptions, App, Frame};
-use eframe::egui::{self, Button, CentralPanel, Color32, Context, FontId, Grid, Key, RichText, Vec2, Rect, Pos2, Align2, FontFamily};
-use serde::Deserialize;
+use eframe::egui::{self, Theme, Button, CentralPanel, Color32, Context, FontId, Grid, Key, RichText, Vec2, Rect, Pos2, Align2, FontFamily};
+use serde::{Deserialize, Serialize};
 use rand::seq::SliceRandom;
+use std::sync::mpsc::{Receiver, TryRecvError};
 use std::time::{Duration, Instant};
+use actions::Action;
+use collection::Collection;
+use data::DataBundle;
+use feedback::{FeedbackCategory, FeedbackForm, FeedbackReport};
+use fonts::DigitStyle;
+use marathon::{Marathon, MarathonSetup, StrikeRule};
+use onboarding::Onboarding;
+use puzzle_index::LineIndex;
+use settings::{GameOptions, GameSetup, OptionOverrides, Settings, Validation};
+use stats::{DigitStats, GameRecord, LossRecord, LostPuzzle, MistakeRecord, Stats};
+use thumbnail::ThumbnailColors;
+use transform::Transform;
+use tutorial::TutorialStep;
+use render::{FrameCounter, RenderBudget, TimerGranularity};
+use samurai::{Samurai, SamuraiPuzzle, SavedSamurai};
+use sandbox::Sandbox;
+use savegame::SavedGame;
+use update::Release;
+use username::Username;
+use warmup::WarmUp;
+
+// the difficulties on the start screen's main row, in the order of their number keys (see difficulty_keys)
+const START_DIFFICULTIES: [&str; 3] = ["Beginner", "Intermediate", "Advanced"];
+
+// how long a given cell flashes red after the user tries to type into it
+const LOCKED_FLASH: Duration = Duration::from_millis(350);
+
+// how long a cell pulses after the selection jumps to it (see jump_to_conflict and jump_to_last_mistake)
+const JUMP_PULSE: Duration = Duration::from_millis(600);
+
+// how long a wrong entry stays on the board before it is erased, when the auto_erase setting is on
+const AUTO_ERASE: Duration = Duration::from_secs(1);
+
+// how long the window flashes red after a strike that is close to losing the game
+const STRIKE_FLASH: Duration = Duration::from_millis(500);
 
 // the Puzzle struct stores the unsolved puzzle as well as the solution as strings
     // the puzzle and solution variables are deserialized from the puzzle json files
-#[derive(Deserialize)]
+    // id is an optional stable identifier -- older files don't have one, so it falls back to a hash of the puzzle (see Puzzle::id)
+    // parity is an optional 81 character parity mask for the even/odd variant (see board::NO_PARITY)
+    // metadata is the optional author, source, and rating, which sit alongside the other fields in the json (see PuzzleMetadata)
+#[derive(Serialize, Deserialize)]
 struct Puzzle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
     puzzle: String,
     solution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parity: Option<String>,
+    #[serde(flatten)]
+    metadata: PuzzleMetadata,
+}
+
+// the PuzzleMetadata struct is the credits a puzzle file can give a puzzle -- who made it, where it is from, and how hard it was rated
+    // every field is optional, so puzzle files without them still load, and they are left out of files that are written
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct PuzzleMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<f32>,
+}
+
+impl PuzzleMetadata {
+    // the credit line shown in the game header, e.g. "Puzzle by Ana, rated 4.2" -- None if there is no author or rating
+    fn credit(&self) -> Option<String> {
+        match (&self.author, self.rating) {
+            (Some(author), Some(rating)) => Some(format!("Puzzle by {}, rated {:.1}", author, rating)),
+            (Some(author), None) => Some(format!("Puzzle by {}", author)),
+            (None, Some(rating)) => Some(format!("Rated {:.1}", rating)),
+            (None, None) => None,
+        }
+    }
 }
 
 // the Puzzles struct stores a vector of puzzles, which also needs deserialization
@@ -21,51 +132,275 @@ struct Puzzles {
     puzzles: Vec<Puzzle>,
 }
 
+// the ColorTag enum is a highlight color the user can put on a cell by hand (Ctrl + 1/2/3), e.g. for the coloring technique
+    // tags are only annotations -- they have nothing to do with the cell's value or whether it is correct
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ColorTag {
+    Yellow,
+    Green,
+    Purple,
+}
+
+impl ColorTag {
+    // the background tint for a tagged cell, with a darker version for dark mode
+    fn fill(self, dark_mode: bool) -> Color32 {
+        match (self, dark_mode) {
+            (ColorTag::Yellow, false) => Color32::from_rgb(255, 240, 150),
+            (ColorTag::Green, false) => Color32::from_rgb(190, 240, 190),
+            (ColorTag::Purple, false) => Color32::from_rgb(225, 200, 245),
+            (ColorTag::Yellow, true) => Color32::from_rgb(110, 100, 30),
+            (ColorTag::Green, true) => Color32::from_rgb(40, 95, 50),
+            (ColorTag::Purple, true) => Color32::from_rgb(85, 55, 110),
+        }
+    }
+}
+
+// the Edit enum is one entry in the undo history
+    // Cells is a list of (row, col, previous char), so a single undo can revert several cells at once (e.g. an Alt + digit sweep),
+    // Note is a note toggled by hand (Shift + digit), along with the cell's notes from before it,
+    // and Tags is every color tag on the board from before they were all cleared
+enum Edit {
+    Cells(Vec<(usize, usize, char)>),
+    Note(usize, usize, u16),
+    Tags([[Option<ColorTag>; 9]; 9]),
+}
+
 /*
     The Sudoku struct is the egui app itself
-    username and user_id are needed for sending the user's scores to our database
+    username and user_id are needed for sending the user's scores to our database -- the username is always valid (see Username)
     starting_grid stores the puzzle from the json file as an array of arrays (9x9 grid)
     player_grid also stores the puzzle from the json file, but the player_grid will be modified as the game is played, while starting_grid will not be
     solution_grid stores the solution from the json file
     difficulty is a string that can either be "Beginner", "Intermediate", "Advanced", or an empty string
-    strikes is an unsigned 8-bit integer that represents the number of incorrect guesses the user has made -- the game ends at three strikes
+    strikes is the number of incorrect guesses the user has made -- the game ends at the strike limit, or once a points game runs out of
+        points (which can take up to 1000 mistakes, so it is wider than a u8)
     time_elapsed and timer_start are used to update the clock while the game is running
     game_over is a bool that represents whether the game has ended or not
+    settings stores the user's preferences, which are loaded from and saved to settings.json
+    show_settings is a bool that represents whether the settings window is open or not
+    history stores the changes made to player_grid and the notes typed by hand, so they can be undone (see Edit)
+    stats stores the results of the user's games, which are loaded from and saved to stats.json
+    is_retry is a bool that represents whether the current game is a retry of a lost puzzle -- retries are recorded separately in stats
+    variant is a bool that represents whether the board is the puzzle rotated, reflected, or relabeled -- it is recorded under the puzzle's own id
+    pending_difficulty is a difficulty that has been picked, whose game options panel is being shown until the game is started
+    difficulty_cursor is the difficulty button picked with the arrow keys on the start screen (an index into START_DIFFICULTIES),
+        or None until an arrow key is pressed
+    generated_puzzle is a bool that represents whether the current puzzle was generated because its puzzle file couldn't be loaded
+    reveal_mistakes is a bool that, when set, highlights every wrong cell on the board (set from the "Not quite" banner)
+    toast is a short message shown at the bottom of the window, along with when it was shown so it can disappear after a few seconds
+    pb_pace_notified is a bool that makes sure the "passed your best time" toast is only shown once per game
+    session_best maps a difficulty to the fastest ranked, fresh win since the app was opened -- it isn't saved, so it starts over each launch
+    previous_bests are the session best and all-time best for the difficulty from just before the current win, so the win screen can
+        tell whether either was beaten
+    time_rank is how a ranked, fresh win's time compares to the earlier wins at the difficulty (see DifficultyStats::faster_than),
+        worked out before the win is recorded so the win isn't compared to itself
+    dark_mode is a bool that represents whether the app is currently using the dark theme
+    warm_up is the 4x4 warm-up puzzle shown before the real puzzle starts, or None if there isn't one
+    update_check receives the result of the background update check, and available_update is a newer release that was found (if any)
+    last_strike_recovery is when the last strike was made or given back, used by arcade mode to time error-free play
+    play_tick is when the last focused frame of a ranked game was drawn, so the wall-clock time between frames can be added
+        to today's play time -- it is None while the window isn't focused or no ranked game is being played
+    stats_unsaved and settings_unsaved are set when the stats or settings change in memory without being saved (the play time and
+        the window geometry), so they are only written on exit when there is something to write
+    mistakes is the log of wrong entries made in the current game, which is added to stats when the game ends and kept until the next game so it can be drilled
+    show_statistics is a bool that represents whether the statistics screen is open, and heatmap_by_digit picks which mistake heatmap it shows
+    actions is the log of every digit entered in the current game, and selected_at is when the selected cell was clicked
+    wrong_entries stores each wrong entry waiting to be erased by the auto_erase setting -- the digit and when it was entered,
+        so it is only erased if it is still the same digit AUTO_ERASE later
+    locked_flash is a given cell the user just tried to type into, and when -- it flashes red for LOCKED_FLASH so it's clear the cell is locked
+    jump_pulse is the cell the selection last jumped to with E or M, and when -- it pulses for JUMP_PULSE so the jump is easy to follow
+    strike_flash is when the last warning strike was made and how strong its flash is (0 to 1), so the window flashes red for STRIKE_FLASH
+    last_progress is when the user last entered a correct digit, and last_stuck_check is when the board was last checked for singles
+    stuck_nudge is the "you're stuck" message being shown, if any, and stuck_dismissed is set once the user dismisses it (until they make progress)
+    color_tags stores the highlight color the user has put on each cell, if any
+    last_digit_press is the cell the last digit was typed into and when, so a repeated press can be dropped (see digit_pressed)
+    pending_digit is the digit being previewed in a cell when the ghost_preview setting is on, waiting to be committed or cancelled
+    explained_conflict is the conflict (a digit repeated in a unit) the user clicked on, which is drawn and explained until it is fixed
+        or another cell is clicked
+    notes stores the notes (pencil marks) of each cell -- they are filled in by the auto_notes setting, or kept from a note hint
+    note_draft is the note for the current puzzle being written on the win screen, or None when the note isn't being edited
+    mistake_review is a bool that represents whether the list of the game's mistakes is open (opened by clicking the strikes)
+    note_hint is the note hint being shown, if any -- its marks are drawn over the board until they fade or are kept (see NoteHint)
+    hints_left is how many note hints the current game has left, starting from the difficulty's budget (see Stats::hint_budget),
+        and hints_used is how many it has shown, which is recorded with the game
+    checkpoint is a saved copy of player_grid the user can go back to, and checkpoint_strikes is the number of strikes when it was saved
+        checkpoint_mistakes and checkpoint_actions are how long the mistake and action logs were then, so going back drops what came after
+    options are the game options for the current game, resolved from the settings when the game starts
+    show_errors is a bool that represents whether wrong entries are colored red right now -- it starts from the game's
+        highlight_errors option and is flipped with H during play, and strikes are counted either way
+    notes_mode is a bool that represents whether plain digit keys turn notes on and off instead of entering digits -- it is
+        flipped with N or the checkbox under the board, and works the same as holding Shift (see digit_key_action)
+    setup is what was picked on the game options panel for the next game (see GameSetup)
+    puzzle_id is the stable id of the current puzzle (from the puzzle file, or a hash of the puzzle string),
+        and puzzle_metadata is its author, source, and rating, if the puzzle file gave any
+    parity is the parity mask of the current game (all '.' unless it is an even/odd game)
+    time_budget is the blitz countdown for the current game, or None if it isn't a blitz game
+    ranked is a bool that represents whether the current game is recorded in stats (retries and drills keep the ranking of the game they came from)
+    samurai is the samurai game being played, or None -- it has its own screen and doesn't use the 9x9 fields above
+    onboarding is the setup being shown (on the first launch, or from "Run setup again"), or None
+    tutorial_step is the tutorial callout being shown (see tutorial.rs), or None once the tutorial is finished or skipped
+    saved_game is a game that was saved when the app was closed, waiting to be resumed from the difficulty screen,
+        and saved_samurai is the same for a samurai game
+    confirm_quit is a bool that represents whether the "quit mid-game?" dialog is open, and allow_close is set once the user has
+        answered it, so the next close request goes through
+    render_budget is the repaint budget worked out from the performance settings, and frame_counter counts recent frames for the debug readout
+    mini_timer_closed is set when the user closes the mini timer, so it stays hidden until the window has been restored and minimized again
+    window_checked is set once the stored window geometry has been checked against the monitor (see window::clamp_to_monitor),
+        and track_window is whether the window's geometry is still being followed -- it is cleared when the layout is reset
+    feedback is the feedback form, or None if the feedback window is closed
+    data_path is the file used for exporting and importing the user's data, pending_import is an imported file waiting for the user to confirm it,
+        and data_message is the result of the last export or import
+    photo_path is the image file to import a puzzle from, and photo_message is why the last import failed, if it did
+    collections are the names of the saved puzzle collections (see collection.rs), collection_path is a collection file to load,
+        collection_name is the collection the current puzzle gets added to, and collection_message is the result of the last load or add
+    pick_difficulty and pick_index choose a specific puzzle from a puzzle file to play (see Puzzle::at), and pick_message is
+        why the last pick failed, if it did
+    marathon is the marathon being played (see marathon.rs), or None -- its puzzles are played as normal games, with the
+        marathon's screen shown between them instead of the win and lose screens
+    saved_marathon is a marathon that was stopped and saved, waiting to be resumed from the difficulty screen
+    marathon_setup is what is picked for the next marathon, and marathon_message is why the last one couldn't start, if it couldn't
+    confirm_abandon is a bool that represents whether the "stop the marathon?" dialog is open
+    sandbox is the real game while the user is playing a sandbox copy of its board (see sandbox.rs), or None outside of a sandbox --
+        in a sandbox, entries aren't strikes or logged, and the game can't be won or lost until the user goes back to the real game
 */
 struct Sudoku {
-    username: String,
+    #[allow(dead_code)] // not read until scores are sent to the database
+    username: Username,
+    #[allow(dead_code)]
     user_id: i32,
     starting_grid: [[char; 9]; 9],
     player_grid: [[char; 9]; 9],
     solution_grid: [[char; 9]; 9],
     selected: [usize; 2],
     difficulty: String,
-    strikes: u8,
+    strikes: u32,
     time_elapsed: Duration,
     timer_start: Option<Instant>,
     game_over: bool,
+    settings: Settings,
+    show_settings: bool,
+    history: Vec<Edit>,
+    stats: Stats,
+    is_retry: bool,
+    variant: bool,
+    pending_difficulty: Option<String>,
+    difficulty_cursor: Option<usize>,
+    generated_puzzle: bool,
+    reveal_mistakes: bool,
+    toast: Option<(String, Instant)>,
+    pb_pace_notified: bool,
+    session_best: HashMap<String, u64>,
+    previous_bests: (Option<u64>, Option<u64>),
+    time_rank: Option<u8>,
+    dark_mode: bool,
+    warm_up: Option<WarmUp>,
+    update_check: Option<Receiver<Release>>,
+    available_update: Option<Release>,
+    last_strike_recovery: Instant,
+    play_tick: Option<Instant>,
+    stats_unsaved: bool,
+    settings_unsaved: bool,
+    mistakes: Vec<MistakeRecord>,
+    show_statistics: bool,
+    heatmap_by_digit: bool,
+    actions: Vec<Action>,
+    selected_at: Instant,
+    wrong_entries: [[Option<(char, Instant)>; 9]; 9],
+    locked_flash: Option<((usize, usize), Instant)>,
+    strike_flash: Option<(Instant, f32)>,
+    jump_pulse: Option<((usize, usize), Instant)>,
+    last_progress: Instant,
+    last_stuck_check: Instant,
+    stuck_nudge: Option<&'static str>,
+    stuck_dismissed: bool,
+    color_tags: [[Option<ColorTag>; 9]; 9],
+    last_digit_press: Option<((usize, usize), Instant)>,
+    pending_digit: Option<(usize, usize, char)>,
+    explained_conflict: Option<board::Conflict>,
+    notes: notes::Notes,
+    note_hint: Option<notes::NoteHint>,
+    hints_left: u8,
+    hints_used: u32,
+    mistake_review: bool,
+    note_draft: Option<String>,
+    checkpoint: Option<[[char; 9]; 9]>,
+    checkpoint_strikes: u32,
+    checkpoint_mistakes: usize,
+    checkpoint_actions: usize,
+    options: GameOptions,
+    show_errors: bool,
+    notes_mode: bool,
+    setup: GameSetup,
+    puzzle_id: String,
+    puzzle_metadata: PuzzleMetadata,
+    parity: [[char; 9]; 9],
+    time_budget: Option<Duration>,
+    ranked: bool,
+    samurai: Option<Samurai>,
+    onboarding: Option<Onboarding>,
+    tutorial_step: Option<TutorialStep>,
+    saved_game: Option<SavedGame>,
+    saved_samurai: Option<SavedSamurai>,
+    confirm_quit: bool,
+    allow_close: bool,
+    render_budget: RenderBudget,
+    frame_counter: FrameCounter,
+    mini_timer_closed: bool,
+    window_checked: bool,
+    track_window: bool,
+    feedback: Option<FeedbackForm>,
+    data_path: String,
+    pending_import: Option<DataBundle>,
+    data_message: Option<String>,
+    photo_path: String,
+    photo_message: Option<String>,
+    collections: Vec<String>,
+    collection_path: String,
+    collection_name: String,
+    collection_message: Option<String>,
+    pick_difficulty: String,
+    pick_index: usize,
+    pick_message: Option<String>,
+    marathon: Option<Marathon>,
+    saved_marathon: Option<Marathon>,
+    marathon_setup: MarathonSetup,
+    marathon_message: Option<String>,
+    confirm_abandon: bool,
+    sandbox: Option<Sandbox>,
 }
 
 impl Puzzle {
-    // Puzzle constructor (takes one argument: difficulty)
-    fn new(difficulty: String) -> Self {
+    // Puzzle constructor (takes two arguments: difficulty, and the ids of the puzzles that have already been played)
+        // puzzles that haven't been played yet are picked first -- once every puzzle has been played, any of them can come up again,
+        // and the returned bool is set to say the puzzle is a repeat
+        // returns None if the puzzle file for the difficulty can't be read or parsed (e.g. the puzzles folder is missing)
+        // a line-delimited file isn't parsed as a whole -- see Puzzle::from_lines
+    fn new(difficulty: String, played: &HashSet<String>) -> Option<(Self, bool)> {
+        let file_path = Puzzles::path(&difficulty);
+        if puzzle_index::is_line_delimited(&file_path) {
+            return Self::from_lines(&file_path, played);
+        }
+
         // Initialize empty strings to store the puzzle and solution data from the json file
+        let mut id = None;
+        let mut parity = None;
+        let mut metadata = PuzzleMetadata::default();
         let mut puzzle = String::new();
         let mut solution = String::new();
 
-        // insert the difficulty string into the file path
-            // e.g. if difficulty is "Intermediate", the file_path will be "./puzzles/Intermediate.json"
-        let file_path = format!("./puzzles/{}.json", difficulty);
-        let file_contents = fs::read_to_string(file_path).unwrap(); // read the file into a string and store it as file_contents
+        let puzzles = Puzzles::load(&difficulty).ok()?;
 
-        // deserialize the string into a Puzzles struct -- note that this gets ALL of the puzzles in the singular json file
-        let puzzles: Puzzles = serde_json::from_str(&file_contents).expect("Failed to deserialize data");
-        
         // make a random number generator
         let mut rng = rand::thread_rng();
 
         // get the random puzzle/solution pair from the Puzzles struct using the rng
-        if let Some(random_puzzle) = puzzles.puzzles.choose(&mut rng) {
+        let fresh: Vec<&Puzzle> = puzzles.puzzles.iter().filter(|puzzle| !played.contains(&puzzle.id())).collect();
+        let repeat = fresh.is_empty();
+        let chosen = if repeat { puzzles.puzzles.choose(&mut rng) } else { fresh.choose(&mut rng).copied() };
+        if let Some(random_puzzle) = chosen {
+            id = random_puzzle.id.clone();
+            parity = random_puzzle.parity.clone();
+            metadata = random_puzzle.metadata.clone();
             puzzle = random_puzzle.puzzle.clone();
             solution = random_puzzle.solution.clone();
         }
@@ -74,31 +409,134 @@ impl Puzzle {
         }
 
         // return puzzle and solution
-        Self {
+        Some((Self {
+            id,
             puzzle,
             solution,
+            parity,
+            metadata,
+        }, repeat))
+    }
+
+    // picks a random puzzle from a line-delimited puzzle file, the same way Puzzle::new does, while only parsing the lines it looks at
+        // the lines are tried in a random order until one hasn't been played, so usually only one is parsed -- every line is only
+        // parsed once every puzzle in the file has been played
+    fn from_lines(file_path: &str, played: &HashSet<String>) -> Option<(Self, bool)> {
+        let mut index = LineIndex::open(file_path).ok()?;
+        let mut order: Vec<usize> = (0..index.count()).collect();
+        order.shuffle(&mut rand::thread_rng());
+        let mut repeat = None;
+        for line in order {
+            let puzzle = index.read(line).ok()?;
+            if !played.contains(&puzzle.id()) {
+                return Some((puzzle, false));
+            }
+            repeat.get_or_insert(puzzle);
+        }
+        repeat.map(|puzzle| (puzzle, true))
+    }
+
+    // gets the puzzle at index in the json file for the difficulty (counting from 0), instead of a random one
+        // this is for replaying a known board, e.g. to reproduce a report about a broken puzzle
+    fn at(difficulty: &str, index: usize) -> Result<Self, String> {
+        let file_path = Puzzles::path(difficulty);
+        if puzzle_index::is_line_delimited(&file_path) {
+            let mut lines = LineIndex::open(&file_path)?;
+            let count = lines.count();
+            if index >= count {
+                return Err(format!("{} only has {} puzzles (0 to {}), so there is no puzzle {}", difficulty, count, count.saturating_sub(1), index));
+            }
+            return lines.read(index);
         }
+        let puzzles = Puzzles::load(difficulty)?;
+        let count = puzzles.puzzles.len();
+        puzzles.puzzles.into_iter().nth(index)
+            .ok_or(format!("{} only has {} puzzles (0 to {}), so there is no puzzle {}", difficulty, count, count.saturating_sub(1), index))
+    }
+
+    // the puzzle's id from the json file, or a hash of the puzzle string if the file didn't give it one
+    fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| board::puzzle_hash(&self.puzzle))
+    }
+}
+
+impl Puzzles {
+    // the puzzle file for the difficulty -- e.g. if difficulty is "Intermediate", the file is "./puzzles/Intermediate.json"
+        // a line-delimited "./puzzles/Intermediate.jsonl" is used instead if there is one (see puzzle_index.rs)
+        // a saved collection is played like a difficulty, so its puzzles come from its own file (see collection.rs)
+    fn path(difficulty: &str) -> String {
+        Collection::file_for(difficulty).unwrap_or_else(|| {
+            let lines = format!("./puzzles/{}.{}", difficulty, puzzle_index::LINES_EXTENSION);
+            if std::path::Path::new(&lines).exists() { lines } else { format!("./puzzles/{}.json", difficulty) }
+        })
+    }
+
+    // reads and parses every puzzle in the puzzle file for the difficulty, in either format
+    fn load(difficulty: &str) -> Result<Self, String> {
+        let file_path = Self::path(difficulty);
+        let file_contents = fs::read_to_string(&file_path).map_err(|err| format!("Failed to read {}: {}", file_path, err))?;
+        if puzzle_index::is_line_delimited(&file_path) {
+            let puzzles = file_contents.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(index, line)| serde_json::from_str(line).map_err(|err| format!("{} is corrupt at puzzle {}: {}", file_path, index, err)))
+                .collect::<Result<_, _>>()?;
+            return Ok(Self { puzzles });
+        }
+        serde_json::from_str(&file_contents).map_err(|err| format!("{} is corrupt: {}", file_path, err))
     }
 }
 
 // This is the implementation of the egui app for the Sudoku struct (this is what makes the Sudoku struct into an app)
 impl App for Sudoku {
     // the update function runs every few milliseconds -- we can treat it like a while loop
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
         // if difficulty has not been set, show the difficulty screen to the user
             // the user can set the difficulty inside of the difficulty screen
-        if self.difficulty.is_empty() {
+        self.frame_counter.tick();
+        #[cfg(feature = "dev-tools")]
+        alloc_count::end_frame();
+        self.render_budget.apply_animations(ctx);
+
+        // the window's geometry is followed so it can be restored on the next launch, once the stored one has been checked
+        if !self.window_checked {
+            self.window_checked = self.settings.window.as_ref().is_none_or(|geometry| window::clamp_to_monitor(ctx, geometry));
+        }
+        else if self.track_window {
+            self.settings_unsaved |= window::observe(ctx, &mut self.settings.window);
+        }
+
+        if self.onboarding.is_some() {
+            self.onboarding_screen(ctx);
+        }
+        else if self.samurai.is_some() {
+            self.samurai_screen(ctx);
+            self.render_budget.schedule_timer_repaint(ctx, false);
+        }
+        else if self.difficulty.is_empty() && self.show_statistics {
+            self.statistics_screen(ctx);
+        }
+        else if self.difficulty.is_empty() {
             self.difficulty_screen(ctx);
         }
+        // if a warm-up puzzle is waiting, it is played (or skipped) before the real puzzle starts
+        else if self.warm_up.is_some() {
+            self.warm_up_screen(ctx);
+        }
         else {  // if difficulty has been set, start the game
-            // if 3 or more strikes, display the game over screen
-            if self.strikes >= 3 {
-                self.lose_screen(&ctx);
+            // if the user has reached the strike limit (3 by default) or run out of time in blitz mode, display the game over screen
+                // in a marathon, the marathon's screen is shown between puzzles instead
+                // a sandbox can't end the game -- running out of time in one throws it away, so the real game is the one that is lost
+            if self.sandbox.is_some() && self.out_of_time() {
+                self.leave_sandbox(false);
+            }
+            if self.sandbox.is_none() && (self.struck_out() || self.out_of_time()) {
+                if self.marathon.is_some() { self.marathon_screen(ctx, false) } else { self.lose_screen(ctx) }
             }
 
             // if the player's grid matches the solution grid exactly, display the win screen
-            else if self.player_grid == self.solution_grid {
-                self.win_screen(&ctx);
+            else if self.is_won() {
+                if self.marathon.is_some() { self.marathon_screen(ctx, true) } else { self.win_screen(ctx) }
             }
 
             // otherwise, the game is still running
@@ -107,20 +545,59 @@ impl App for Sudoku {
                 // if timer_start is None (uninitialized), it will be initialized
                 // if it is already initialized, time_elapsed will be incremented
                 let elapsed = match self.timer_start {
-                    Some(timer) => { 
-                        if let Some(time) = self.time_elapsed.checked_add(timer.elapsed()) {
-                            time
-                        }
-                        else {
-                            Duration::ZERO
-                        }
-                    }
+                    Some(timer) => clock::add_elapsed(self.time_elapsed, timer.elapsed()),
                     None => {
                         self.timer_start = Some(Instant::now());
+                        self.last_progress = Instant::now();
+                        if self.settings.auto_notes {
+                            self.notes = notes::fill(&self.player_grid);
+                        }
                         Duration::ZERO
                     }
                 };
 
+                self.erase_wrong_entries(ctx);
+
+                // once the user has gone a while without progress, check every few seconds whether a single is available
+                    // the check only uses the correct entries, so a wrong digit can't make up a deduction
+                let stuck_for = Duration::from_secs(self.settings.stuck_minutes * 60);
+                if self.settings.stuck_nudge && self.stuck_nudge.is_none() && !self.stuck_dismissed
+                    && self.last_progress.elapsed() >= stuck_for && self.last_stuck_check.elapsed() >= Duration::from_secs(5) {
+                    self.last_stuck_check = Instant::now();
+                    let mut correct = self.player_grid;
+                    for row in 0..9 {
+                        for col in 0..9 {
+                            if correct[row][col] != self.solution_grid[row][col] {
+                                correct[row][col] = '.';
+                            }
+                        }
+                    }
+                    self.stuck_nudge = board::find_single(&correct).map(|single| match single {
+                        board::Single::Naked => "Stuck? There's a cell with only one possible digit",
+                        board::Single::Hidden => "Stuck? There's a hidden single available",
+                    });
+                }
+
+                self.count_play_time(ctx.input(|input| input.focused));
+
+                // in arcade mode, every stretch of error-free play gives back one strike
+                let recovery_time = Duration::from_secs(self.settings.arcade_recovery_secs);
+                if self.options.arcade && self.strikes > 0 && self.last_strike_recovery.elapsed() >= recovery_time {
+                    self.strikes -= 1;
+                    self.last_strike_recovery = Instant::now();
+                }
+
+                // once per game, let the user know when they have passed their best time for this difficulty
+                if self.settings.show_pb_pace && !self.pb_pace_notified {
+                    let best = self.stats.difficulties.get(&self.difficulty).and_then(|stats| stats.best_time);
+                    if let Some(best) = best {
+                        if elapsed.as_secs() > best {
+                            self.show_toast(format!("Your PB pace was {} -- push!", format_time(best)));
+                            self.pb_pace_notified = true;
+                        }
+                    }
+                }
+
                 // selected_row is the row of the cell that the user currently has selected
                 // same is true for selected_col
                 let selected_row = self.selected[0];
@@ -134,6 +611,13 @@ impl App for Sudoku {
                     '.'
                 };
 
+                // how tense the header looks right now (see header.rs)
+                let header_style = header::header_style(&header::HeaderState {
+                    elapsed,
+                    time_left: self.time_budget.map(|budget| budget.saturating_sub(elapsed)),
+                    strikes_left: self.strikes_left(),
+                }, self.stats.difficulties.get(&self.difficulty));
+
                 // egui window
                 CentralPanel::default().show(ctx, |ui| {
                     // shows the selected difficulty and the time elapsed since the game started
@@ -141,144 +625,376 @@ impl App for Sudoku {
                         let header_text = RichText::new(self.difficulty.clone())
                             .font(FontId::new(30.0, FontFamily::Proportional));
                         ui.heading(header_text);
+                        // a marathon shows which puzzle this is, and the clock for the whole marathon so far
+                        if let Some(marathon) = &self.marathon {
+                            let total = marathon.total_secs() + elapsed.as_secs();
+                            ui.label(RichText::new(format!("Marathon: puzzle {} of {} -- {} in total", marathon.results.len() + 1, marathon.queue.len(), format_time(total)))
+                                .family(FontFamily::Monospace));
+                        }
+                        if self.sandbox.is_some() {
+                            let text = if self.player_grid == self.solution_grid { "Sandbox -- solved! Apply or discard it to finish the real game" } else { "Sandbox -- no strikes, nothing is recorded" };
+                            ui.label(RichText::new(text).strong().color(Color32::from_rgb(230, 150, 20)));
+                        }
+                        // a warning chip once one more mistake would end the game (unless the strikes are hidden)
+                        if header_style.strike_chip && self.settings.show_strikes {
+                            let plural = if self.strikes == 1 { "" } else { "s" };
+                            ui.label(RichText::new(format!(" {} strike{} -- one more ends the game ", self.strikes, plural))
+                                .small().strong().color(Color32::WHITE).background_color(Color32::from_rgb(255, 60, 110)));
+                        }
+                        // let the user know when the puzzle files were missing and a generated puzzle is being used
+                        if self.generated_puzzle {
+                            ui.label("Puzzle files not found -- using a generated puzzle");
+                        }
+                        unranked_badge(ui, self.ranked);
+                        // the puzzle's credits, with where it is from on hover
+                        let credit = self.puzzle_metadata.credit();
+                        let source = self.puzzle_metadata.source.as_ref();
+                        if let Some(text) = credit.clone().or_else(|| source.map(|source| format!("From {}", source))) {
+                            let label = ui.label(RichText::new(text).weak());
+                            if let (Some(_), Some(source)) = (credit, source) {
+                                label.on_hover_text(format!("Source: {}", source));
+                            }
+                        }
+                        if self.parity != board::NO_PARITY {
+                            ui.label("Even/odd -- shaded squares need an even digit, circles need an odd digit");
+                        }
+                        // a note the user attached to this puzzle the last time they played it
+                        if let Some(note) = self.stats.puzzle_notes.get(&self.puzzle_id) {
+                            ui.label(RichText::new(format!("Your note: {}", note)).italics());
+                        }
                         ui.add_space(30.0);
-                        ui.heading(format!("Time elapsed: {}", elapsed.as_secs().to_string()));
+                        // blitz mode counts down instead of up -- the clock turns amber once the game is behind the personal best,
+                            // and red for the last minute of a countdown
+                            // the clock is monospace, so the header doesn't shift sideways as the digits change
+                        let clock_text = match self.time_budget {
+                            Some(budget) => format!("Time left: {}", format_time(budget.saturating_sub(elapsed).as_secs())),
+                            None if self.render_budget.shows_tenths() => format!("Time elapsed: {}", clock::format_tenths(elapsed)),
+                            None => format!("Time elapsed: {}", elapsed.as_secs()),
+                        };
+                        let clock_color = header_style.timer.color().unwrap_or(ui.visuals().text_color());
+                        ui.heading(RichText::new(clock_text).family(FontFamily::Monospace).color(clock_color));
+                        layout::centered_row(ui, "game_buttons", |ui| {
+                            if ui.button("Settings").clicked() {
+                                self.show_settings = true;
+                            }
+                            if self.marathon.is_some() && ui.button("Stop marathon").clicked() {
+                                self.confirm_abandon = true;
+                            }
+                            // a sandbox is a copy of the board to try a guess on, which is thrown away (or applied) to get back to the real game
+                                // checkpoints belong to the real game, so they are put away while in a sandbox
+                            if self.sandbox.is_none() {
+                                if ui.button("Sandbox").on_hover_text("Try things out on a copy of the board, without strikes").clicked() {
+                                    self.enter_sandbox();
+                                }
+                                // a checkpoint saves the board so the user can try a line of reasoning and back out of it in one click
+                                if ui.button("Checkpoint").clicked() {
+                                    self.save_checkpoint();
+                                    self.show_toast("Checkpoint saved".into());
+                                }
+                                if self.checkpoint.is_some() && ui.button("Revert to checkpoint").clicked() {
+                                    self.revert_to_checkpoint();
+                                }
+                            }
+                            else {
+                                if ui.button("Discard sandbox").on_hover_text("Go back to the real game as it was").clicked() {
+                                    self.leave_sandbox(false);
+                                }
+                                if self.settings.sandbox_apply && ui.button("Apply sandbox").on_hover_text("Go back and play the new entries in the real game").clicked() {
+                                    self.leave_sandbox(true);
+                                }
+                            }
+                            // a note hint marks every cell the hardest-pressed digit could still go in, without filling any of them
+                            match &self.note_hint {
+                                Some(hint) => {
+                                    if ui.button(format!("Keep the {}s as notes", hint.digit)).clicked() {
+                                        hint.keep(&mut self.notes);
+                                        self.note_hint = None;
+                                    }
+                                }
+                                None => {
+                                    // each hint shown spends one from the game's budget
+                                    let button = ui.add_enabled(self.hints_left > 0, Button::new(format!("Note hint ({} left)", self.hints_left)));
+                                    if button.clicked() {
+                                        self.note_hint = notes::NoteHint::fewest_placements(&self.player_grid);
+                                        if self.note_hint.is_some() {
+                                            self.hints_left -= 1;
+                                            self.hints_used += 1;
+                                        }
+                                    }
+                                }
+                            }
+                            ui.checkbox(&mut self.notes_mode, "Notes mode (N)")
+                                .on_hover_text("Digits turn notes on and off instead of being entered -- holding Shift while typing a digit does the same");
+                            // only show the clear button once there is something to clear
+                            if self.color_tags.iter().flatten().any(|tag| tag.is_some())
+                                && ui.button("Clear color tags").on_hover_text("Ctrl + Backspace").clicked() {
+                                self.clear_color_tags();
+                            }
+                        });
                         ui.add_space(20.0);
-                        ui.horizontal(|ui| {
-                            ui.add_space(ui.available_width() / 2.0 - 75.0 - 10.0);
-                            for i in 1..=3 {
-                                let (rect_response, painter) = ui.allocate_painter(Vec2::new(50.0, 50.0), egui::Sense::hover()); 
-                                let rect = rect_response.rect;
+                        // the strike boxes can be hidden -- strikes still count, the user just doesn't see them until the game ends
+                            // a points game shows the points left instead of the boxes
+                            // clicking a filled box (or the points) opens the list of the game's mistakes
+                        let mut review_clicked = false;
+                        let mut strikes_rect = Rect::NOTHING;
+                        if let (Some(rule), true) = (self.options.points, self.settings.show_strikes) {
+                            let left = rule.remaining(self.strikes);
+                            let color = if left <= rule.per_mistake { Color32::from_rgb(255, 60, 110) } else { ui.visuals().text_color() };
+                            let points = ui.add(egui::Label::new(RichText::new(format!("{} / {} points", left, rule.start))
+                                .font(FontId::new(28.0, FontFamily::Proportional))
+                                .color(color)).sense(egui::Sense::click()));
+                            review_clicked = points.clicked() && self.strikes > 0;
+                            strikes_rect = points.rect;
+                        }
+                        else if self.settings.show_strikes {
+                            strikes_rect = ui.horizontal(|ui| {
+                                // center the strike boxes -- each box is 50 wide, with the default item spacing between them
+                                let limit = self.options.strike_limit;
+                                let width = limit as f32 * 50.0 + (limit as f32 - 1.0) * ui.spacing().item_spacing.x;
+                                ui.add_space(ui.available_width() / 2.0 - width / 2.0);
+                                for i in 1..=limit {
+                                    let (rect_response, painter) = ui.allocate_painter(Vec2::new(50.0, 50.0), egui::Sense::click()); 
+                                    let rect = rect_response.rect;
+                                    if i as u32 <= self.strikes {
+                                        review_clicked |= rect_response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked();
+                                    }
                                 
-                                // Draw the rectangle
-                                painter.rect_filled(rect, 0.0, Color32::WHITE);
-                                // Blue rectangle
-                                // Draw the text inside the rectangle
+                                    // Draw the rectangle
+                                    painter.rect_filled(rect, 0.0, Color32::WHITE);
+                                    // Blue rectangle
+                                    // Draw the text inside the rectangle
+
+                                    let text = if i as u32 <= self.strikes {
+                                        "X"
+                                    }
+                                    else {
+                                        ""
+                                    };
 
-                                let text = if i <= self.strikes {
-                                    "X"
+                                    painter.text(rect.center(), 
+                                        Align2::CENTER_CENTER,
+                                        text,
+                                        FontId::new(40.0, FontFamily::Proportional),
+                                        Color32::RED);
                                 }
-                                else {
-                                    ""
-                                };
-
-                                painter.text(rect.center(), 
-                                    Align2::CENTER_CENTER,
-                                    text,
-                                    FontId::new(40.0, FontFamily::Proportional),
-                                    Color32::RED);
+                            }).response.rect;
+                        }
+                        self.mistake_review(ui.ctx(), strikes_rect, review_clicked);
+                        self.tutorial_callout(ui.ctx(), TutorialStep::Strikes, strikes_rect);
+
+                        // arcade mode is labeled so it's clear this isn't a normal game, along with how long until the next strike comes back
+                        if self.options.arcade {
+                            let label = if self.strikes > 0 {
+                                let remaining = recovery_time.saturating_sub(self.last_strike_recovery.elapsed());
+                                let what = if self.options.points.is_some() { "points" } else { "strike" };
+                                format!("Arcade mode -- next {} back in {}s", what, remaining.as_secs() + 1)
                             }
-                        });
+                            else {
+                                format!("Arcade mode -- strikes come back after {}s without a mistake", recovery_time.as_secs())
+                            };
+                            ui.label(RichText::new(label).color(Color32::from_rgb(200, 120, 20)));
+                        }
+
+                        // the stuck nudge only says that a single exists, never where it is
+                        if let Some(nudge) = self.stuck_nudge {
+                            layout::centered_row(ui, "stuck_nudge", |ui| {
+                                ui.label(RichText::new(nudge).color(Color32::from_rgb(200, 120, 20)));
+                                if ui.small_button("Dismiss").clicked() {
+                                    self.stuck_nudge = None;
+                                    self.stuck_dismissed = true;
+                                }
+                            });
+                        }
+
+                        // a small indicator that stays up until the user's first strike
+                        if self.settings.show_clean_indicator && self.strikes == 0 {
+                            ui.label(RichText::new("Clean so far").color(Color32::from_rgb(60, 170, 90)));
+                        }
                     });
                     ui.add_space(20.0);
+                    // cell_rects stores where each cell was drawn this frame, so things can be painted around the grid after it is laid out
+                    let mut cell_rects = [[Rect::NOTHING; 9]; 9];
+                    // cell_gap is the space between cells inside a 3x3 box, and box_gap is the space between the boxes themselves
+                    let cell_gap = self.settings.cell_gap;
+                    let box_gap = self.settings.box_gap;
+                    // reserve a spot in the paint order for the background, so it is drawn behind the cells once the grid's size is known
+                    let background = ui.painter().add(egui::Shape::Noop);
                     ui.horizontal(|ui| {
                         // place the grid at the center of the window, then offset it to the left by half of its width
-                        // half of grid width -- 4.5 buttons, width of 80 per button = 360
-                        // we also have to include the spaces between buttons when calculating the offset
-                        // spaces -- 4 spaces, width of 5 per space = 20
-                        ui.add_space(ui.available_width() / 2.0 - 360.0 - 20.0);
-                        // this is the grid that holds the 9x9 grid of cells
-                        Grid::new("9x9_grid")
-                            .spacing([5.0, 5.0]) // Optional spacing between cells 
-                            .show(ui, |ui| {
-                                // iterate through each row and column
-                                for row in 0..9 {
+                        // the grid is 9 buttons with a width of 80, plus 6 gaps inside the boxes and 2 gaps between boxes
+                        let grid_width = 9.0 * 80.0 + 6.0 * cell_gap + 2.0 * box_gap;
+                        ui.add_space(ui.available_width() / 2.0 - grid_width / 2.0);
+                        // this holds the 9x9 grid of cells -- each row is laid out horizontally, and the rows are stacked vertically
+                            // an egui Grid only supports one spacing, so the rows and columns are laid out by hand to fit the wider gaps between boxes
+                        ui.vertical(|ui| {
+                            ui.spacing_mut().item_spacing = Vec2::new(cell_gap, cell_gap);
+                            // iterate through each row and column
+                            for row in 0..9 {
+                                // add the extra space between the 3x3 boxes (the item spacing already adds cell_gap)
+                                if row == 3 || row == 6 {
+                                    ui.add_space(box_gap - cell_gap);
+                                }
+                                ui.horizontal(|ui| {
                                     for col in 0..9 {
-                                        // get the number currenlty stored in the player grid at the current row and column
-                                        let num = self.player_grid[row][col];
-
-                                        // if the cell does not have a number
-                                        if num != '.' {
-                                            // create the text for the cell
-                                            let mut button_text = RichText::new(format!("{}", num.to_string()))
-                                                .font(FontId::new(34.0, FontFamily::Proportional));
-                                            
-                                            // if the number in the grid does not match the solution grid, make the text color Red
-                                            if self.solution_grid[row][col] != num {
-                                                button_text = button_text.color(Color32::from_rgb(255, 60, 110));
-                                            }
-                                            
-                                            // if the number in the grid does match the solution grid,
-                                                // and the starting grid is empty at the current row and colunn, make the text color Blue
-                                            else if self.starting_grid[row][col] == '.' {
-                                                button_text = button_text.color(Color32::from_rgb(0, 124, 255));
-                                            }
-
-                                            // create the button element
-                                            // first, highlight all cells in the grid that are the same as the selected number
-                                                // for example, if the user has selected a cell with 3 in it, all cells in the grid that contain 3 will be highlighted Blue
-                                            let button_element = if selected_row < 10
-                                                && selected_col < 10
-                                                && self.player_grid[row][col] == selected_num {
-                                                    Button::new(button_text)
-                                                        .min_size(Vec2::new(80.0, 80.0))
-                                                        .fill(Color32::from_rgb(200, 200, 255))
-                                            }
-                                            // next we make the checkerboard pattern
-                                                // for example, the top left, top right, bottom left, and bottom right 3x3 areas will have white cells,
-                                                // while the remaining cells will be gray
-                                            else if (row <= 2 && 3 <= col && col <= 5)
-                                                || (6 <= row && row <= 8 && 3 <= col && col <= 5)
-                                                || (3 <= row && row <= 5 && col <= 2)
-                                                || (3 <= row && row <= 5 && 6 <= col && col <= 8) {
-                                                    Button::new(button_text)
-                                                        .min_size(Vec2::new(80.0, 80.0))
-                                                        .fill(Color32::from_rgb(255, 255, 255))
-                                            }
-                                            else {
-                                                Button::new(button_text)
-                                                        .min_size(Vec2::new(80.0, 80.0))
-                                            };
-                                            
-                                            // add the button, and make a clone of it to check for clicks
-                                            let button = ui.add(button_element);
-                                            let button_clone = button.clone();
-
-                                            // highlight the entire row and the entire column that correspond to the cell the user has selected
-                                            if row == selected_row || col == selected_col{
-                                                button.highlight();
-                                            }
-                                            // if a button is clicked, set self.selected to the correct coordinates
-                                            if button_clone.clicked() {
-                                                self.selected[0] = row;
-                                                self.selected[1] = col;
-                                            }
+                                        if col == 3 || col == 6 {
+                                            ui.add_space(box_gap - cell_gap);
+                                        }
+
+                                        // create the button element with the text and background color for the cell
+                                        let mut button_element = Button::new(self.cell_text(row, col))
+                                            .min_size(Vec2::new(80.0, 80.0));
+                                        let fill = self.cell_fill(row, col, selected_num);
+                                        // a cell with a parity mark paints its own background (with the mark on it) underneath a see-through button,
+                                            // so the mark ends up under the digit
+                                        let parity = self.parity[row][col];
+                                        let background = (parity != '.').then(|| ui.painter().add(egui::Shape::Noop));
+                                        button_element = button_element.fill(if background.is_some() { Color32::TRANSPARENT } else { fill });
+
+                                        // add the button, and check it for clicks
+                                        let button = ui.add(button_element);
+                                        cell_rects[row][col] = button.rect;
+                                        if let Some(background) = background {
+                                            ui.painter().set(background, parity_marker(button.rect, parity, fill, self.dark_mode));
                                         }
-                                        // for all of the empty cells on the board
-                                            // again make the checkerboard pattern, dividing up each 3x3 area in the grid
-                                            // this time, the text in the button is just an empty string
-                                        else {
-                                            let button_element = if (row <= 2 && 3 <= col && col <= 5)
-                                                || (6 <= row && row <= 8 && 3 <= col && col <= 5)
-                                                || (3 <= row && row <= 5 && col <= 2)
-                                                || (3 <= row && row <= 5 && 6 <= col && col <= 8) {
-                                                    Button::new("")
-                                                        .min_size(Vec2::new(80.0, 80.0))
-                                                        .fill(Color32::from_rgb(255, 255, 255))
-                                            }
-                                            else {
-                                                Button::new("")
-                                                    .min_size(Vec2::new(80.0, 80.0))
-                                            };
-
-                                            // this code is identical to the code at the bottom of the last if block
-                                            let button = ui.add(button_element);
-                                            let button_clone = button.clone();
-
-                                            if row == selected_row || col == selected_col {
-                                                button.highlight();
-                                            }
-                                            if button_clone.clicked() {
-                                                self.selected[0] = row;
-                                                self.selected[1] = col;
-                                            }
+                                        let clicked = button.clicked();
+
+                                        // highlight the entire row and the entire column that correspond to the cell the user has selected
+                                        if row == selected_row || col == selected_col {
+                                            button.highlight();
+                                        }
+                                        // if a button is clicked, set self.selected to the correct coordinates
+                                        // clicking a cell that is part of a conflict explains it, and clicking any other cell clears the explanation
+                                        if clicked {
+                                            self.selected[0] = row;
+                                            self.selected[1] = col;
+                                            self.selected_at = Instant::now();
+                                            self.explained_conflict = board::conflicts(&self.player_grid)
+                                                .into_iter()
+                                                .find(|conflict| conflict.cells.contains(&(row, col)));
                                         }
                                     }
-                                    // after each row, call ui.end_row() to tell the grid that we want to start a new row
-                                    ui.end_row();
-                                }
+                                });
+                            }
                         });
                     });
+                    if self.settings.grid_background {
+                        let grid_rect = cell_rects[0][0].union(cell_rects[8][8]);
+                        ui.painter().set(background, grid_background(grid_rect.expand(16.0), self.dark_mode));
+                    }
+
+                    // a sandbox board gets an amber border and a watermark over the cells, so it can't be mistaken for the real game
+                    if self.sandbox.is_some() {
+                        let grid_rect = cell_rects[0][0].union(cell_rects[8][8]);
+                        let amber = Color32::from_rgb(230, 150, 20);
+                        ui.painter().rect_stroke(grid_rect.expand(10.0), 8.0, (4.0, amber));
+                        ui.painter().text(grid_rect.center(), Align2::CENTER_CENTER, "SANDBOX", FontId::proportional(120.0), amber.gamma_multiply(0.25));
+                    }
+
+                    // the note hint's marks are outlined digits in each cell's note spot for the digit, fading out over NOTE_HINT_TIME
+                        // marks the board has since ruled out are dropped, and the hint goes away once it has none left or has expired
+                    if let Some(hint) = &mut self.note_hint {
+                        if !hint.retain_valid(&self.player_grid) || hint.remaining() == 0.0 {
+                            self.note_hint = None;
+                        }
+                    }
+                    if let Some(hint) = &self.note_hint {
+                        let color = Color32::from_rgb(255, 150, 0).gamma_multiply(hint.remaining().sqrt());
+                        let index = actions::digit_index(hint.digit).unwrap_or(0);
+                        for &(row, col) in &hint.cells {
+                            let rect = cell_rects[row][col];
+                            let spot = rect.min + Vec2::new(((index % 3) as f32 + 0.5) * rect.width() / 3.0, ((index / 3) as f32 + 0.5) * rect.height() / 3.0);
+                            ui.painter().circle_stroke(spot, rect.width() / 8.0, (1.5, color));
+                            ui.painter().text(spot, Align2::CENTER_CENTER, hint.digit, FontId::monospace(rect.width() * 0.2), color);
+                        }
+                        ui.ctx().request_repaint();
+                    }
+
+                    // the tutorial points at the grid until a cell is selected, and then at the selected cell
+                    self.advance_tutorial();
+                    let grid_rect = cell_rects[0][0].union(cell_rects[8][8]);
+                    self.tutorial_callout(ui.ctx(), TutorialStep::SelectCell, grid_rect);
+                    if selected_row < 9 && selected_col < 9 {
+                        for step in [TutorialStep::EnterDigit, TutorialStep::Erase] {
+                            self.tutorial_callout(ui.ctx(), step, cell_rects[selected_row][selected_col]);
+                        }
+                    }
+
+                    // the digit chart sits to the right of the grid, as tall as the grid
+                    if self.settings.digit_chart {
+                        let grid_rect = cell_rects[0][0].union(cell_rects[8][8]);
+                        let chart_rect = Rect::from_min_size(grid_rect.right_top() + Vec2::new(30.0, 0.0), Vec2::new(120.0, grid_rect.height()));
+                        let counts = board::digit_counts(&self.player_grid, None);
+                        let correct = board::digit_counts(&self.player_grid, Some(&self.solution_grid));
+                        let complete = std::array::from_fn(|i| correct[i] == 9);
+                        chart::paint_digit_bars(ui.painter(), chart_rect, &counts, &complete, ui.visuals());
+                    }
+
+                    // a ring that grows and fades around the cell the selection just jumped to
+                    if let Some(((row, col), at)) = self.jump_pulse {
+                        let progress = at.elapsed().as_secs_f32() / JUMP_PULSE.as_secs_f32();
+                        if progress < 1.0 {
+                            let rect = cell_rects[row][col];
+                            let color = ui.visuals().selection.stroke.color.gamma_multiply(1.0 - progress);
+                            ui.painter().rect_stroke(rect.expand(4.0 + progress * 12.0), 6.0, (3.0, color));
+                            ui.ctx().request_repaint();
+                        }
+                        else {
+                            self.jump_pulse = None;
+                        }
+                    }
+
+                    // the crosshair runs through the center of the selected cell, from one edge of the grid to the other
+                        // it uses the rects the cells were actually drawn at, so it lines up whatever the cell size and gaps are
+                        // there is nothing to draw until a cell has been selected
+                    if self.settings.crosshair && selected_row < 9 && selected_col < 9 {
+                        let grid_rect = cell_rects[0][0].union(cell_rects[8][8]);
+                        let center = cell_rects[selected_row][selected_col].center();
+                        let color = if self.dark_mode { Color32::from_white_alpha(60) } else { Color32::from_black_alpha(50) };
+                        ui.painter().vline(center.x, grid_rect.y_range(), (2.0, color));
+                        ui.painter().hline(grid_rect.x_range(), center.y, (2.0, color));
+                    }
+
+                    // the explained conflict gets a line joining its two cells, drawn over the grid, and its explanation underneath
+                        // it goes away by itself once the conflict is fixed
+                    if self.explained_conflict.is_some_and(|conflict| !board::conflicts(&self.player_grid).contains(&conflict)) {
+                        self.explained_conflict = None;
+                    }
+                    if let Some(conflict) = self.explained_conflict {
+                        let [(row_a, col_a), (row_b, col_b)] = conflict.cells;
+                        let (a, b) = (cell_rects[row_a][col_a].center(), cell_rects[row_b][col_b].center());
+                        let color = Color32::from_rgb(255, 60, 110);
+                        ui.painter().line_segment([a, b], (3.0, color));
+                        ui.painter().circle_stroke(a, 30.0, (2.0, color));
+                        ui.painter().circle_stroke(b, 30.0, (2.0, color));
+                        ui.add_space(10.0);
+                        ui.vertical_centered(|ui| {
+                            ui.label(RichText::new(conflict.explanation()).color(color));
+                        });
+                    }
+
+                    // if every cell is filled but the board doesn't match the solution, tell the user how many cells are wrong
+                        // rather than leaving them staring at a full board
+                    if let Some(wrong) = self.wrong_cells_if_full() {
+                        ui.add_space(10.0);
+                        ui.vertical_centered(|ui| {
+                            let plural = if wrong == 1 { "" } else { "s" };
+                            ui.heading(RichText::new(format!("Not quite -- {} cell{} wrong", wrong, plural))
+                                .color(Color32::from_rgb(255, 60, 110)));
+                            if !self.reveal_mistakes && ui.button("Highlight mistakes").clicked() {
+                                self.reveal_mistakes = true;
+                            }
+                        });
+                    }
+
+                    // if enabled, show how many empty cells are left in each row and column
+                    if self.options.show_unit_counters {
+                        self.draw_unit_counters(ui, &cell_rects);
+                    }
+
+                    // read the selection again now that the grid has handled this frame's clicks
+                        // otherwise clicking a cell and typing a digit in the same frame would write to the previously selected cell
+                    let selected_row = self.selected[0];
+                    let selected_col = self.selected[1];
 
                     // define key presses that are allowed -- the only ones allowed are digits 1-9
                     // NOTE: below, we also allow for the user to press the backspace key, but we do not need to include it in this array
@@ -288,42 +1004,179 @@ impl App for Sudoku {
                         Key::Num7, Key::Num8, Key::Num9,
                     ];
 
+                    // what a digit key does depends on the modifiers held and the notes mode (see digit_key_action)
+                    let modifiers = ui.input(|input| input.modifiers);
+
+                    // a key typed into a text field (e.g. a number box in the settings window) belongs to that field, not the board
+                    let keys_taken = ctx.wants_keyboard_input();
+
                     // iterate through the valid keys (digits) to check if any were pressed during the last frame
                     for &key in &valid_keys {
-                        // if a number key was pressed and the selected_row and selected_col are in range
-                        // and the starting grid at that position is empty,
-                            // we get the digit associated with that key press and store it in the player grid
-                        if ui.input(|input| input.key_pressed(key))
-                            && selected_row != 10
-                            && selected_col != 10
-                            && self.starting_grid[selected_row][selected_col] == '.' {
-                                let num = key.name();
-                                self.player_grid[selected_row][selected_col] = num.chars().next().unwrap();
-
-                                // if the number entered is incorrect, increment the user's strikes by 1
-                                if self.solution_grid[selected_row][selected_col] != self.player_grid[selected_row][selected_col] {
-                                    self.strikes += 1;
+                        if keys_taken || !ui.input(|input| input.key_pressed(key)) {
+                            continue;
+                        }
+                        let digit = key.name().chars().next().unwrap();
+                        let selected = selected_row != 10 && selected_col != 10;
+
+                        match digit_key_action(modifiers, self.notes_mode, digit) {
+                            // if the sweep is enabled, Alt + digit places that digit in all of its forced positions
+                            DigitAction::Sweep => {
+                                if self.settings.sweep_forced_digits {
+                                    self.sweep_forced_digit(digit);
+                                }
+                            }
+                            // Ctrl + 1/2/3 toggles a color tag on the selected cell -- pressing the same one again removes it
+                            DigitAction::Tag(tag) => {
+                                if let (Some(tag), true) = (tag, selected) {
+                                    let cell = &mut self.color_tags[selected_row][selected_col];
+                                    *cell = if *cell == Some(tag) { None } else { Some(tag) };
+                                }
+                            }
+                            DigitAction::Note => {
+                                if selected {
+                                    self.toggle_note(selected_row, selected_col, digit);
+                                }
+                            }
+                            // if the selected cell is in range and the starting grid at that position is empty,
+                                // we store the digit in the player grid
+                            DigitAction::Enter if selected && self.starting_grid[selected_row][selected_col] == '.' => {
+                                self.digit_pressed(selected_row, selected_col, digit, elapsed);
+                            }
+                            // typing into a given doesn't change anything, so flash the cell to show it is locked
+                            DigitAction::Enter => {
+                                if selected {
+                                    self.locked_flash = Some(((selected_row, selected_col), Instant::now()));
+                                    ctx.request_repaint_after(LOCKED_FLASH);
                                 }
+                            }
+                        }
+                    }
+
+                    // a previewed digit is committed with Enter and thrown away with Escape, or by selecting a different cell
+                        // with no digit being previewed, Enter moves on to the next empty cell (when enter_advances is on)
+                    let enter = !keys_taken && ui.input(|input| input.key_pressed(Key::Enter));
+                    if let Some((row, col, digit)) = self.pending_digit {
+                        if (row, col) != (self.selected[0], self.selected[1]) || ui.input(|input| input.key_pressed(Key::Escape)) {
+                            self.pending_digit = None;
                         }
+                        else if enter {
+                            self.place_digit(row, col, digit, elapsed);
+                        }
+                    }
+                    else if enter && self.settings.enter_advances && selected_row != 10 && selected_col != 10 {
+                        self.advance_to_empty(selected_row, selected_col);
                     }
 
                     // if the backspace key was pressed during the last frame, reset the player grid at that position to be empty
-                    if ui.input(|input| input.key_pressed(Key::Backspace)) {
-                        self.player_grid[selected_row][selected_col] = '.';
+                        // givens can't be erased, and nothing happens if no cell is selected yet
+                        // with Ctrl (Cmd on mac) held, backspace clears the color tags instead (see below)
+                    if !keys_taken && ui.input(|input| input.key_pressed(Key::Backspace) && !input.modifiers.command)
+                        && selected_row != 10
+                        && selected_col != 10
+                        && self.starting_grid[selected_row][selected_col] == '.' {
+                            let change = (selected_row, selected_col, self.player_grid[selected_row][selected_col]);
+                            self.history.push(Edit::Cells(vec![change]));
+                            self.player_grid[selected_row][selected_col] = '.';
+                            self.update_auto_notes(&[change]);
+                    }
+
+                    // E jumps to the next cell that is part of a conflict, and M jumps to the cell of the most recent strike
+                    if !keys_taken && ui.input(|input| input.key_pressed(Key::E) && !input.modifiers.any()) {
+                        self.jump_to_conflict();
+                    }
+                    if !keys_taken && ui.input(|input| input.key_pressed(Key::M) && !input.modifiers.any()) {
+                        self.jump_to_last_mistake();
+                    }
+
+                    // H hides or shows the red coloring of wrong entries, so the user can check their work now and then
+                    if !keys_taken && ui.input(|input| input.key_pressed(Key::H) && !input.modifiers.any()) {
+                        self.show_errors = !self.show_errors;
+                        self.show_toast(if self.show_errors { "Showing wrong entries (H to hide)" } else { "Hiding wrong entries (H to show)" }.into());
+                    }
+
+                    // N turns notes mode on or off
+                    if !keys_taken && ui.input(|input| input.key_pressed(Key::N) && !input.modifiers.any()) {
+                        self.notes_mode = !self.notes_mode;
+                        self.show_toast(if self.notes_mode { "Notes mode on (N to turn off)" } else { "Notes mode off (N to turn on)" }.into());
+                    }
+
+                    // Ctrl+Backspace clears every color tag on the board -- not Ctrl+0, which is eframe's shortcut for resetting the zoom
+                    if !keys_taken && ui.input(|input| input.modifiers.command && input.key_pressed(Key::Backspace)) {
+                        self.clear_color_tags();
+                    }
+
+                    // Ctrl+Z (or Cmd+Z on mac) undoes the last change to the board
+                    if !keys_taken && ui.input(|input| input.modifiers.command && input.key_pressed(Key::Z)) {
+                        self.undo();
                     }
                 });
             }
             // by default, egui only updates the window when there is user input like mouse movement or keyboard presses.
-            // request repaint solves this by updating the window each frame
-            ctx.request_repaint();
+            // the render budget schedules a repaint in time for the clock's next tick (unless the performance settings turned that off),
+                // and keeps repainting while something runs on the clock either way
+            self.render_budget.schedule_timer_repaint(ctx, self.runs_on_clock());
         }
+
+        // the settings window and toasts can appear on any screen, so they are shown after the current screen
+        self.settings_window(ctx);
+        self.feedback_window(ctx);
+        self.quit_dialog(ctx);
+        self.abandon_dialog(ctx);
+        self.draw_strike_flash(ctx);
+        self.draw_toast(ctx);
+        self.update_notice(ctx);
+        self.mini_timer(ctx);
+        self.fps_overlay(ctx, frame.info().cpu_usage);
+    }
+
+    // runs once when the app closes, however it was closed -- everything that is only held in memory is written out here, in order:
+        // stats first (today's play time is only added to them in memory until a game ends), then settings (unless setup is open),
+        // then the game in progress, which is saved so it can be resumed -- unless the quit dialog already handled it
+        // ("Save and quit" saved it, and "Quit and lose progress" means the user doesn't want it)
+        // stats and settings are only written if they changed since they were saved, so a session that changed nothing leaves
+        // their files (and backups) exactly as they were
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let written = self.flush(std::path::Path::new("."));
+        println!("Saved on exit: {}", written.join(", "));
     }
 }
 
 // functions for Sudoku struct
 impl Sudoku {
-    // Sudoku constructor -- takes username and user_id -- all other member variables are initialized to a default value 
+    // Sudoku constructor -- takes username and user_id, and reads the settings, stats, and saved games from the working directory
     fn new(username: String, user_id: i32) -> Self {
+        // the setup runs on the first launch, which is when there isn't a settings file yet
+        let first_launch = !std::path::Path::new(settings::SETTINGS_PATH).exists();
+        let mut app = Self::with_data(username, user_id, Settings::load(), Stats::load(), first_launch);
+        app.saved_game = SavedGame::load();
+        app.saved_samurai = SavedSamurai::load();
+        app.saved_marathon = Marathon::load();
+        app
+    }
+
+    // builds the app from settings and stats that were already read, with no saved games -- all other member variables are
+        // initialized to a default value, and nothing is read from or written to the working directory (so tests can use it too)
+        // a saved name that isn't valid (e.g. from before names were checked) is cleaned up instead of stopping the app, with a toast
+        // saying so, and the cleaned up name is saved on exit
+    fn with_data(username: String, user_id: i32, mut settings: Settings, stats: Stats, first_launch: bool) -> Self {
+        let onboarding = first_launch.then(|| Onboarding::new(&username));
+        // the tutorial is shown on the first launch too, and keeps coming back until it is finished or turned off
+        if onboarding.is_some() {
+            settings.show_tutorial = true;
+        }
+        let tutorial_step = settings.show_tutorial.then_some(TutorialStep::Difficulty);
+        let saved_name = settings.username.clone().unwrap_or(username);
+        let username = Username::sanitize(&saved_name);
+        let mut toast = None;
+        if Username::new(&saved_name).is_err() {
+            toast = Some((format!("Your saved name couldn't be used as it was, so it was changed to \"{}\"", username.as_str()), Instant::now()));
+        }
+        let settings_unsaved = toast.is_some() && settings.username.is_some();
+        if settings_unsaved {
+            settings.username = Some(username.as_str().to_string());
+        }
+        let options = settings::resolve_options(&settings, "", &OptionOverrides::default());
+        let render_budget = RenderBudget::from_settings(&settings);
         Self {
             username,
             user_id,
@@ -336,6 +1189,96 @@ impl Sudoku {
             time_elapsed: Duration::from_secs(0),
             timer_start: None,
             game_over: false,
+            settings,
+            show_settings: false,
+            history: Vec::new(),
+            stats,
+            is_retry: false,
+            variant: false,
+            pending_difficulty: None,
+            difficulty_cursor: None,
+            generated_puzzle: false,
+            reveal_mistakes: false,
+            toast,
+            pb_pace_notified: false,
+            session_best: HashMap::new(),
+            previous_bests: (None, None),
+            time_rank: None,
+            dark_mode: false,
+            warm_up: None,
+            update_check: None,
+            available_update: None,
+            last_strike_recovery: Instant::now(),
+            play_tick: None,
+            stats_unsaved: false,
+            settings_unsaved,
+            mistakes: Vec::new(),
+            show_statistics: false,
+            heatmap_by_digit: false,
+            actions: Vec::new(),
+            selected_at: Instant::now(),
+            wrong_entries: [[None; 9]; 9],
+            locked_flash: None,
+            strike_flash: None,
+            jump_pulse: None,
+            last_progress: Instant::now(),
+            last_stuck_check: Instant::now(),
+            stuck_nudge: None,
+            stuck_dismissed: false,
+            color_tags: [[None; 9]; 9],
+            last_digit_press: None,
+            pending_digit: None,
+            explained_conflict: None,
+            notes: [[0; 9]; 9],
+            note_hint: None,
+            hints_left: stats::DEFAULT_HINT_BUDGET,
+            hints_used: 0,
+            mistake_review: false,
+            note_draft: None,
+            checkpoint: None,
+            checkpoint_strikes: 0,
+            checkpoint_mistakes: 0,
+            checkpoint_actions: 0,
+            show_errors: options.highlight_errors,
+            notes_mode: false,
+            options,
+            setup: GameSetup::default(),
+            puzzle_id: String::new(),
+            puzzle_metadata: PuzzleMetadata::default(),
+            parity: board::NO_PARITY,
+            time_budget: None,
+            ranked: true,
+            samurai: None,
+            onboarding,
+            tutorial_step,
+            saved_game: None,
+            saved_samurai: None,
+            confirm_quit: false,
+            allow_close: false,
+            render_budget,
+            frame_counter: FrameCounter::default(),
+            mini_timer_closed: false,
+            window_checked: false,
+            track_window: true,
+            feedback: None,
+            data_path: "./sudoku_data.json".into(),
+            pending_import: None,
+            data_message: None,
+            photo_path: String::new(),
+            photo_message: None,
+            collections: Collection::names(),
+            collection_path: String::new(),
+            collection_name: String::new(),
+            collection_message: None,
+            pick_difficulty: START_DIFFICULTIES[0].to_string(),
+            pick_index: 0,
+            pick_message: None,
+            marathon: None,
+            saved_marathon: None,
+            marathon_setup: MarathonSetup::default(),
+            marathon_message: None,
+            confirm_abandon: false,
+            sandbox: None,
         }
     }
 
@@ -343,7 +1286,58 @@ impl Sudoku {
     fn get_puzzle(&mut self) {
         // when Puzzle::new is called, we fetch a random puzzle from the json file associated with the current difficulty
         // NOTE: self.difficulty will always be populated to either "Beginner", "Intermediate", or "Advanced" when this function is called
-        let puzzle = Puzzle::new(self.difficulty.clone());
+        // if the json file can't be loaded, a puzzle is generated instead so the game still works without the puzzles folder
+        // a repeat is transformed when transform_repeats is on, so it doesn't look exactly like the last time it was played
+        let mut repeat = false;
+        let puzzle = match Puzzle::new(self.difficulty.clone(), &self.stats.played_puzzles) {
+            Some((puzzle, was_played)) => {
+                self.generated_puzzle = false;
+                repeat = was_played;
+                puzzle
+            }
+            None => {
+                let (puzzle, solution, parity) = if self.setup.even_odd {
+                    let (puzzle, solution, mask) = generator::generate_even_odd(&self.difficulty);
+                    (puzzle, solution, Some(board::grid_to_string(&mask)))
+                }
+                else {
+                    let (puzzle, solution) = generator::generate(&self.difficulty);
+                    (puzzle, solution, None)
+                };
+                self.generated_puzzle = true;
+                Puzzle {
+                    id: None,
+                    puzzle: board::grid_to_string(&puzzle),
+                    solution: board::grid_to_string(&solution),
+                    parity,
+                    metadata: PuzzleMetadata::default(),
+                }
+            }
+        };
+        self.start_puzzle(puzzle, self.settings.transform_puzzles || (repeat && self.settings.transform_repeats));
+    }
+
+    // loads the puzzle picked with pick_difficulty and pick_index and starts it, or leaves a message saying why it couldn't
+        // the board is shown exactly as it is in the file (no transform), and the game is unranked since the user chose the puzzle
+    fn pick_puzzle(&mut self) {
+        match Puzzle::at(&self.pick_difficulty, self.pick_index) {
+            Ok(puzzle) => {
+                self.pick_message = None;
+                self.difficulty = self.pick_difficulty.clone();
+                self.generated_puzzle = false;
+                self.start_puzzle(puzzle, false);
+                self.ranked = false;
+            }
+            Err(err) => self.pick_message = Some(err),
+        }
+    }
+
+    // stores a puzzle in the Sudoku struct's member variables and works out the options for the game
+        // if transform is set, a random transform is applied so the board looks different
+    fn start_puzzle(&mut self, puzzle: Puzzle, transform: bool) {
+        // remember which puzzle this is, so it can be recognized later (e.g. when it is retried), and who to credit for it
+        self.puzzle_id = puzzle.id();
+        self.puzzle_metadata = puzzle.metadata.clone();
 
         // Convert the puzzle string to a vector of chars
         // Do the same for the solution string
@@ -363,107 +1357,1509 @@ impl Sudoku {
                     // store the character from the puzzle string in self.starting_grid as well as self.player_grid
                     self.starting_grid[row][col] = *puzzle_char;
                     self.player_grid[row][col] = *puzzle_char;
-                } else { }
+                }
 
                 // get the char at the specified index in the solution char vector
                 if let Some(solution_char) = solution_char_vec.get(index) {
                     // store the character from the solution string in self.solution_grid
                     self.solution_grid[row][col] = *solution_char;
-                } else { }
+                }
             }
         }
-    }
 
-    // displays the start screen where the user selects the difficulty
-    fn difficulty_screen(&mut self, ctx: &Context) {
-        CentralPanel::default().show(&ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.add_space(400.0);
-                // Sudoku title
-                let title_text = RichText::new("Sudoku")
-                    .font(FontId::new(30.0, FontFamily::Proportional))
-                    .color(Color32::from_rgb(60, 190, 220));
-                ui.heading(title_text);
+        // work out the options for this game
+        self.resolve_game_options();
 
-                // Beginner, Intermediate, and Advanced butttons
-                ui.add_space(-300.0);
-                ui.horizontal_centered(|ui| {
-                    ui.add_space(ui.available_width() / 2.0 - 230.0 - 30.0);
-                    let beginner_button_text = RichText::new("Beginner")
-                        .font(FontId::new(24.0, FontFamily::Proportional));
-                    let intermediate_button_text = RichText::new("Intermediate")
-                        .font(FontId::new(24.0, FontFamily::Proportional));
-                    let advanced_button_text = RichText::new("Advanced")
-                        .font(FontId::new(24.0, FontFamily::Proportional));
-
-                    if ui.add(Button::new(beginner_button_text).min_size(Vec2::new(150.0, 100.0))).clicked() {
-                        self.difficulty = "Beginner".to_string();
-                    };
-                    ui.add_space(30.0);
-                    if ui.add(Button::new(intermediate_button_text).min_size(Vec2::new(150.0, 100.0))).clicked() {
-                        self.difficulty = "Intermediate".to_string();
-                    };
-                    ui.add_space(30.0);
-                    if ui.add(Button::new(advanced_button_text).min_size(Vec2::new(150.0, 100.0))).clicked() {
-                        self.difficulty = "Advanced".to_string();
-                    };
-                });
-                // THIS SHOULD NOT BE INCLUDED IN FINAL SUBMISSION -- THIS IS FOR TESTING WIN SCREEN
-                ui.add_space(-350.0);
-                let test_button_text = RichText::new("Test")
-                    .font(FontId::new(24.0, FontFamily::Proportional));
-                if ui.add(Button::new(test_button_text).min_size(Vec2::new(150.0, 100.0))).clicked() {
-                    self.difficulty = "Test".to_string();
-                };
-            });
+        // in the even/odd variant, use the puzzle's parity marks, or mark some cells from the solution if the puzzle doesn't have any
+        let mut rng = rand::thread_rng();
+        self.parity = board::NO_PARITY;
+        if self.setup.even_odd {
+            self.parity = match &puzzle.parity {
+                Some(mask) => board::string_to_grid(mask),
+                None => generator::parity_mask(&self.starting_grid, &self.solution_grid, generator::PARITY_MARKS, &mut rng),
+            };
+        }
 
-            // if the difficulty is not an empty string, call self.get_puzzle to randomly get a puzzle
-            if self.difficulty != "" {
-                self.get_puzzle();
+        // if enabled, apply the same random transform to the starting grid and the solution grid
+            // applying it to both keeps the solution consistent with the puzzle, so the board just looks different
+        self.variant = transform;
+        if transform {
+            let mut transform = Transform::random(&mut rng);
+            // relabeling changes which digits are even, so it would break the parity marks
+            while self.setup.even_odd && matches!(transform, Transform::Relabel(_)) {
+                transform = Transform::random(&mut rng);
             }
-        });
+            self.starting_grid = transform.apply(&self.starting_grid);
+            self.player_grid = self.starting_grid;
+            self.solution_grid = transform.apply(&self.solution_grid);
+            self.parity = transform.apply(&self.parity);
+        }
     }
 
-    // displays the game over screen when the user loses
-    fn lose_screen(&mut self, ctx: &Context) {
-        // iterate through self.player_grid and self.solution_grid, and count how many of the 81 cells the user had correct
-        let mut count= 0.0;
-        for row in 0..9 {
-            for col in 0..9 {
-                if self.player_grid[row][col] == self.solution_grid[row][col] {
-                    count += 1.0;
-                }
+    // places digit in every cell where it is forced (the only legal spot for it in a row, column, or box)
+        // only placements that also match the solution are made, so a wrong entry elsewhere can't cause a bad sweep
+        // all of the placements are stored as one history entry, so a single undo reverts the whole sweep
+    fn sweep_forced_digit(&mut self, digit: char) {
+        let mut changes = Vec::new();
+        for (row, col) in board::forced_positions(&self.player_grid, digit) {
+            if self.solution_grid[row][col] == digit {
+                changes.push((row, col, self.player_grid[row][col]));
+                self.player_grid[row][col] = digit;
             }
         }
-        // calculate the percentage of cells the user had correct
-        let percentage: f32 = (count / 81.0) * 100.0;
-        // convert percentage to i32
-        let rounded = percentage.round() as i32;
-
-        // display ui elements, including the percentage of the board the user had correct
-        CentralPanel::default().show(&ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.heading("Game over!");
-                ui.label(format!("You filled {} percent of the board", rounded));
-
-                // create the button text
-                let button_text = RichText::new("Play Again")
-                    .font(FontId::new(30.0, FontFamily::Proportional));
+        self.update_auto_notes(&changes);
+        if !changes.is_empty() {
+            self.history.push(Edit::Cells(changes));
+        }
+    }
 
-                // create the button element
-                let button_element = Button::new(button_text)
-                            .min_size(Vec2::new(50.0, 20.0));
-                
-                // add the button
-                ui.add_space(40.0);
-                let button = ui.add(button_element);
+    // moves the selection to the next empty cell after (row, col) in reading order, wrapping around to the top of the board
+        // nothing happens when there are no other empty cells
+    fn advance_to_empty(&mut self, row: usize, col: usize) {
+        let start = row * 9 + col;
+        let next = (1..81)
+            .map(|offset| (start + offset) % 81)
+            .find(|&index| self.player_grid[index / 9][index % 9] == '.');
+        if let Some(index) = next {
+            self.selected = [index / 9, index % 9];
+            self.selected_at = Instant::now();
+        }
+    }
 
-                // if a button is clicked, reset the gamestate
-                if button.clicked() {
-                    self.reset_gamestate();
-                }
-            });
-        });
+    // moves the selection to a cell and starts its pulse, so the user can see where it went
+    fn jump_to(&mut self, row: usize, col: usize) {
+        self.selected = [row, col];
+        self.selected_at = Instant::now();
+        self.jump_pulse = Some(((row, col), Instant::now()));
+    }
+
+    // moves the selection to the next cell that is part of a conflict, in reading order (row by row) after the selected cell
+        // pressing it again cycles through the rest, wrapping around after the last one
+    fn jump_to_conflict(&mut self) {
+        let mut cells: Vec<(usize, usize)> = board::conflicts(&self.player_grid).into_iter().flat_map(|conflict| conflict.cells).collect();
+        cells.sort();
+        cells.dedup();
+        let current = (self.selected[0], self.selected[1]);
+        // with no cell selected the selection is (10, 10), so this starts from the first conflict
+        let next = cells.iter().find(|&&cell| cell > current).or(cells.first()).copied();
+        match next {
+            Some((row, col)) => self.jump_to(row, col),
+            None => self.show_toast("No conflicts on the board".into()),
+        }
+    }
+
+    // moves the selection to the cell of the most recent wrong entry
+    fn jump_to_last_mistake(&mut self) {
+        match self.mistakes.last() {
+            Some(&MistakeRecord { row, col, .. }) => self.jump_to(row, col),
+            None => self.show_toast("No mistakes yet".into()),
+        }
+    }
+
+    // reverts the most recent history entry, restoring every cell (or the notes) it changed
+        // strikes are not given back -- undo only changes the board
+    fn undo(&mut self) {
+        match self.history.pop() {
+            Some(Edit::Cells(changes)) => {
+                let mut undone = Vec::new();
+                for (row, col, previous) in changes.into_iter().rev() {
+                    undone.push((row, col, self.player_grid[row][col]));
+                    self.player_grid[row][col] = previous;
+                }
+                self.update_auto_notes(&undone);
+            }
+            Some(Edit::Note(row, col, previous)) => self.notes[row][col] = previous,
+            Some(Edit::Tags(previous)) => self.color_tags = previous,
+            None => {}
+        }
+    }
+
+    // clears every color tag on the board, as one undo step -- with no tags on the board, nothing is added to the history
+    fn clear_color_tags(&mut self) {
+        if self.color_tags.iter().flatten().all(Option::is_none) {
+            return;
+        }
+        self.history.push(Edit::Tags(self.color_tags));
+        self.color_tags = [[None; 9]; 9];
+    }
+
+    // turns a note on or off in an empty cell the user can type in, as one undo step -- a filled cell or a given is left alone
+    fn toggle_note(&mut self, row: usize, col: usize, digit: char) {
+        if self.starting_grid[row][col] != '.' || self.player_grid[row][col] != '.' {
+            return;
+        }
+        self.history.push(Edit::Note(row, col, self.notes[row][col]));
+        self.notes[row][col] ^= notes::bit(digit);
+    }
+
+    // handles a digit typed into the cell at (row, col)
+        // a digit for the same cell within entry_debounce_ms of the last one is dropped, so key repeat or a bouncy key
+        // can't enter a digit twice (and strike twice) -- this also covers confirming a preview
+        // with the ghost preview on, the first press only previews the digit, and pressing it again commits it
+    fn digit_pressed(&mut self, row: usize, col: usize, digit: char, elapsed: Duration) {
+        let now = Instant::now();
+        if is_bounce(self.last_digit_press, (row, col), now, Duration::from_millis(self.settings.entry_debounce_ms)) {
+            return;
+        }
+        self.last_digit_press = Some(((row, col), now));
+        if !self.settings.ghost_preview || self.pending_digit == Some((row, col, digit)) {
+            self.place_digit(row, col, digit, elapsed);
+        }
+        else {
+            self.pending_digit = Some((row, col, digit));
+        }
+    }
+
+    // enters digit into the cell at (row, col), the same way for a key press or a committed preview
+        // the entry is logged for the digit stats, a wrong digit is a strike, and a correct digit counts as progress
+    fn place_digit(&mut self, row: usize, col: usize, digit: char, elapsed: Duration) {
+        self.pending_digit = None;
+        let change = (row, col, self.player_grid[row][col]);
+        self.history.push(Edit::Cells(vec![change]));
+        self.player_grid[row][col] = digit;
+        self.update_auto_notes(&[change]);
+
+        // log the entry so it can be added to the digit stats when the game ends -- nothing in a sandbox is logged
+        let sandboxed = self.sandbox.is_some();
+        if !sandboxed {
+            self.actions.push(Action {
+                row,
+                col,
+                digit,
+                expected: self.solution_grid[row][col],
+                at_ms: elapsed.as_millis() as u64,
+                think_ms: self.selected_at.elapsed().as_millis() as u64,
+            });
+        }
+
+        // if the number entered is incorrect, increment the user's strikes by 1
+            // in a sandbox a wrong entry is only colored -- it isn't a strike, and isn't kept as a mistake
+        if self.is_wrong(row, col) {
+            if !sandboxed {
+                self.mistakes.push(MistakeRecord {
+                    row,
+                    col,
+                    entered: digit,
+                    expected: self.solution_grid[row][col],
+                });
+                self.on_strike();
+            }
+            if self.settings.auto_erase {
+                self.wrong_entries[row][col] = Some((digit, Instant::now()));
+            }
+            // a digit that breaks the cell's parity mark is always wrong, so say why
+            if !board::parity_allows(&self.parity, row, col, digit) {
+                let needed = if self.parity[row][col] == 'e' { "an even" } else { "an odd" };
+                self.show_toast(format!("That cell needs {} digit", needed));
+            }
+        }
+        else {
+            // a correct digit is progress, so the stuck nudge goes away and its timer starts over
+            self.last_progress = Instant::now();
+            self.stuck_nudge = None;
+            self.stuck_dismissed = false;
+
+            // with row typing on, a correct digit moves the selection to the next empty cell to the right,
+                // so a whole row can be typed in one go -- a wrong digit stays put so it can be fixed
+            if self.settings.row_typing {
+                if let Some(next) = (col + 1..9).find(|&next| self.player_grid[row][next] == '.') {
+                    self.selected[1] = next;
+                    self.selected_at = Instant::now();
+                }
+            }
+        }
+    }
+
+    // erases the wrong entries that have been on the board for AUTO_ERASE (see the auto_erase setting) -- the strike stays
+        // an entry that was already changed or erased is just forgotten, and each erase is its own undo step
+    fn erase_wrong_entries(&mut self, ctx: &Context) {
+        for row in 0..9 {
+            for col in 0..9 {
+                let Some((digit, at)) = self.wrong_entries[row][col] else { continue };
+                if self.player_grid[row][col] != digit {
+                    self.wrong_entries[row][col] = None;
+                }
+                else if at.elapsed() >= AUTO_ERASE {
+                    self.wrong_entries[row][col] = None;
+                    let change = (row, col, digit);
+                    self.history.push(Edit::Cells(vec![change]));
+                    self.player_grid[row][col] = '.';
+                    self.update_auto_notes(&[change]);
+                }
+                else {
+                    ctx.request_repaint_after(AUTO_ERASE - at.elapsed());
+                }
+            }
+        }
+    }
+
+    // adds a strike, and gives the warnings the settings ask for once the game is close to being lost
+        // this is the only place a strike is added in a 9x9 game, so every strike gets the same feedback
+        // the warnings get stronger the closer the game is to ending, and the strike that ends it doesn't warn (the game over screen says enough)
+    fn on_strike(&mut self) {
+        self.strikes = self.strikes.saturating_add(1);
+        // in arcade mode, a strike restarts the error-free stretch needed to win one back
+        self.last_strike_recovery = Instant::now();
+
+        let left = self.strikes_left();
+        let threshold = self.settings.warn_strikes_left;
+        if left == 0 || left > threshold as u32 {
+            return;
+        }
+        if self.settings.strike_toast {
+            let message = match self.options.points {
+                Some(rule) => format!("Careful -- {} points left", rule.remaining(self.strikes)),
+                None if left == 1 => "Careful -- one more strike ends the game".to_string(),
+                None => format!("Careful -- {} strikes left", left),
+            };
+            self.show_toast(message);
+        }
+        if self.settings.strike_flash {
+            // the last strike before losing flashes at full strength, and earlier warnings fade down from there
+            let strength = 1.0 - (left - 1) as f32 / threshold as f32;
+            self.strike_flash = Some((Instant::now(), strength.max(0.25)));
+        }
+    }
+
+    // how many more strikes the current game can take before it is lost -- in a points game, how many more mistakes the points cover
+    fn strikes_left(&self) -> u32 {
+        match self.options.points {
+            Some(rule) => rule.remaining(self.strikes).div_ceil(rule.per_mistake),
+            None => (self.options.strike_limit as u32).saturating_sub(self.strikes),
+        }
+    }
+
+    // shows the frame rate and frame time in the top right corner when the fps_overlay setting is on -- F3 turns it on and off anywhere
+        // cpu_usage is how long eframe spent on the last frame, which is the app's own cost (the frame time also includes waiting)
+    fn fps_overlay(&mut self, ctx: &Context, cpu_usage: Option<f32>) {
+        if ctx.input(|input| input.key_pressed(Key::F3)) {
+            self.settings.fps_overlay = !self.settings.fps_overlay;
+            self.settings.save();
+        }
+        if !self.settings.fps_overlay {
+            return;
+        }
+        let frame_ms = self.frame_counter.frame_time().map_or("-".to_string(), |time| format!("{:.1}", time.as_secs_f32() * 1000.0));
+        let cpu_ms = cpu_usage.map_or("-".to_string(), |secs| format!("{:.1}", secs * 1000.0));
+        egui::Area::new(egui::Id::new("fps_overlay"))
+            .anchor(Align2::RIGHT_TOP, Vec2::new(-8.0, 8.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(RichText::new(format!("{} fps\nframe {} ms\ncpu {} ms", self.frame_counter.fps(), frame_ms, cpu_ms)).monospace());
+                });
+            });
+    }
+
+    // while the window is minimized during a game, shows the game's clock in a small always-on-top window of its own
+        // the time comes from elapsed(), the same as the header, so the two can't drift apart -- blitz games show the time left instead
+        // closing the mini timer hides it until the next time the window is minimized, and restoring the window hides it too
+    fn mini_timer(&mut self, ctx: &Context) {
+        let minimized = ctx.input(|input| input.viewport().minimized.unwrap_or(false));
+        if !minimized {
+            self.mini_timer_closed = false;
+            return;
+        }
+        if !self.settings.mini_timer || self.mini_timer_closed || !self.game_in_progress() || self.samurai.is_some() {
+            return;
+        }
+
+        let elapsed = self.elapsed();
+        let text = match self.time_budget {
+            Some(budget) => format!("{} left", format_time(budget.saturating_sub(elapsed).as_secs())),
+            None => format_time(elapsed.as_secs()),
+        };
+        let viewport = egui::ViewportBuilder::default()
+            .with_title(format!("Sudoku -- {}", self.difficulty))
+            .with_inner_size([180.0, 60.0])
+            .with_resizable(false)
+            .with_always_on_top();
+        let closed = ctx.show_viewport_immediate(egui::ViewportId::from_hash_of("mini_timer"), viewport, |ctx, _class| {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.vertical_centered(|ui| ui.heading(RichText::new(text.as_str()).font(FontId::monospace(28.0))));
+            });
+            ctx.input(|input| input.viewport().close_requested())
+        });
+        self.mini_timer_closed = closed;
+        // the clock still has to tick while the main window isn't being drawn to
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+
+    // draws the red flash over the whole window after a warning strike, fading out over STRIKE_FLASH
+    fn draw_strike_flash(&mut self, ctx: &Context) {
+        let Some((at, strength)) = self.strike_flash else { return };
+        let progress = at.elapsed().as_secs_f32() / STRIKE_FLASH.as_secs_f32();
+        if progress >= 1.0 {
+            self.strike_flash = None;
+            return;
+        }
+        let alpha = (90.0 * strength * (1.0 - progress)) as u8;
+        ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("strike_flash")))
+            .rect_filled(ctx.screen_rect(), 0.0, Color32::from_rgba_unmultiplied(255, 40, 80, alpha));
+        ctx.request_repaint();
+    }
+
+    // the popover under the strikes listing the game's wrong entries, with a button to jump to each one's cell
+        // each row shows what the cell holds now too, since the mistake may have been fixed since
+        // clicking anywhere outside of it closes it, and toggled is set on the frame the strikes were clicked
+    fn mistake_review(&mut self, ctx: &Context, anchor: Rect, toggled: bool) {
+        if toggled {
+            self.mistake_review = !self.mistake_review;
+        }
+        if !self.mistake_review {
+            return;
+        }
+        let wrong: Vec<Action> = self.actions.iter().filter(|action| !action.correct()).copied().collect();
+        let mut jump = None;
+        let area = egui::Area::new(egui::Id::new("mistake_review"))
+            .order(egui::Order::Foreground)
+            .pivot(Align2::CENTER_TOP)
+            .fixed_pos(anchor.center_bottom() + Vec2::new(0.0, 4.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    Grid::new("mistake_review_grid").striped(true).show(ui, |ui| {
+                        ui.strong("Cell");
+                        ui.strong("Entered");
+                        ui.strong("Now");
+                        ui.strong("When");
+                        ui.end_row();
+                        for action in &wrong {
+                            ui.label(format!("Row {}, column {}", action.row + 1, action.col + 1));
+                            ui.label(action.digit.to_string());
+                            ui.label(match self.player_grid[action.row][action.col] {
+                                '.' => "empty".to_string(),
+                                digit if digit == self.solution_grid[action.row][action.col] => format!("{} (fixed)", digit),
+                                digit => digit.to_string(),
+                            });
+                            ui.label(format_time(action.at_ms / 1000));
+                            if ui.button("Jump to cell").clicked() {
+                                jump = Some((action.row, action.col));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if let Some((row, col)) = jump {
+            self.selected = [row, col];
+            self.selected_at = Instant::now();
+            self.mistake_review = false;
+        }
+        let clicked_outside = ctx.input(|input| {
+            input.pointer.any_click() && input.pointer.interact_pos().is_some_and(|pos| !area.response.rect.contains(pos))
+        });
+        if clicked_outside && !toggled {
+            self.mistake_review = false;
+        }
+    }
+
+    // keeps the automatic notes in step with the board after some cells changed -- each change is (row, col, previous char),
+        // the same as an Edit::Cells history entry, and they are applied in order so several changes to one cell still work out
+    fn update_auto_notes(&mut self, changes: &[(usize, usize, char)]) {
+        if !self.settings.auto_notes {
+            return;
+        }
+        let mut grid = self.player_grid;
+        // rewind to before the changes, then replay them one at a time
+        for &(row, col, previous) in changes.iter().rev() {
+            grid[row][col] = previous;
+        }
+        for &(row, col, previous) in changes {
+            grid[row][col] = self.player_grid[row][col];
+            notes::apply_change(&mut self.notes, &grid, row, col, previous);
+        }
+    }
+
+    // saves the board, the strikes, and how far the mistake and action logs go, so the game can be put back to this point
+    fn save_checkpoint(&mut self) {
+        self.checkpoint = Some(self.player_grid);
+        self.checkpoint_strikes = self.strikes;
+        self.checkpoint_mistakes = self.mistakes.len();
+        self.checkpoint_actions = self.actions.len();
+    }
+
+    // puts the board back the way it was at the checkpoint, along with the strikes
+        // the mistakes and actions logged since are dropped too, so entries that were backed out of don't end up in the stats
+        // the cells it changes are added to history as one entry, so the revert itself can be undone
+    fn revert_to_checkpoint(&mut self) {
+        let Some(checkpoint) = self.checkpoint else { return };
+        let mut changes = Vec::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.player_grid[row][col] != checkpoint[row][col] {
+                    changes.push((row, col, self.player_grid[row][col]));
+                }
+            }
+        }
+        self.player_grid = checkpoint;
+        self.update_auto_notes(&changes);
+        if !changes.is_empty() {
+            self.history.push(Edit::Cells(changes));
+        }
+        self.strikes = self.checkpoint_strikes;
+        self.mistakes.truncate(self.checkpoint_mistakes);
+        self.actions.truncate(self.checkpoint_actions);
+    }
+
+    // puts the real game aside and starts a sandbox on a copy of it -- same givens, entries, notes, and color tags
+        // the sandbox starts with an empty undo history, so undoing can't reach back into the real game
+    fn enter_sandbox(&mut self) {
+        self.pending_digit = None;
+        self.sandbox = Some(Sandbox {
+            player_grid: self.player_grid,
+            notes: self.notes,
+            color_tags: self.color_tags,
+            history: std::mem::take(&mut self.history),
+            strikes: self.strikes,
+        });
+    }
+
+    // goes back to the real game exactly as it was left, then (if apply is set) places each entry the sandbox added, in reading order
+        // applied entries are judged like any other entry, so a wrong one is a strike -- applying stops if a strike ends the game
+    fn leave_sandbox(&mut self, apply: bool) {
+        let Some(real) = self.sandbox.take() else { return };
+        let additions = sandbox::additions(&real.player_grid, &self.player_grid);
+        self.player_grid = real.player_grid;
+        self.notes = real.notes;
+        self.color_tags = real.color_tags;
+        self.history = real.history;
+        self.strikes = real.strikes;
+        self.pending_digit = None;
+        self.explained_conflict = None;
+        self.wrong_entries = [[None; 9]; 9];
+        if !apply {
+            return;
+        }
+        let elapsed = self.elapsed();
+        let mut applied = 0;
+        for (row, col, digit) in additions {
+            if self.struck_out() {
+                break;
+            }
+            self.place_digit(row, col, digit, elapsed);
+            applied += 1;
+        }
+        let plural = if applied == 1 { "y" } else { "ies" };
+        self.show_toast(format!("Applied {} entr{} from the sandbox", applied, plural));
+    }
+
+    // the digits that could legally go in the cell at (row, col) on the board as it is now -- none of them repeat in the cell's
+        // row, column, or box, and in an even/odd game they also fit the cell's parity mark (a filled cell has none)
+    fn candidates_for(&self, row: usize, col: usize) -> Vec<char> {
+        board::candidates(&self.player_grid, row, col)
+            .into_iter()
+            .filter(|&digit| board::parity_allows(&self.parity, row, col, digit))
+            .collect()
+    }
+
+    // creates the text for a cell -- empty cells get an empty string
+        // givens use the default text color, the user's correct entries are Blue, and wrong entries are Red
+        // wrong entries are only colored Red if highlighting errors is turned on, or the user asked to see their mistakes
+    fn cell_text(&self, row: usize, col: usize) -> RichText {
+        // a previewed digit is drawn faintly in its cell until it is committed or thrown away
+        if let Some((_, _, digit)) = self.pending_digit.filter(|&(r, c, _)| (r, c) == (row, col)) {
+            return RichText::new(board::digit_label(digit)).font(fonts::digit_font(34.0)).color(Color32::from_gray(150).gamma_multiply(0.6));
+        }
+        let num = self.player_grid[row][col];
+        // with the candidates assist on, the selected empty cell shows what could go there now, unless it has notes of its own
+        if num == '.' && self.settings.show_candidates && self.selected == [row, col] && self.notes[row][col] == 0 {
+            let mask = self.candidates_for(row, col).into_iter().fold(0, |mask, digit| mask | notes::bit(digit));
+            return RichText::new(notes::label(mask)).font(FontId::monospace(80.0 * 0.2)).color(Color32::from_rgb(0, 150, 110));
+        }
+        if num == '.' {
+            return self.notes_text(row, col);
+        }
+
+        let button_text = RichText::new(board::digit_label(num))
+            .font(fonts::digit_font(34.0));
+        if self.starting_grid[row][col] != '.' {
+            button_text
+        }
+        else if self.is_wrong(row, col) && (self.show_errors || self.reveal_mistakes || self.wrong_entries[row][col].is_some()) {
+            button_text.color(Color32::from_rgb(255, 60, 110))
+        }
+        else {
+            button_text.color(Color32::from_rgb(0, 124, 255))
+        }
+    }
+
+    // lays a cell's notes out as a 3x3 block of small digits, with each digit in its own spot (1 top left, 9 bottom right)
+        // the font is sized from the 80 pixel cell, and is monospace so the gaps left by missing digits keep the columns lined up
+    fn notes_text(&self, row: usize, col: usize) -> RichText {
+        let mask = self.notes[row][col];
+        if mask == 0 {
+            return RichText::new("");
+        }
+        RichText::new(notes::label(mask)).font(FontId::monospace(80.0 * 0.2)).color(Color32::GRAY)
+    }
+
+    // picks the background color of a cell
+    fn cell_fill(&self, row: usize, col: usize, selected_num: char) -> Color32 {
+        let num = self.player_grid[row][col];
+
+        // every cell starts from its box's color: the boxes alternate like a checkerboard (the corners and the center share one shade),
+            // with the contrast and pastel tints from the settings (see palette.rs)
+        let base = palette::box_color((row / 3) * 3 + col / 3, self.settings.box_contrast, self.settings.pastel_boxes, self.dark_mode);
+
+        // at most one highlight is layered over the box color, so the highlight reads the same on every box
+            // each color has a darker version for dark mode, so the light text stays readable
+        // a given the user just tried to type into flashes red, on top of everything else
+        if self.locked_flash.is_some_and(|(cell, at)| cell == (row, col) && at.elapsed() < LOCKED_FLASH) {
+            palette::layer(base, Color32::from_rgb(255, 60, 90), 0.55)
+        }
+        // when the user asks to see their mistakes, wrong cells get a Red background
+        else if self.reveal_mistakes && self.is_wrong(row, col) {
+            palette::layer(base, if self.dark_mode { Color32::from_rgb(160, 30, 50) } else { Color32::from_rgb(255, 120, 140) }, 0.6)
+        }
+        // cells the user has tagged with a color are tinted with that color
+        else if let Some(tag) = self.color_tags[row][col] {
+            palette::layer(base, tag.fill(self.dark_mode), 0.8)
+        }
+        // highlight all cells in the grid that are the same as the selected number
+            // for example, if the user has selected a cell with 3 in it, all cells in the grid that contain 3 will be highlighted Blue
+        else if num != '.' && num == selected_num {
+            palette::layer(base, if self.dark_mode { Color32::from_rgb(70, 70, 170) } else { Color32::from_rgb(150, 150, 255) }, 0.55)
+        }
+        else {
+            base
+        }
+    }
+
+    // returns how many cells are wrong if the board is completely filled but doesn't match the solution, otherwise None
+    fn wrong_cells_if_full(&self) -> Option<usize> {
+        let full = self.player_grid.iter().flatten().all(|&c| c != '.');
+        if !full || self.player_grid == self.solution_grid {
+            return None;
+        }
+        let wrong = (0..81).filter(|&i| self.is_wrong(i / 9, i % 9)).count();
+        Some(wrong)
+    }
+
+    // returns true if the user's entry at (row, col) is wrong, going by the game's validation (see Validation)
+        // empty cells and givens are never wrong -- when checking by the rules, a given that clashes with an entry leaves the entry marked
+    fn is_wrong(&self, row: usize, col: usize) -> bool {
+        let num = self.player_grid[row][col];
+        if num == '.' || self.starting_grid[row][col] != '.' {
+            return false;
+        }
+        match self.options.validation {
+            Validation::Solution => num != self.solution_grid[row][col],
+            Validation::Rules => !board::is_legal(&self.player_grid, row, col, num) || !board::parity_allows(&self.parity, row, col, num),
+        }
+    }
+
+    // draws the number of empty cells left in each row (to the left of the grid) and each column (above the grid)
+        // a counter disappears once its row or column has been completely filled
+    fn draw_unit_counters(&self, ui: &egui::Ui, cell_rects: &[[Rect; 9]; 9]) {
+        let painter = ui.painter();
+        let color = ui.visuals().weak_text_color();
+        let font = FontId::new(20.0, FontFamily::Proportional);
+
+        for i in 0..9 {
+            // count the empty cells in row i and in column i
+            let row_empty = (0..9).filter(|&col| self.player_grid[i][col] == '.').count();
+            let col_empty = (0..9).filter(|&row| self.player_grid[row][i] == '.').count();
+
+            // the row counter goes just left of the first cell in the row
+            if row_empty > 0 {
+                let rect = cell_rects[i][0];
+                painter.text(Pos2::new(rect.left() - 20.0, rect.center().y),
+                    Align2::CENTER_CENTER,
+                    row_empty.to_string(),
+                    font.clone(),
+                    color);
+            }
+            // the column counter goes just above the first cell in the column
+            if col_empty > 0 {
+                let rect = cell_rects[0][i];
+                painter.text(Pos2::new(rect.center().x, rect.top() - 12.0),
+                    Align2::CENTER_CENTER,
+                    col_empty.to_string(),
+                    font.clone(),
+                    color);
+            }
+        }
+    }
+
+    // switches between the light and dark theme
+        // the user's choice in settings wins, otherwise the system's preference is used, falling back to light if it is unknown
+    fn apply_theme(&mut self, ctx: &Context) {
+        self.dark_mode = self.settings.dark_mode.unwrap_or(ctx.system_theme() == Some(Theme::Dark));
+        ctx.set_theme(if self.dark_mode { Theme::Dark } else { Theme::Light });
+    }
+
+    // starts the background update check if the user opted in and it hasn't run in the last day
+    fn start_update_check(&mut self, ctx: &Context) {
+        if self.settings.check_for_updates && update::check_due(self.settings.last_update_check) {
+            self.update_check = Some(update::spawn_check(ctx.clone()));
+            self.settings.last_update_check = Some(clock::unix_time());
+            self.settings.save();
+        }
+    }
+
+    // shows a small notice in the corner when a newer release is available, with a button to open its release page
+    fn update_notice(&mut self, ctx: &Context) {
+        if let Some(receiver) = &self.update_check {
+            match receiver.try_recv() {
+                Ok(release) => {
+                    self.available_update = Some(release);
+                    self.update_check = None;
+                }
+                // the check finished without finding anything newer
+                Err(TryRecvError::Disconnected) => self.update_check = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        let mut dismissed = false;
+        if let Some(release) = &self.available_update {
+            egui::Area::new(egui::Id::new("update_notice"))
+                .anchor(Align2::RIGHT_BOTTOM, Vec2::new(-20.0, -20.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button(format!("{} available -- open release page", release.version)).clicked() {
+                                if let Err(err) = open::that(&release.url) {
+                                    println!("Failed to open the release page: {}", err);
+                                }
+                                dismissed = true;
+                            }
+                            if ui.small_button("x").clicked() {
+                                dismissed = true;
+                            }
+                        });
+                    });
+                });
+        }
+        if dismissed {
+            self.available_update = None;
+        }
+    }
+
+    // shows a short message at the bottom of the window for a few seconds
+    fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+
+    // draws the current toast, and clears it once it has been up for long enough
+    fn draw_toast(&mut self, ctx: &Context) {
+        if let Some((message, shown_at)) = &self.toast {
+            if shown_at.elapsed() > Duration::from_secs(4) {
+                self.toast = None;
+                return;
+            }
+            egui::Area::new(egui::Id::new("toast"))
+                .anchor(Align2::CENTER_BOTTOM, Vec2::new(0.0, -40.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(RichText::new(message).font(FontId::new(20.0, FontFamily::Proportional)));
+                    });
+                });
+            // repaint when the toast is due to disappear, so it goes away on time even if there is no user input
+            ctx.request_repaint_after(Duration::from_secs(4).saturating_sub(shown_at.elapsed()));
+        }
+    }
+
+    // displays the settings window when it is open, and saves the settings whenever one of them is changed
+    fn settings_window(&mut self, ctx: &Context) {
+        let mut changed = false;
+        let mut theme_changed = false;
+        // the window's open flag is copied out so the window's contents can still call methods on self
+        let mut open = self.show_settings;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Theme:");
+                    let theme_text = match self.settings.dark_mode {
+                        None => "Follow system",
+                        Some(false) => "Light",
+                        Some(true) => "Dark",
+                    };
+                    egui::ComboBox::from_id_salt("theme")
+                        .selected_text(theme_text)
+                        .show_ui(ui, |ui| {
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, None, "Follow system").changed();
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, Some(false), "Light").changed();
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, Some(true), "Dark").changed();
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Digit style:");
+                    egui::ComboBox::from_id_salt("digit_style")
+                        .selected_text(self.settings.digit_style.name())
+                        .show_ui(ui, |ui| {
+                            for style in DigitStyle::ALL {
+                                if ui.selectable_value(&mut self.settings.digit_style, style, style.name()).changed() {
+                                    fonts::install(ui.ctx(), style);
+                                    changed = true;
+                                }
+                            }
+                        });
+                });
+                changed |= ui.checkbox(&mut self.settings.transform_puzzles, "Randomly rotate/reflect puzzles").changed();
+                changed |= ui.checkbox(&mut self.settings.transform_repeats, "Rotate/reflect puzzles I've already played").changed();
+                // the counters of the current game follow this straight away too -- the per-difficulty and per-game values only
+                    // decide whether a game starts with them shown
+                if ui.checkbox(&mut self.settings.show_unit_counters, "Show empty cells left per row/column").changed() {
+                    self.options.show_unit_counters = self.settings.show_unit_counters;
+                    changed = true;
+                }
+                changed |= ui.checkbox(&mut self.settings.sweep_forced_digits, "Alt + digit fills every forced cell of that digit").changed();
+                // the notes are filled in (or cleared) straight away, so turning this on mid-game works too
+                if ui.checkbox(&mut self.settings.auto_notes, "Fill in notes automatically").changed() {
+                    self.notes = if self.settings.auto_notes { notes::fill(&self.player_grid) } else { [[0; 9]; 9] };
+                    changed = true;
+                }
+                changed |= ui.checkbox(&mut self.settings.ghost_preview, "Preview digits first (press the digit again or Enter to enter it)").changed();
+                changed |= ui.checkbox(&mut self.settings.row_typing, "Typing a correct digit moves to the next empty cell in the row").changed();
+                changed |= ui.checkbox(&mut self.settings.enter_advances, "Enter moves to the next empty cell").changed();
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut self.settings.stuck_nudge, "Nudge me when I'm stuck for").changed();
+                    changed |= ui.add(egui::DragValue::new(&mut self.settings.stuck_minutes).range(1..=30).suffix(" min")).changed();
+                });
+                changed |= ui.checkbox(&mut self.settings.confirm_quit, "Confirm before closing the window mid-game").changed();
+                changed |= ui.checkbox(&mut self.settings.quick_start, "Start games without showing the game options first").changed();
+                changed |= ui.checkbox(&mut self.settings.highlight_errors, "Color wrong entries red").changed();
+                changed |= ui.checkbox(&mut self.settings.auto_erase, "Erase wrong entries after a second (they still count as strikes)").changed();
+                changed |= ui.checkbox(&mut self.settings.sandbox_apply, "Let sandbox entries be applied back to the game (they count as normal entries)").changed();
+                ui.horizontal(|ui| {
+                    ui.label("Check entries against:");
+                    for validation in [Validation::Solution, Validation::Rules] {
+                        changed |= ui.selectable_value(&mut self.settings.validation, validation, validation.name()).changed();
+                    }
+                });
+                changed |= ui.checkbox(&mut self.settings.grid_background, "Show a subtle pattern behind the grid").changed();
+                changed |= ui.checkbox(&mut self.settings.crosshair, "Show a crosshair through the selected cell").changed();
+                changed |= ui.checkbox(&mut self.settings.show_candidates, "Show the possible digits in the selected empty cell").changed();
+                changed |= ui.checkbox(&mut self.settings.digit_chart, "Show a chart of how many of each digit are placed").changed();
+                changed |= ui.checkbox(&mut self.settings.mini_timer, "Keep a small timer on screen while the window is minimized").changed();
+                changed |= ui.checkbox(&mut self.settings.show_strikes, "Show the strike boxes").changed();
+                changed |= ui.checkbox(&mut self.settings.show_clean_indicator, "Show \"Clean so far\" until the first strike").changed();
+                ui.horizontal(|ui| {
+                    ui.label("Warn me with this many strikes left (0 for never):");
+                    changed |= ui.add(egui::DragValue::new(&mut self.settings.warn_strikes_left).range(0..=8)).changed();
+                });
+                ui.add_enabled_ui(self.settings.warn_strikes_left > 0, |ui| {
+                    ui.horizontal(|ui| {
+                        changed |= ui.checkbox(&mut self.settings.strike_toast, "With a message").changed();
+                        changed |= ui.checkbox(&mut self.settings.strike_flash, "With a red flash").changed();
+                    });
+                });
+                // turning the tutorial back on starts it over from the beginning
+                if ui.checkbox(&mut self.settings.show_tutorial, "Show the tutorial").changed() {
+                    self.tutorial_step = self.settings.show_tutorial.then_some(TutorialStep::Difficulty);
+                    changed = true;
+                }
+                changed |= ui.checkbox(&mut self.settings.show_pb_pace, "Tell me when I pass my best time").changed();
+                changed |= ui.checkbox(&mut self.settings.show_time_rank, "Rank my win times against my earlier wins").changed();
+                changed |= ui.checkbox(&mut self.settings.warm_up, "Play a 4x4 warm-up before each new game").changed();
+                changed |= ui.checkbox(&mut self.settings.record_loss_details, "Record survival time and board fill when I lose").changed();
+                changed |= ui.checkbox(&mut self.settings.check_for_updates, "Check for updates once a day").changed();
+                ui.horizontal(|ui| {
+                    ui.label("Ignore a repeated digit in the same cell within (ms):");
+                    changed |= ui.add(egui::DragValue::new(&mut self.settings.entry_debounce_ms).range(0..=1000)).changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Box shading:");
+                    changed |= ui.add(egui::Slider::new(&mut self.settings.box_contrast, 0.0..=1.0).show_value(false)).changed();
+                    changed |= ui.checkbox(&mut self.settings.pastel_boxes, "Tint each box a different color").changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Space between cells:");
+                    changed |= ui.add(egui::Slider::new(&mut self.settings.cell_gap, 0.0..=10.0)).changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Space between boxes:");
+                    changed |= ui.add(egui::Slider::new(&mut self.settings.box_gap, 0.0..=20.0)).changed();
+                });
+                // the gap between boxes can't be smaller than the gap between cells, otherwise the layout would have to go backwards
+                self.settings.box_gap = self.settings.box_gap.max(self.settings.cell_gap);
+                changed |= ui.checkbox(&mut self.settings.arcade_mode, "Arcade mode: win strikes back with error-free play").changed();
+                ui.horizontal(|ui| {
+                    ui.label("Arcade seconds per strike:");
+                    changed |= ui.add(egui::DragValue::new(&mut self.settings.arcade_recovery_secs).range(10..=600)).changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Strike limit:");
+                    changed |= ui.add(egui::DragValue::new(&mut self.settings.strike_limit).range(1..=9)).changed();
+                });
+                changed |= ui.checkbox(&mut self.settings.points_mode, "Points instead of strikes: mistakes cost points, and the game ends at zero").changed();
+                ui.add_enabled_ui(self.settings.points_mode, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Starting points:");
+                        changed |= ui.add(egui::DragValue::new(&mut self.settings.starting_points).range(1..=1000)).changed();
+                        ui.label("Cost of a mistake:");
+                        changed |= ui.add(egui::DragValue::new(&mut self.settings.points_per_mistake).range(1..=1000)).changed();
+                    });
+                });
+
+                // a grid of difficulty x option, where each difficulty can override the global settings above
+                ui.separator();
+                ui.label("Per-difficulty defaults");
+                Grid::new("difficulty_defaults").striped(true).show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Strike limit");
+                    ui.label("Color wrong entries");
+                    ui.label("Empty cell counters");
+                    ui.label("Arcade mode");
+                    ui.label("Blitz minutes");
+                    ui.end_row();
+                    for difficulty in ["Beginner", "Intermediate", "Advanced"] {
+                        let overrides = self.settings.difficulty_defaults.entry(difficulty.to_string()).or_default();
+                        ui.label(difficulty);
+                        changed |= override_combos(ui, difficulty, overrides);
+                        let minutes = self.settings.blitz_minutes.entry(difficulty.to_string()).or_insert(10);
+                        changed |= ui.add(egui::DragValue::new(minutes).range(1..=60)).changed();
+                        ui.end_row();
+                    }
+                });
+
+                // how often the app repaints -- the frame count shows how much the settings are saving
+                ui.separator();
+                ui.label("Performance");
+                ui.horizontal(|ui| {
+                    ui.label("Clock updates:");
+                    egui::ComboBox::from_id_salt("timer_granularity")
+                        .selected_text(match self.settings.timer_granularity {
+                            TimerGranularity::Second => "Every second",
+                            TimerGranularity::Tenth => "Every 100 ms",
+                            TimerGranularity::Off => "Off",
+                        })
+                        .show_ui(ui, |ui| {
+                            changed |= ui.selectable_value(&mut self.settings.timer_granularity, TimerGranularity::Second, "Every second").changed();
+                            changed |= ui.selectable_value(&mut self.settings.timer_granularity, TimerGranularity::Tenth, "Every 100 ms").changed();
+                            changed |= ui.selectable_value(&mut self.settings.timer_granularity, TimerGranularity::Off, "Off").changed();
+                        });
+                });
+                changed |= ui.checkbox(&mut self.settings.animations, "Animations").changed();
+                changed |= ui.checkbox(&mut self.settings.battery_saver, "Battery saver (no animations, only repaint on input)").changed();
+                changed |= ui.checkbox(&mut self.settings.fps_overlay, "Show the frame rate (F3)").changed();
+                #[cfg(feature = "dev-tools")]
+                ui.label(RichText::new(format!("Frames rendered in the last 10 s: {}", self.frame_counter.count())).small().weak());
+                #[cfg(feature = "dev-tools")]
+                ui.label(RichText::new(format!("Allocations in the last frame: {}", alloc_count::last_frame())).small().weak());
+                // forgets the stored window geometry and maximizes the window again, which is how it opens from now on
+                if ui.button("Reset window layout").clicked() {
+                    self.settings.window = None;
+                    self.track_window = false;
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+                    changed = true;
+                }
+
+                ui.separator();
+                self.data_section(ui);
+
+                ui.separator();
+                // the setup starts games of its own, so it can only be run again from the start screen
+                if ui.add_enabled(self.difficulty.is_empty(), Button::new("Run setup again"))
+                    .on_disabled_hover_text("Finish or quit the current game first")
+                    .clicked()
+                {
+                    self.onboarding = Some(Onboarding::new(self.username.as_str()));
+                }
+                if ui.button("Send feedback...").clicked() {
+                    // attaching the game is only offered by default when there is a game to attach
+                    self.feedback = Some(FeedbackForm::new(!self.difficulty.is_empty()));
+                }
+            });
+        // the setup takes over the window, so settings close when it starts
+        self.show_settings = open && self.onboarding.is_none();
+        if theme_changed {
+            self.apply_theme(ctx);
+        }
+        if changed || theme_changed {
+            self.settings.save();
+            self.render_budget = RenderBudget::from_settings(&self.settings);
+        }
+    }
+
+    // displays the feedback window, opened from the settings window
+        // submitting writes a report file into the feedback folder and shows its path, so the user can attach it to a GitHub issue
+    fn feedback_window(&mut self, ctx: &Context) {
+        let Some(form) = &mut self.feedback else { return };
+        let mut open = true;
+        let mut submit = false;
+        egui::Window::new("Send feedback")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("feedback_category")
+                    .selected_text(form.category.name())
+                    .show_ui(ui, |ui| {
+                        for category in FeedbackCategory::ALL {
+                            ui.selectable_value(&mut form.category, category, category.name());
+                        }
+                    });
+                ui.add(egui::TextEdit::multiline(&mut form.message).hint_text("What happened, or what would you like to see?"));
+                ui.checkbox(&mut form.attach_game, "Attach current game state");
+                if ui.add_enabled(!form.message.trim().is_empty(), Button::new("Write report")).clicked() {
+                    submit = true;
+                }
+                match &form.result {
+                    Some(Ok(path)) => {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Saved to {}", path));
+                            if ui.button("Copy path").clicked() {
+                                ui.ctx().copy_text(path.clone());
+                            }
+                        });
+                    }
+                    Some(Err(err)) => {
+                        ui.label(RichText::new(err).color(Color32::from_rgb(255, 60, 110)));
+                    }
+                    None => {}
+                }
+            });
+
+        if submit {
+            // the game is only attached if one is being played, and it is attached the same way it would be saved
+            let attach_game = self.feedback.as_ref().is_some_and(|form| form.attach_game);
+            let game = (attach_game && !self.difficulty.is_empty()).then(|| self.to_saved_game());
+            let Some(form) = &mut self.feedback else { return };
+            let report = FeedbackReport::new(form.category, form.message.clone(), &self.settings, game);
+            form.result = Some(report.write());
+        }
+        if !open {
+            self.feedback = None;
+        }
+    }
+
+    // the part of the settings window for exporting all of the user's data to a file, and importing it back
+        // importing shows a preview of what is in the file first, and only overwrites anything once the user chooses to replace or merge
+    fn data_section(&mut self, ui: &mut egui::Ui) {
+        ui.label("Your data");
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.data_path);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Export data").clicked() {
+                self.data_message = Some(match DataBundle::new(&self.settings, &self.stats).export(&self.data_path) {
+                    Ok(()) => format!("Exported to {}", self.data_path),
+                    Err(err) => err,
+                });
+            }
+            // the CSV goes next to the data file, with the same name
+            if ui.button("Export stats as CSV").clicked() {
+                let path = std::path::Path::new(&self.data_path).with_extension("csv").to_string_lossy().into_owned();
+                self.data_message = Some(match data::export_csv(&self.stats, &path) {
+                    Ok(()) => format!("Exported {} games to {}", self.stats.games.len(), path),
+                    Err(err) => err,
+                });
+            }
+            if ui.button("Import data").clicked() {
+                match DataBundle::read(&self.data_path) {
+                    Ok(bundle) => {
+                        self.pending_import = Some(bundle);
+                        self.data_message = None;
+                    }
+                    Err(err) => self.data_message = Some(err),
+                }
+            }
+        });
+
+        // preview the file being imported, and let the user decide what to do with it
+        if let Some(bundle) = &self.pending_import {
+            ui.label(format!("This file contains:\n{}", bundle.summary()));
+            ui.label("Replace overwrites your settings and stats. Merge keeps your stats and adds the file's to them.");
+            let mut choice = None;
+            ui.horizontal(|ui| {
+                if ui.button("Replace").clicked() {
+                    choice = Some(false);
+                }
+                if ui.button("Merge").clicked() {
+                    choice = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.pending_import = None;
+                }
+            });
+            if let Some(merge) = choice {
+                if let Some(mut bundle) = self.pending_import.take() {
+                    if merge {
+                        let mut stats = self.stats.clone();
+                        stats.merge(&bundle.stats);
+                        bundle.stats = stats;
+                    }
+                    self.data_message = Some(match bundle.apply() {
+                        Ok(()) => {
+                            self.settings = bundle.settings;
+                            self.stats = bundle.stats;
+                            "Imported data".to_string()
+                        }
+                        Err(err) => err,
+                    });
+                }
+            }
+        }
+
+        if let Some(message) = &self.data_message {
+            ui.label(message);
+        }
+    }
+
+    // called when a difficulty is picked -- the game options panel for it is shown next, where the game is started from
+        // a quick start (shift held down, or the quick_start setting) skips the panel and starts with what is already picked
+    fn choose_difficulty(&mut self, difficulty: &str, quick: bool) {
+        if quick || self.settings.quick_start {
+            self.difficulty = difficulty.to_string();
+        }
+        else {
+            self.pending_difficulty = Some(difficulty.to_string());
+        }
+    }
+
+    // the second stage of the difficulty screen: the options the game will be played with, resolved for the picked difficulty,
+        // with everything on it changeable before Start is pressed -- Back returns to the difficulty buttons
+    fn game_options_panel(&mut self, ui: &mut egui::Ui, difficulty: &str) {
+        ui.add_space(20.0);
+        ui.heading(RichText::new(difficulty).font(FontId::new(26.0, FontFamily::Proportional)));
+        ui.add_space(10.0);
+
+        // what the game will use once the overrides below are applied
+        let options = settings::resolve_options(&self.settings, difficulty, &self.setup.overrides);
+        match options.points {
+            Some(rule) => ui.label(format!("{} points, {} per mistake", rule.start, rule.per_mistake)),
+            None => ui.label(format!("{} strikes", options.strike_limit)),
+        };
+        ui.label(if options.highlight_errors { "Wrong entries colored" } else { "No error coloring" });
+        if options.validation == Validation::Rules {
+            ui.label("Entries checked against the rules only");
+        }
+        ui.label(if options.show_unit_counters { "Counters on" } else { "Counters off" });
+        if options.arcade {
+            ui.label("Arcade mode");
+        }
+
+        ui.add_space(10.0);
+        layout::centered_row(ui, "game_overrides", |ui| {
+            ui.label("Override:");
+            override_combos(ui, "next_game", &mut self.setup.overrides);
+        });
+        ui.checkbox(&mut self.setup.unranked, "Play without recording (unranked)");
+        ui.checkbox(&mut self.setup.even_odd, "Even/odd variant (squares hold even digits, circles hold odd digits)");
+        let budget = format_time(self.settings.blitz_budget(difficulty).as_secs());
+        ui.checkbox(&mut self.setup.blitz, format!("Blitz: race a {} countdown (the limits are in settings)", budget));
+
+        ui.add_space(20.0);
+        let start_text = RichText::new("Start").font(FontId::new(30.0, FontFamily::Proportional));
+        if ui.add(Button::new(start_text).min_size(Vec2::new(160.0, 50.0))).on_hover_text("Press Enter").clicked() {
+            self.difficulty = difficulty.to_string();
+            self.pending_difficulty = None;
+        }
+        ui.add_space(10.0);
+        if ui.button("Back").on_hover_text("Press Escape").clicked() {
+            self.pending_difficulty = None;
+        }
+    }
+
+    // lets the start screen be used without a mouse: 1/2/3 pick a difficulty, the arrow keys move between them and Enter picks one
+        // while the game options panel is open, Enter starts the game and Escape goes back, and holding shift makes a pick a quick start
+        // nothing happens while a text field has focus or a window is open over the screen, so typing a digit into it doesn't start a game
+    fn difficulty_keys(&mut self, ctx: &Context) {
+        if ctx.wants_keyboard_input() || self.show_settings || self.feedback.is_some() {
+            return;
+        }
+        if let Some(pending) = self.pending_difficulty.clone() {
+            if ctx.input(|input| input.key_pressed(Key::Enter)) {
+                self.difficulty = pending;
+                self.pending_difficulty = None;
+            }
+            else if ctx.input(|input| input.key_pressed(Key::Escape)) {
+                self.pending_difficulty = None;
+            }
+            return;
+        }
+        let quick = ctx.input(|input| input.modifiers.shift);
+        for (key, difficulty) in [Key::Num1, Key::Num2, Key::Num3].into_iter().zip(START_DIFFICULTIES) {
+            if ctx.input(|input| input.key_pressed(key)) {
+                self.choose_difficulty(difficulty, quick);
+                return;
+            }
+        }
+        let last = START_DIFFICULTIES.len() - 1;
+        if ctx.input(|input| input.key_pressed(Key::ArrowRight) || input.key_pressed(Key::ArrowDown)) {
+            self.difficulty_cursor = Some(self.difficulty_cursor.map_or(0, |cursor| (cursor + 1).min(last)));
+        }
+        if ctx.input(|input| input.key_pressed(Key::ArrowLeft) || input.key_pressed(Key::ArrowUp)) {
+            self.difficulty_cursor = Some(self.difficulty_cursor.map_or(last, |cursor| cursor.saturating_sub(1)));
+        }
+        if let Some(cursor) = self.difficulty_cursor {
+            if ctx.input(|input| input.key_pressed(Key::Enter)) {
+                self.choose_difficulty(START_DIFFICULTIES[cursor], quick);
+            }
+        }
+    }
+
+    // displays the start screen where the user selects the difficulty
+    fn difficulty_screen(&mut self, ctx: &Context) {
+        self.difficulty_keys(ctx);
+        // retry_lost is set when the user clicks the button to retry their last lost puzzle, and import_photo when they import one
+        let mut retry_lost = false;
+        let mut import_photo = false;
+        let mut pick_puzzle = false;
+        let mut load_collection = false;
+        let mut start_marathon = false;
+        let mut resume_marathon = false;
+        CentralPanel::default().show(ctx, |ui| {
+            // the layout is sized from the window instead of fixed offsets, so it still fits when the window is small
+                // the buttons shrink to fit the width, and the screen scrolls if it runs out of height
+            let available = ui.available_size();
+            let gap = (available.x * 0.02).clamp(10.0, 30.0);
+            let button_size = Vec2::new(((available.x - 2.0 * gap) / 3.0).clamp(90.0, 150.0), (available.y * 0.1).clamp(50.0, 100.0));
+            egui::ScrollArea::vertical().show(ui, |ui| ui.vertical_centered(|ui| {
+                ui.add_space(available.y * 0.2);
+                // Sudoku title
+                let title_text = RichText::new("Sudoku")
+                    .font(FontId::new(30.0, FontFamily::Proportional))
+                    .color(Color32::from_rgb(60, 190, 220));
+                ui.heading(title_text);
+
+                // once a difficulty is picked, its game options panel takes the place of the rest of the screen
+                if let Some(pending) = self.pending_difficulty.clone() {
+                    self.game_options_panel(ui, &pending);
+                    return;
+                }
+
+                // Beginner, Intermediate, and Advanced butttons, centered as one row
+                    // shift-clicking a button is a quick start, which skips the game options panel
+                ui.add_space(gap);
+                let mut buttons_rect = Rect::NOTHING;
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    ui.add_space(((ui.available_width() - 3.0 * button_size.x - 2.0 * gap) / 2.0).max(0.0));
+                    // the button picked with the arrow keys is drawn as selected, and each button shows its number key on hover
+                    for (i, difficulty) in START_DIFFICULTIES.into_iter().enumerate() {
+                        if i > 0 {
+                            ui.add_space(gap);
+                        }
+                        let button_text = RichText::new(difficulty)
+                            .font(FontId::new(24.0, FontFamily::Proportional));
+                        let button = Button::new(button_text).min_size(button_size).selected(self.difficulty_cursor == Some(i));
+                        let response = ui.add(button).on_hover_text(format!("Press {} (shift for a quick start)", i + 1));
+                        buttons_rect = buttons_rect.union(response.rect);
+                        if response.clicked() {
+                            self.choose_difficulty(difficulty, ui.input(|input| input.modifiers.shift));
+                        };
+                    }
+                });
+                self.tutorial_callout(ctx, TutorialStep::Difficulty, buttons_rect);
+                // THIS SHOULD NOT BE INCLUDED IN FINAL SUBMISSION -- THIS IS FOR TESTING WIN SCREEN
+                ui.add_space(gap);
+                let test_button_text = RichText::new("Test")
+                    .font(FontId::new(24.0, FontFamily::Proportional));
+                if ui.add(Button::new(test_button_text).min_size(button_size)).clicked() {
+                    self.choose_difficulty("Test", ui.input(|input| input.modifiers.shift));
+                };
+
+                // samurai is five overlapping boards played on their own screen, so it doesn't go through choose_difficulty
+                ui.add_space(gap);
+                let samurai_text = RichText::new("Samurai")
+                    .font(FontId::new(24.0, FontFamily::Proportional));
+                if ui.add(Button::new(samurai_text).min_size(button_size)).on_hover_text("Five overlapping boards").clicked() {
+                    self.samurai = Some(Samurai::new(!self.setup.unranked, &self.stats.played_puzzles));
+                }
+
+                // a puzzle can be read from a photo by an OCR program (see source.rs), and is checked before it is played
+                egui::CollapsingHeader::new("Import from photo").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Image file:");
+                        ui.text_edit_singleline(&mut self.photo_path);
+                        import_photo = ui.button("Import").clicked();
+                    });
+                    if let Some(message) = &self.photo_message {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                });
+
+                // saved collections are played like a difficulty, and a collection file someone shared can be loaded here
+                egui::CollapsingHeader::new("Puzzle collections").show(ui, |ui| {
+                    for name in self.collections.clone() {
+                        if ui.button(&name).on_hover_text("Shift-click for a quick start").clicked() {
+                            self.choose_difficulty(&name, ui.input(|input| input.modifiers.shift));
+                        }
+                    }
+                    if self.collections.is_empty() {
+                        ui.label("No collections yet -- add puzzles to one from the end of a game, or load a file");
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Collection file:");
+                        ui.text_edit_singleline(&mut self.collection_path);
+                        load_collection = ui.button("Load").clicked();
+                    });
+                    if let Some(message) = &self.collection_message {
+                        ui.label(message);
+                    }
+                });
+
+                // a marathon is a few different puzzles at one difficulty played back to back, with one clock for the whole run
+                egui::CollapsingHeader::new("Marathon").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("marathon_difficulty")
+                            .selected_text(self.marathon_setup.difficulty.clone())
+                            .show_ui(ui, |ui| {
+                                for difficulty in START_DIFFICULTIES.into_iter().map(String::from).chain(self.collections.iter().cloned()) {
+                                    ui.selectable_value(&mut self.marathon_setup.difficulty, difficulty.clone(), difficulty);
+                                }
+                            });
+                        for length in marathon::LENGTHS {
+                            ui.selectable_value(&mut self.marathon_setup.length, length, format!("{} puzzles", length));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.marathon_setup.strike_rule, StrikeRule::PerPuzzle, StrikeRule::PerPuzzle.name())
+                            .on_hover_text("Every puzzle gets the full strike limit");
+                        ui.radio_value(&mut self.marathon_setup.strike_rule, StrikeRule::Shared, StrikeRule::Shared.name())
+                            .on_hover_text("One strike limit for the whole marathon");
+                    });
+                    start_marathon = ui.button("Start marathon").clicked();
+                    if let Some(message) = &self.marathon_message {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                });
+
+                // a specific puzzle can be picked from a puzzle file by its index, to replay a known board
+                egui::CollapsingHeader::new("Pick a puzzle by number").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("pick_difficulty")
+                            .selected_text(self.pick_difficulty.clone())
+                            .show_ui(ui, |ui| {
+                                for difficulty in START_DIFFICULTIES {
+                                    ui.selectable_value(&mut self.pick_difficulty, difficulty.to_string(), difficulty);
+                                }
+                            });
+                        ui.label("Index (from 0):");
+                        ui.add(egui::DragValue::new(&mut self.pick_index));
+                        pick_puzzle = ui.button("Play").clicked();
+                    });
+                    if let Some(message) = &self.pick_message {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                });
+
+                // a game saved when the app was closed can be picked back up
+                let mut resume = false;
+                if let Some(saved) = &self.saved_game {
+                    ui.add_space(20.0);
+                    resume = ui.button(format!("Resume saved game ({}, {})", saved.difficulty, format_time(saved.elapsed_secs))).clicked();
+                }
+                if resume {
+                    self.resume_saved_game();
+                }
+                if let Some(saved) = &self.saved_samurai {
+                    ui.add_space(20.0);
+                    if ui.button(format!("Resume samurai game ({})", format_time(saved.elapsed_secs))).clicked() {
+                        self.resume_samurai();
+                    }
+                }
+                if let Some(saved) = &self.saved_marathon {
+                    ui.add_space(20.0);
+                    let label = format!("Resume marathon ({}, puzzle {} of {})", saved.difficulty, saved.results.len() + 1, saved.queue.len());
+                    resume_marathon = ui.button(label).clicked();
+                }
+
+                // if the user lost a puzzle (even in an earlier session), offer to retry it
+                if let Some(lost) = &self.stats.last_lost {
+                    ui.add_space(20.0);
+                    if ui.button(format!("Retry last lost puzzle ({})", lost.difficulty)).clicked() {
+                        retry_lost = true;
+                    }
+                }
+
+                // opens the settings window or the statistics screen
+                ui.add_space(20.0);
+                if ui.button("Settings").clicked() {
+                    self.show_settings = true;
+                }
+                if ui.button("Statistics").clicked() {
+                    self.show_statistics = true;
+                }
+            }));
+
+            // if the user wants to retry their last lost puzzle, load it instead of a random one
+            if retry_lost {
+                self.retry_lost_puzzle();
+            }
+            else if import_photo {
+                self.import_photo();
+            }
+            else if pick_puzzle {
+                self.pick_puzzle();
+            }
+            else if load_collection {
+                self.load_collection();
+            }
+            else if start_marathon {
+                self.start_marathon();
+            }
+            else if resume_marathon {
+                self.resume_marathon();
+            }
+            // if the difficulty is not an empty string, call self.get_puzzle to randomly get a puzzle
+            else if !self.difficulty.is_empty() {
+                self.get_puzzle();
+                // retries go straight to the puzzle, but a fresh game can start with a warm-up
+                if self.settings.warm_up {
+                    self.warm_up = Some(WarmUp::random());
+                }
+            }
+        });
+    }
+
+    // displays the 4x4 warm-up puzzle -- it is untimed and never recorded, and can always be skipped with one click
+        // the screen has an amber background so it is obviously not the real game
+    fn warm_up_screen(&mut self, ctx: &Context) {
+        let Some(warm_up) = &mut self.warm_up else { return };
+        let mut finished = false;
+
+        let frame = egui::Frame::central_panel(&ctx.style()).fill(Color32::from_rgb(255, 236, 200));
+        CentralPanel::default().frame(frame).show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(RichText::new("Warm-up")
+                    .font(FontId::new(30.0, FontFamily::Proportional))
+                    .color(Color32::from_rgb(200, 120, 20)));
+                ui.label(RichText::new("Untimed and unranked -- fill in 1-4 so no row, column, or box repeats").color(Color32::DARK_GRAY));
+                ui.add_space(10.0);
+                if ui.button("Skip warm-up").clicked() {
+                    finished = true;
+                }
+                ui.add_space(20.0);
+
+                // the 4x4 board, with a wider gap between the 2x2 boxes
+                layout::centered_row(ui, "warm_up_board", |ui| {
+                    ui.vertical(|ui| {
+                        for row in 0..4 {
+                            if row == 2 {
+                                ui.add_space(10.0);
+                            }
+                            ui.horizontal(|ui| {
+                                for col in 0..4 {
+                                    if col == 2 {
+                                        ui.add_space(10.0);
+                                    }
+                                    let num = warm_up.grid[row][col];
+                                    let text = RichText::new(board::digit_label(num))
+                                        .font(FontId::new(34.0, FontFamily::Proportional));
+                                    // the warm-up always has a light background, so givens are drawn black even in dark mode
+                                    let text = if warm_up.givens[row][col] == '.' {
+                                        text.color(Color32::from_rgb(200, 120, 20))
+                                    }
+                                    else {
+                                        text.color(Color32::BLACK)
+                                    };
+                                    let mut button_element = Button::new(text).min_size(Vec2::new(80.0, 80.0));
+                                    if warm_up.selected == Some((row, col)) {
+                                        button_element = button_element.fill(Color32::from_rgb(255, 210, 140));
+                                    }
+                                    if ui.add(button_element).clicked() {
+                                        warm_up.selected = Some((row, col));
+                                    }
+                                }
+                            });
+                        }
+                    });
+                });
+
+                // digits 1-4 fill the selected cell, and backspace clears it -- givens can't be changed
+                if let Some((row, col)) = warm_up.selected {
+                    if warm_up.givens[row][col] == '.' {
+                        for (key, digit) in [(Key::Num1, '1'), (Key::Num2, '2'), (Key::Num3, '3'), (Key::Num4, '4')] {
+                            if ui.input(|input| input.key_pressed(key)) {
+                                warm_up.grid[row][col] = digit;
+                            }
+                        }
+                        if ui.input(|input| input.key_pressed(Key::Backspace)) {
+                            warm_up.grid[row][col] = '.';
+                        }
+                    }
+                }
+
+                if warm_up.is_solved() {
+                    ui.add_space(20.0);
+                    ui.heading("Nice! You're warmed up.");
+                    if ui.button(RichText::new("Start the puzzle").font(FontId::new(24.0, FontFamily::Proportional))).clicked() {
+                        finished = true;
+                    }
+                }
+            });
+        });
+
+        // the real puzzle's clock only starts once its screen is shown, so the warm-up doesn't count toward its time
+        if finished {
+            self.warm_up = None;
+        }
+    }
+
+    // displays the game over screen when the user loses
+    fn lose_screen(&mut self, ctx: &Context) {
+        let rounded = self.fill_percentage();
+
+        // the first time the lose screen is shown, stop the clock, record the loss, and remember the puzzle so it can be retried later
+        if !self.game_over {
+            self.time_elapsed = self.elapsed();
+            self.record_game_end(false, self.ranked);
+            self.game_over = true;
+        }
+
+        // display ui elements, including the percentage of the board the user had correct
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(RichText::new("Game over!").color(Color32::from_rgb(255, 60, 110)));
+                unranked_badge(ui, self.ranked);
+                ui.label(format!("You filled {} percent of the board", rounded));
+                ui.label(format!("You lasted {}", format_time(self.time_elapsed.as_secs())));
+                if self.out_of_time() {
+                    ui.label("You ran out of time");
+                }
+                // with the strike boxes hidden, this is the first time the user sees how many strikes they made
+                if let Some(rule) = self.options.points.filter(|_| self.struck_out()) {
+                    ui.label(format!("You ran out of points -- {} mistakes at {} points each", self.strikes, rule.per_mistake));
+                }
+                else if !self.settings.show_strikes {
+                    ui.label(format!("You made {} strikes", self.strikes));
+                }
+
+                ui.add_space(10.0);
+                self.collection_adder(ui);
+
+                // show the board as it was when the game ended
+                ui.add_space(20.0);
+                thumbnail::draw(ui, &self.starting_grid, &self.player_grid, &self.solution_grid, 22.0, &ThumbnailColors::standard());
+
+                // create the button text
+                let button_text = RichText::new("Play Again")
+                    .font(FontId::new(30.0, FontFamily::Proportional));
+
+                // create the button element
+                let button_element = Button::new(button_text)
+                            .min_size(Vec2::new(50.0, 20.0));
+                
+                // add the button
+                ui.add_space(40.0);
+                let button = ui.add(button_element);
+
+                // if a button is clicked, reset the gamestate
+                if button.clicked() {
+                    self.reset_gamestate();
+                }
+
+                // the retry button restarts the same puzzle with a fresh clock and no strikes
+                ui.add_space(20.0);
+                let retry_text = RichText::new("Retry this puzzle")
+                    .font(FontId::new(30.0, FontFamily::Proportional));
+                if ui.add(Button::new(retry_text).min_size(Vec2::new(50.0, 20.0))).clicked() {
+                    self.retry_puzzle();
+                }
+
+                self.drill_button(ui);
+            });
+        });
     }
 
     // displays win screen when the user has correctly filled the entire board
@@ -472,19 +2868,85 @@ impl Sudoku {
         // if self.game_over has not been set, record the time elapsed and store it in self.time_elapsed
             // then set self.game_over to true so the program only enters this if block once
         if !self.game_over {
-            if let Some(time) = self.timer_start {
-                self.time_elapsed = time.elapsed();
-            } else { }
+            self.time_elapsed = self.elapsed();
             self.game_over = true;
+            let all_time = self.stats.difficulties.get(&self.difficulty).and_then(|stats| stats.best_time);
+            self.previous_bests = (self.session_best.get(&self.difficulty).copied(), all_time);
+            self.record_game_end(true, self.ranked);
+            // like the all-time best, the session best only counts ranked wins that aren't retries
+            if self.ranked && !self.is_retry {
+                let secs = self.time_elapsed.as_secs();
+                let best = self.session_best.entry(self.difficulty.clone()).or_insert(secs);
+                *best = (*best).min(secs);
+            }
         }
 
         // display ui elements
         CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
-                ui.heading("You Win!");
+                ui.heading(RichText::new("You Win!").color(Color32::from_rgb(0, 124, 255)));
+                unranked_badge(ui, self.ranked);
+
+                // the score is the one number to compare wins by, so it gets the biggest text -- it isn't shown for difficulties without one
+                if let Some(score) = self.compute_score() {
+                    ui.heading(RichText::new(format!("Score: {}", score))
+                        .font(FontId::new(36.0, FontFamily::Proportional)));
+                    if let Some(best) = self.stats.difficulties.get(&self.difficulty).and_then(|stats| stats.best_score) {
+                        ui.label(format!("Best {} score: {}", self.difficulty, best));
+                    }
+                }
+
+                // display how many seconds it took the user to complete the puzzle, with tenths if the clock showed them
+                    // both are cut off rather than rounded, like the recorded time (see clock::format_tenths)
+                let final_time = if self.render_budget.shows_tenths() { clock::format_tenths(self.time_elapsed) } else { self.time_elapsed.as_secs().to_string() };
+                ui.label(format!("You completed the puzzle in {} seconds", final_time));
+                if let Some(budget) = self.time_budget {
+                    ui.label(format!("Time left on the clock: {}", format_time(budget.saturating_sub(self.time_elapsed).as_secs())));
+                }
+
+                // the session best and all-time best, with the one(s) this win just beat called out
+                if self.ranked && !self.is_retry {
+                    let secs = self.time_elapsed.as_secs();
+                    let (session, all_time) = self.previous_bests;
+                    for (label, previous) in [("Session best", session), ("All-time best", all_time)] {
+                        let best = previous.map_or(secs, |best| best.min(secs));
+                        if previous.is_none_or(|best| secs < best) {
+                            ui.label(RichText::new(format!("{}: {} -- new best!", label, format_time(best))).strong().color(Color32::from_rgb(230, 170, 30)));
+                        }
+                        else {
+                            ui.label(format!("{}: {}", label, format_time(best)));
+                        }
+                    }
+                }
+
+                // retries and unranked games aren't recorded, so they aren't ranked either
+                if self.settings.show_time_rank && self.ranked && !self.is_retry {
+                    match self.time_rank {
+                        Some(percent) => ui.label(format!("Faster than {}% of your {} solves", percent, self.difficulty)),
+                        None => ui.label(format!("Not enough {} solves to rank this time yet (it takes {})", self.difficulty, stats::MIN_RANKED_WINS)),
+                    };
+                }
+
+                // winning without a single strike earns a badge
+                if self.strikes == 0 {
+                    ui.add_space(10.0);
+                    let flawless_count = self.stats.difficulties.get(&self.difficulty).map_or(0, |stats| stats.flawless_wins);
+                    ui.heading(RichText::new("Flawless!")
+                        .font(FontId::new(28.0, FontFamily::Proportional))
+                        .color(Color32::from_rgb(230, 170, 30)));
+                    ui.label(format!("Flawless {} wins: {}", self.difficulty, flawless_count));
+                }
+
+                ui.add_space(10.0);
+                self.puzzle_note_editor(ui);
+                self.collection_adder(ui);
 
-                // display how many seconds it took the user to complete the puzzle
-                ui.label(format!("You completed the puzzle in {} seconds", self.time_elapsed.as_secs()));
+                // show the completed board
+                ui.add_space(20.0);
+                thumbnail::draw(ui, &self.starting_grid, &self.player_grid, &self.solution_grid, 22.0, &ThumbnailColors::standard());
+
+                ui.add_space(20.0);
+                self.solve_timeline(ui);
 
                 // create the button text
                 let button_text = RichText::new("Play Again")
@@ -502,8 +2964,327 @@ impl Sudoku {
                 if button.clicked() {
                     self.reset_gamestate();
                 }
+
+                self.drill_button(ui);
+            });
+        });
+    }
+
+    // the screen shown at the end of each puzzle of a marathon, in place of the win and lose screens
+        // between puzzles it shows the running totals, and once the marathon is over (every puzzle solved, or one lost) its summary
+    fn marathon_screen(&mut self, ctx: &Context, won: bool) {
+        // the first time the screen is shown, stop the clock and record the puzzle -- then the marathon too, if this puzzle ended it
+        if !self.game_over {
+            self.time_elapsed = self.elapsed();
+            self.game_over = true;
+            self.record_game_end(won, self.ranked);
+            if let Some(marathon) = &mut self.marathon {
+                marathon.finish_puzzle(self.time_elapsed.as_secs(), self.strikes, won);
+                if marathon.is_over() && self.ranked {
+                    self.stats.record_marathon(&marathon.record_key(), !marathon.lost, marathon.total_secs(), marathon.total_strikes());
+                    self.stats.save();
+                }
+            }
+        }
+
+        let mut next_puzzle = false;
+        let mut back_to_menu = false;
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                let Some(marathon) = &self.marathon else { return };
+                let count = marathon.queue.len();
+                let total_secs = marathon.total_secs();
+                let total_strikes = marathon.total_strikes();
+                if !marathon.is_over() {
+                    ui.heading(RichText::new(format!("Puzzle {} of {} solved!", marathon.results.len(), count)).color(Color32::from_rgb(0, 124, 255)));
+                    unranked_badge(ui, self.ranked);
+                    ui.label(format!("This puzzle: {} with {} strikes", format_time(self.time_elapsed.as_secs()), self.strikes));
+                    ui.label(RichText::new(format!("Marathon so far: {} with {} strikes", format_time(total_secs), total_strikes)).strong());
+                    if marathon.strike_rule == StrikeRule::Shared && self.options.points.is_none() {
+                        ui.label(format!("{} strikes left for the rest of the marathon", (self.options.strike_limit as u32).saturating_sub(self.strikes)));
+                    }
+
+                    ui.add_space(20.0);
+                    thumbnail::draw(ui, &self.starting_grid, &self.player_grid, &self.solution_grid, 22.0, &ThumbnailColors::standard());
+
+                    ui.add_space(40.0);
+                    let next_text = RichText::new("Next puzzle")
+                        .font(FontId::new(30.0, FontFamily::Proportional));
+                    next_puzzle = ui.add(Button::new(next_text).min_size(Vec2::new(50.0, 20.0))).clicked();
+                    ui.add_space(20.0);
+                    if ui.button("Stop marathon").clicked() {
+                        self.confirm_abandon = true;
+                    }
+                    return;
+                }
+
+                // the summary, with how each puzzle went
+                if marathon.lost {
+                    ui.heading(RichText::new("Marathon over").color(Color32::from_rgb(255, 60, 110)));
+                }
+                else {
+                    ui.heading(RichText::new("Marathon complete!").color(Color32::from_rgb(0, 124, 255)));
+                }
+                unranked_badge(ui, self.ranked);
+                ui.label(format!("You solved {} of {} {} puzzles", marathon.solved(), count, marathon.difficulty));
+                ui.heading(format!("Total time: {}", format_time(total_secs)));
+                ui.label(format!("Total strikes: {} ({})", total_strikes, marathon.strike_rule.name().to_lowercase()));
+                if marathon.solved() > 0 {
+                    let solved_secs: u64 = marathon.results.iter().take(marathon.solved()).map(|result| result.secs).sum();
+                    ui.label(format!("Average time per solved puzzle: {}", format_time(solved_secs / marathon.solved() as u64)));
+                }
+                ui.add_space(10.0);
+                Grid::new("marathon_results").striped(true).show(ui, |ui| {
+                    for (i, result) in marathon.results.iter().enumerate() {
+                        let lost = marathon.lost && i + 1 == marathon.results.len();
+                        ui.label(format!("Puzzle {}", i + 1));
+                        ui.label(format_time(result.secs));
+                        ui.label(format!("{} strikes", result.strikes));
+                        ui.label(if lost { "lost" } else { "solved" });
+                        ui.end_row();
+                    }
+                });
+
+                // the records for this kind of marathon, which already include this one if it was ranked
+                if let Some(record) = self.stats.marathons.get(&marathon.record_key()) {
+                    ui.add_space(10.0);
+                    ui.label(format!("{}: {} finished, {} failed", marathon.record_key(), record.finished, record.failed));
+                    if let (Some(best), Some(fewest)) = (record.best_secs, record.fewest_strikes) {
+                        let new_best = !marathon.lost && self.ranked && best == total_secs;
+                        let text = format!("Best time: {} -- fewest strikes: {}{}", format_time(best), fewest, if new_best { " -- new best!" } else { "" });
+                        ui.label(RichText::new(text).color(if new_best { Color32::from_rgb(230, 170, 30) } else { ui.visuals().text_color() }));
+                    }
+                }
+
+                ui.add_space(40.0);
+                let menu_text = RichText::new("Back to menu")
+                    .font(FontId::new(30.0, FontFamily::Proportional));
+                back_to_menu = ui.add(Button::new(menu_text).min_size(Vec2::new(50.0, 20.0))).clicked();
+            });
+        });
+
+        if next_puzzle {
+            self.next_marathon_puzzle();
+        }
+        else if back_to_menu {
+            self.marathon = None;
+            self.reset_gamestate();
+        }
+    }
+
+    // asks whether to save the marathon for later or give it up, once "Stop marathon" has been clicked
+    fn abandon_dialog(&mut self, ctx: &Context) {
+        if !self.confirm_abandon {
+            return;
+        }
+        let mut save = None;
+        egui::Window::new("Stop the marathon?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("A saved marathon can be picked back up from the start screen, with the puzzle you're on.");
+                ui.label("Giving up records the marathon as failed.");
+                ui.horizontal(|ui| {
+                    if ui.button("Save for later").clicked() {
+                        save = Some(true);
+                    }
+                    if ui.button("Give up").clicked() {
+                        save = Some(false);
+                    }
+                    if ui.button("Keep playing").clicked() {
+                        self.confirm_abandon = false;
+                    }
+                });
+            });
+        if let Some(save) = save {
+            self.confirm_abandon = false;
+            self.stop_marathon(save);
+        }
+    }
+
+    // shows how the solve went over time: a bar per tenth of the game with the number of cells filled in it, and a red line at each strike
+        // a steady solve has even bars, while getting stuck shows up as empty bars followed by a burst
+    fn solve_timeline(&self, ui: &mut egui::Ui) {
+        const SLICES: usize = 10;
+        const HEIGHT: f32 = 60.0;
+        let duration_ms = self.time_elapsed.as_millis() as u64;
+        let values: Vec<f32> = actions::fills_per_slice(&self.actions, duration_ms, SLICES).into_iter().map(|count| count as f32).collect();
+        let labels: Vec<String> = (1..=SLICES).map(|i| format_time(duration_ms * i as u64 / SLICES as u64 / 1000)).collect();
+        ui.label("Cells filled over the game");
+        let response = chart::draw_bar_chart(ui, &values, &labels, 28.0, HEIGHT);
+        for action in self.actions.iter().filter(|action| !action.correct()) {
+            chart::draw_marker(ui, &response, HEIGHT, action.at_ms as f32 / duration_ms.max(1) as f32, Color32::from_rgb(255, 60, 110));
+        }
+        if self.actions.iter().any(|action| !action.correct()) {
+            response.on_hover_text("Red lines are strikes");
+        }
+    }
+
+    // the percentage of the 81 cells the user has correct, rounded to a whole number
+    fn fill_percentage(&self) -> u8 {
+        // iterate through self.player_grid and self.solution_grid, and count how many of the 81 cells the user had correct
+        let mut count = 0.0;
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.player_grid[row][col] == self.solution_grid[row][col] {
+                    count += 1.0;
+                }
+            }
+        }
+        // calculate the percentage of cells the user had correct
+        let percentage: f32 = (count / 81.0) * 100.0;
+        percentage.round() as u8
+    }
+
+    // records a finished game in stats and saves them -- this is the only place a game's result is written
+        // a loss remembers the puzzle so it can be retried later, and a win forgets the last lost puzzle if this was it
+        // unranked games return straight away, so they never touch the stats file
+    fn record_game_end(&mut self, won: bool, ranked: bool) {
+        self.time_rank = None;
+        if !ranked {
+            return;
+        }
+        let puzzle = board::grid_to_string(&self.starting_grid);
+        if won {
+            self.time_rank = self.stats.difficulties.get(&self.difficulty).and_then(|stats| stats.faster_than(self.time_elapsed.as_secs()));
+            self.stats.record_win(&self.difficulty, self.time_elapsed.as_secs(), self.compute_score(), self.strikes == 0, self.is_retry);
+            if self.stats.last_lost.as_ref().is_some_and(|lost| lost.puzzle == puzzle) {
+                self.stats.last_lost = None;
+            }
+        }
+        else {
+            self.stats.record_loss(&self.difficulty, self.is_retry);
+            if self.settings.record_loss_details {
+                self.stats.losses.push(LossRecord {
+                    difficulty: self.difficulty.clone(),
+                    survival_secs: self.time_elapsed.as_secs(),
+                    fill_percent: self.fill_percentage(),
+                });
+            }
+            self.stats.last_lost = Some(LostPuzzle {
+                id: Some(self.puzzle_id.clone()),
+                parity: (self.parity != board::NO_PARITY).then(|| board::grid_to_string(&self.parity)),
+                difficulty: self.difficulty.clone(),
+                puzzle,
+                solution: board::grid_to_string(&self.solution_grid),
+            });
+        }
+        self.stats.days.entry(clock::today()).or_default().games += 1;
+        self.stats.games.push(GameRecord {
+            difficulty: self.difficulty.clone(),
+            day: clock::today(),
+            secs: self.time_elapsed.as_secs(),
+            strikes: self.strikes,
+            hints: self.hints_used,
+            won,
+            retry: self.is_retry,
+            puzzle_id: self.puzzle_id.clone(),
+            variant: self.variant,
+        });
+        self.stats.played_puzzles.insert(self.puzzle_id.clone());
+        // the game's mistakes are kept (not moved) so they can still be drilled from the game over screen
+        self.stats.mistakes.extend(self.mistakes.iter().copied());
+        self.stats.digits.add(&DigitStats::from_actions(&self.actions));
+        self.stats.save();
+    }
+
+    // shows the note attached to the current puzzle, with a text box to write or change it
+        // the note is only saved (to stats, by puzzle id) when the user clicks Save, and an empty note removes it
+    fn puzzle_note_editor(&mut self, ui: &mut egui::Ui) {
+        let saved = self.stats.puzzle_notes.get(&self.puzzle_id).cloned();
+        let Some(draft) = &mut self.note_draft else {
+            if let Some(note) = &saved {
+                ui.label(RichText::new(format!("Your note: {}", note)).italics());
+            }
+            if ui.button(if saved.is_some() { "Edit note" } else { "Add a note to this puzzle" }).clicked() {
+                self.note_draft = Some(saved.unwrap_or_default());
+            }
+            return;
+        };
+        ui.add(egui::TextEdit::singleline(draft).char_limit(stats::MAX_PUZZLE_NOTE).hint_text("e.g. lovely X-wing in box 7"));
+        let mut close = false;
+        layout::centered_row(ui, "note_buttons", |ui| {
+            if ui.button("Save").clicked() {
+                let note = draft.trim().to_string();
+                if note.is_empty() {
+                    self.stats.puzzle_notes.remove(&self.puzzle_id);
+                }
+                else {
+                    self.stats.puzzle_notes.insert(self.puzzle_id.clone(), note);
+                }
+                self.stats.save();
+                close = true;
+            }
+            close |= ui.button("Cancel").clicked();
+        });
+        if close {
+            self.note_draft = None;
+        }
+    }
+
+    // shows a row for adding the puzzle that was just played to a collection, which is made if it doesn't exist yet
+        // a rotated or reflected board is added as it was played, without the original puzzle's id
+    fn collection_adder(&mut self, ui: &mut egui::Ui) {
+        let mut add = false;
+        layout::centered_row(ui, "collection_adder", |ui| {
+            ui.label("Add this puzzle to a collection:");
+            ui.add(egui::TextEdit::singleline(&mut self.collection_name).desired_width(150.0).hint_text("Collection name"));
+            add = ui.button("Add").clicked();
+        });
+        if add {
+            let name = self.collection_name.trim().to_string();
+            let puzzle = Puzzle {
+                id: (!self.variant).then(|| self.puzzle_id.clone()),
+                puzzle: board::grid_to_string(&self.starting_grid),
+                solution: board::grid_to_string(&self.solution_grid),
+                parity: (self.parity != board::NO_PARITY).then(|| board::grid_to_string(&self.parity)),
+                metadata: self.puzzle_metadata.clone(),
+            };
+            self.collection_message = Some(match collection::check_name(&name) {
+                Err(err) => err,
+                Ok(()) => {
+                    let mut collection = Collection::load_or_new(&name);
+                    if !collection.add(puzzle) {
+                        format!("This puzzle is already in {}", name)
+                    }
+                    else {
+                        match collection.save() {
+                            Ok(()) => format!("Added to {} ({} puzzles)", name, collection.puzzles.len()),
+                            Err(err) => err,
+                        }
+                    }
+                }
             });
+            self.collections = Collection::names();
+        }
+        if let Some(message) = &self.collection_message {
+            ui.label(message);
+        }
+    }
+
+    // loads the collection file at collection_path into the collections folder, and reports how many of its puzzles were kept
+    fn load_collection(&mut self) {
+        self.collection_message = Some(match Collection::import(std::path::Path::new(self.collection_path.trim())) {
+            Ok((collection, skipped)) if skipped.is_empty() => format!("Loaded {} with {} puzzles", collection.name, collection.puzzles.len()),
+            Ok((collection, skipped)) => format!("Loaded {} with {} puzzles, skipped {}:\n{}",
+                collection.name, collection.puzzles.len(), skipped.len(), skipped.join("\n")),
+            Err(err) => err,
         });
+        self.collections = Collection::names();
+    }
+
+    // shows the "Drill mistakes" button on the game over screens, if the game had any mistakes to drill
+    fn drill_button(&mut self, ui: &mut egui::Ui) {
+        if self.mistakes.is_empty() {
+            return;
+        }
+        ui.add_space(20.0);
+        let drill_text = RichText::new("Drill mistakes")
+            .font(FontId::new(30.0, FontFamily::Proportional));
+        if ui.add(Button::new(drill_text).min_size(Vec2::new(50.0, 20.0))).clicked() {
+            self.drill_mistakes();
+        }
     }
     
     fn reset_gamestate(&mut self) {
@@ -516,14 +3297,616 @@ impl Sudoku {
         self.time_elapsed = Duration::from_secs(0);
         self.timer_start = None;
         self.game_over = false;
+        self.history.clear();
+        self.is_retry = false;
+        self.variant = false;
+        self.pending_difficulty = None;
+        self.generated_puzzle = false;
+        self.reveal_mistakes = false;
+        self.pb_pace_notified = false;
+        self.puzzle_id.clear();
+        self.puzzle_metadata = PuzzleMetadata::default();
+        self.parity = board::NO_PARITY;
+        self.time_budget = None;
+        self.warm_up = None;
+        self.last_strike_recovery = Instant::now();
+        self.play_tick = None;
+        self.mistakes.clear();
+        self.color_tags = [[None; 9]; 9];
+        self.wrong_entries = [[None; 9]; 9];
+        self.explained_conflict = None;
+        self.pending_digit = None;
+        self.notes = [[0; 9]; 9];
+        self.note_hint = None;
+        self.hints_used = 0;
+        self.mistake_review = false;
+        self.note_draft = None;
+        self.collection_message = None;
+        self.last_progress = Instant::now();
+        self.stuck_nudge = None;
+        self.stuck_dismissed = false;
+        self.checkpoint = None;
+        self.checkpoint_strikes = 0;
+        self.checkpoint_mistakes = 0;
+        self.checkpoint_actions = 0;
+        self.actions.clear();
+        self.sandbox = None;
+    }
+
+    // restarts the current puzzle -- the board, clock, strikes, and history are reset, but the puzzle and difficulty are kept
+        // the new attempt is marked as a retry so it is recorded separately in stats
+    fn retry_puzzle(&mut self) {
+        let difficulty = self.difficulty.clone();
+        let starting_grid = self.starting_grid;
+        let solution_grid = self.solution_grid;
+        let generated_puzzle = self.generated_puzzle;
+        let options = self.options;
+        let ranked = self.ranked;
+        let puzzle_id = std::mem::take(&mut self.puzzle_id);
+        let puzzle_metadata = std::mem::take(&mut self.puzzle_metadata);
+        let parity = self.parity;
+        let time_budget = self.time_budget;
+        let variant = self.variant;
+        self.reset_gamestate();
+        self.time_budget = time_budget;
+        self.variant = variant;
+        self.puzzle_id = puzzle_id;
+        self.puzzle_metadata = puzzle_metadata;
+        self.parity = parity;
+        self.generated_puzzle = generated_puzzle;
+        self.options = options;
+        self.show_errors = options.highlight_errors;
+        self.ranked = ranked;
+        self.difficulty = difficulty;
+        self.starting_grid = starting_grid;
+        self.player_grid = starting_grid;
+        self.solution_grid = solution_grid;
+        self.is_retry = true;
+        self.hints_left = self.stats.hint_budget(&self.difficulty);
+    }
+
+    // starts a practice game on the same board, where only the cells the user got wrong are left blank
+        // every other cell is filled in from the solution, so the drill is played unranked -- it keeps the puzzle's id, and
+        // recording it would file the drill's board as the last lost puzzle and count its entries twice in the heatmap and history
+    fn drill_mistakes(&mut self) {
+        let mut drill = self.solution_grid;
+        for mistake in &self.mistakes {
+            drill[mistake.row][mistake.col] = '.';
+        }
+        self.starting_grid = drill;
+        self.retry_puzzle();
+        self.ranked = false;
+    }
+
+    // loads the last lost puzzle from stats (which may be from an earlier session) and retries it
+    fn retry_lost_puzzle(&mut self) {
+        if let Some(lost) = self.stats.last_lost.clone() {
+            self.difficulty = lost.difficulty;
+            self.puzzle_id = lost.id.unwrap_or_else(|| board::puzzle_hash(&lost.puzzle));
+            self.starting_grid = board::string_to_grid(&lost.puzzle);
+            self.solution_grid = board::string_to_grid(&lost.solution);
+            self.parity = lost.parity.as_deref().map_or(board::NO_PARITY, board::string_to_grid);
+            self.resolve_game_options();
+            self.retry_puzzle();
+        }
+    }
+
+    // starts a new marathon with what was picked in marathon_setup, or leaves a message saying why it couldn't
+    fn start_marathon(&mut self) {
+        match Marathon::new(&self.marathon_setup, &self.stats.played_puzzles) {
+            Ok(marathon) => {
+                self.marathon_message = None;
+                self.marathon = Some(marathon);
+                self.next_marathon_puzzle();
+            }
+            Err(err) => self.marathon_message = Some(err),
+        }
+    }
+
+    // starts the marathon's next puzzle as a fresh game -- with shared strikes, it only gets what is left of the strike limit
+        // if the puzzle can't be read (e.g. the puzzle file changed since the marathon was saved), the marathon ends with a toast saying why
+    fn next_marathon_puzzle(&mut self) {
+        let Some(next) = self.marathon.as_ref().map(|marathon| marathon.next_puzzle().map(|puzzle| (puzzle, marathon.difficulty.clone()))) else { return };
+        self.reset_gamestate();
+        match next {
+            Ok((puzzle, difficulty)) => {
+                self.difficulty = difficulty;
+                self.start_puzzle(puzzle, self.settings.transform_puzzles);
+                if let Some(marathon) = &self.marathon {
+                    self.options.strike_limit = marathon.strike_limit(self.options.strike_limit);
+                }
+            }
+            Err(err) => {
+                self.marathon = None;
+                self.show_toast(err);
+            }
+        }
+    }
+
+    // the marathon as it would be saved right now, with the puzzle being played if there is one -- None outside of a marathon
+    fn marathon_to_save(&self) -> Option<Marathon> {
+        let mut marathon = self.marathon.clone()?;
+        marathon.game = self.game_in_progress().then(|| self.to_saved_game());
+        Some(marathon)
+    }
+
+    // stops the marathon part of the way through -- it is either saved so it can be resumed from the difficulty screen,
+        // or given up, which records it as failed (the puzzle being played isn't recorded, just like closing a normal game)
+    fn stop_marathon(&mut self, save: bool) {
+        if save {
+            let Some(marathon) = self.marathon_to_save() else { return };
+            if let Err(err) = marathon.save() {
+                self.show_toast(err);
+                return;
+            }
+            self.saved_marathon = Some(marathon);
+        }
+        else if let Some(marathon) = self.marathon.as_ref().filter(|_| self.ranked) {
+            self.stats.record_marathon(&marathon.record_key(), false, marathon.total_secs(), marathon.total_strikes());
+            self.stats.save();
+        }
+        self.marathon = None;
+        self.reset_gamestate();
+    }
+
+    // picks the saved marathon back up -- at the puzzle that was being played, or the next one if it was saved between puzzles
+    fn resume_marathon(&mut self) {
+        let Some(mut marathon) = self.saved_marathon.take() else { return };
+        Marathon::delete();
+        let game = marathon.game.take();
+        self.marathon = Some(marathon);
+        match game {
+            Some(game) => self.restore_game(game),
+            None => self.next_marathon_puzzle(),
+        }
+    }
+
+    // reads a puzzle from the photo at photo_path and starts it, or leaves a message saying why it couldn't
+        // imported puzzles have no real difficulty, so they are played as "Imported" and are never recorded in stats
+    fn import_photo(&mut self) {
+        let result = source::photo_source().read(std::path::Path::new(self.photo_path.trim()))
+            .and_then(|grid| source::validate(&grid).map(|solution| (grid, solution)));
+        match result {
+            Ok((grid, solution)) => {
+                self.photo_message = None;
+                self.difficulty = "Imported".to_string();
+                self.puzzle_id = board::puzzle_hash(&board::grid_to_string(&grid));
+                self.starting_grid = grid;
+                self.player_grid = grid;
+                self.solution_grid = solution;
+                self.parity = board::NO_PARITY;
+                self.resolve_game_options();
+                self.ranked = false;
+            }
+            Err(err) => self.photo_message = Some(err),
+        }
+    }
+
+    // works out the options for a new game at the current difficulty, then clears the one-off overrides so they only apply once
+        // retries keep the options of the game being retried, so this isn't called by retry_puzzle
+    fn resolve_game_options(&mut self) {
+        self.options = settings::resolve_options(&self.settings, &self.difficulty, &self.setup.overrides);
+        self.show_errors = self.options.highlight_errors;
+        self.setup.overrides = OptionOverrides::default();
+        self.ranked = !self.setup.unranked;
+        self.time_budget = self.setup.blitz.then(|| self.settings.blitz_budget(&self.difficulty));
+        self.hints_left = self.stats.hint_budget(&self.difficulty);
+    }
+
+    // the score for winning the current game, from its difficulty, time, strikes, and hints (see score.rs for the formula)
+        // None for a difficulty that has no score
+    fn compute_score(&self) -> Option<i64> {
+        score::compute(&self.difficulty, self.elapsed().as_secs(), self.strikes, self.hints_used)
+    }
+
+    // returns true if the game has been lost to mistakes: the points ran out in a points game, or the strike limit was reached otherwise
+        // this is the only place that decides, so points games and strike games can't disagree about when a game ends
+    fn struck_out(&self) -> bool {
+        match self.options.points {
+            Some(rule) => rule.remaining(self.strikes) == 0,
+            None => self.strikes >= self.options.strike_limit as u32,
+        }
+    }
+
+    // adds the time since the last frame to today's play time -- ranked games count toward it, but only while the window is focused
+        // the time is measured between frames rather than taken from the frame time, since frames can be a second
+        // or more apart when the clock's repaints are slowed down or off
+    fn count_play_time(&mut self, focused: bool) {
+        if !self.ranked || !focused {
+            self.play_tick = None;
+            return;
+        }
+        let now = Instant::now();
+        if let Some(last) = self.play_tick {
+            let end = clock::unix_secs();
+            self.stats.add_play_time(end, now.duration_since(last).as_secs_f64(), clock::utc_offset_secs(end));
+            self.stats_unsaved = true;
+        }
+        self.play_tick = Some(now);
+    }
+
+    // returns true if the board has been solved -- the player's grid matches the solution grid exactly, outside of a sandbox
+    fn is_won(&self) -> bool {
+        self.sandbox.is_none() && self.player_grid == self.solution_grid
+    }
+
+    // returns true if this is a blitz game and its countdown has run out
+    fn out_of_time(&self) -> bool {
+        self.time_budget.is_some_and(|budget| self.elapsed() >= budget)
+    }
+
+    // how long the current game has been played -- time_elapsed holds the time from before the clock was last started
+        // (e.g. before the game was saved), and once the game is over it holds the final time
+    fn elapsed(&self) -> Duration {
+        match self.timer_start {
+            Some(start) if !self.game_over => clock::add_elapsed(self.time_elapsed, start.elapsed()),
+            _ => self.time_elapsed,
+        }
+    }
+
+    // returns true while something in the running game changes with time alone: a blitz countdown, a strike waiting to come back
+        // in arcade mode, or the stuck nudge waiting to be checked for
+    fn runs_on_clock(&self) -> bool {
+        let stuck_check = self.settings.stuck_nudge && self.stuck_nudge.is_none() && !self.stuck_dismissed;
+        !self.game_over && (self.time_budget.is_some() || (self.options.arcade && self.strikes > 0) || stuck_check)
+    }
+
+    // returns true if a game is being played and isn't over yet
+    fn game_in_progress(&self) -> bool {
+        if let Some(samurai) = &self.samurai {
+            return samurai.in_progress();
+        }
+        !self.difficulty.is_empty() && !self.game_over && self.timer_start.is_some()
+    }
+
+    // saves the current game so it can be resumed the next time the app is opened -- in a marathon, the whole marathon is saved with it
+        // a samurai game is saved to its own file (see SavedSamurai), so it doesn't replace a saved 9x9 game
+    fn save_game(&self) -> Result<(), String> {
+        self.save_game_in(std::path::Path::new("."))
+    }
+
+    // the same as save_game, but into the save files in dir
+    fn save_game_in(&self, dir: &std::path::Path) -> Result<(), String> {
+        if let Some(samurai) = &self.samurai {
+            return samurai.to_saved().save_to(&save_path(dir, samurai::SAVE_PATH));
+        }
+        match self.marathon_to_save() {
+            Some(marathon) => marathon.save_to(&save_path(dir, marathon::MARATHON_PATH)),
+            None => self.to_saved_game().save_to(&save_path(dir, savegame::SAVEGAME_PATH)),
+        }
+    }
+
+    // writes everything that would otherwise be lost on exit into the save files in dir (the working directory, outside of tests),
+        // and returns what was written: the stats and settings if they have changed, then the game in progress, or a marathon
+        // closed between puzzles so it picks up at its next puzzle
+        // settings aren't written while setup is still open, so setup runs again on the next launch (see Onboarding), and the
+        // game isn't saved once the quit dialog has been answered, since it either saved the game already or threw it away
+    fn flush(&self, dir: &std::path::Path) -> Vec<&'static str> {
+        let mut written = Vec::new();
+        if self.stats_unsaved {
+            self.stats.save_to(&save_path(dir, stats::STATS_PATH));
+            written.push("stats");
+        }
+        if self.settings_unsaved && self.onboarding.is_none() {
+            self.settings.save_to(&save_path(dir, settings::SETTINGS_PATH));
+            written.push("settings");
+        }
+        if self.game_in_progress() && !self.allow_close {
+            match self.save_game_in(dir) {
+                Ok(()) => written.push("game in progress"),
+                Err(err) => println!("{}", err),
+            }
+        }
+        else if let Some(marathon) = self.marathon.as_ref().filter(|marathon| !marathon.is_over() && !self.allow_close) {
+            match marathon.save_to(&save_path(dir, marathon::MARATHON_PATH)) {
+                Ok(()) => written.push("marathon"),
+                Err(err) => println!("{}", err),
+            }
+        }
+        if written.is_empty() {
+            written.push("nothing");
+        }
+        written
+    }
+
+    // picks the saved samurai game back up, and deletes the save so it can't be resumed twice
+    fn resume_samurai(&mut self) {
+        let Some(saved) = self.saved_samurai.take() else { return };
+        match Samurai::from_saved(saved) {
+            Ok(game) => self.samurai = Some(game),
+            Err(err) => self.show_toast(err),
+        }
+        SavedSamurai::delete();
+    }
+
+    // the current game as a saved game -- in a sandbox, the real game is saved and the sandbox is thrown away
+    fn to_saved_game(&self) -> SavedGame {
+        SavedGame {
+            difficulty: self.difficulty.clone(),
+            puzzle_id: self.puzzle_id.clone(),
+            starting: board::grid_to_string(&self.starting_grid),
+            player: board::grid_to_string(self.sandbox.as_ref().map_or(&self.player_grid, |sandbox| &sandbox.player_grid)),
+            solution: board::grid_to_string(&self.solution_grid),
+            parity: board::grid_to_string(&self.parity),
+            strikes: self.sandbox.as_ref().map_or(self.strikes, |sandbox| sandbox.strikes),
+            elapsed_secs: self.elapsed().as_secs(),
+            time_budget_secs: self.time_budget.map(|budget| budget.as_secs()),
+            options: self.options,
+            ranked: self.ranked,
+            is_retry: self.is_retry,
+            variant: self.variant,
+            hints_left: Some(self.hints_left),
+            hints_used: self.hints_used,
+            metadata: self.puzzle_metadata.clone(),
+            mistakes: self.mistakes.clone(),
+            actions: self.actions.clone(),
+        }
+    }
+
+    // picks the saved game back up where it was left, and deletes the save so it can't be resumed twice
+    fn resume_saved_game(&mut self) {
+        let Some(saved) = self.saved_game.take() else { return };
+        self.restore_game(saved);
+        SavedGame::delete();
+    }
+
+    // puts a saved game back on the board, with its clock, strikes, and logs
+    fn restore_game(&mut self, saved: SavedGame) {
+        self.reset_gamestate();
+        self.difficulty = saved.difficulty;
+        self.puzzle_id = saved.puzzle_id;
+        self.puzzle_metadata = saved.metadata;
+        self.starting_grid = board::string_to_grid(&saved.starting);
+        self.player_grid = board::string_to_grid(&saved.player);
+        self.solution_grid = board::string_to_grid(&saved.solution);
+        self.parity = board::string_to_grid(&saved.parity);
+        self.strikes = saved.strikes;
+        // the clock starts again from the saved time the first time the game is shown
+        self.time_elapsed = Duration::from_secs(saved.elapsed_secs);
+        self.time_budget = saved.time_budget_secs.map(Duration::from_secs);
+        self.options = saved.options;
+        self.show_errors = self.options.highlight_errors;
+        self.ranked = saved.ranked;
+        self.is_retry = saved.is_retry;
+        self.variant = saved.variant;
+        self.hints_left = saved.hints_left.unwrap_or_else(|| self.stats.hint_budget(&self.difficulty));
+        self.hints_used = saved.hints_used;
+        self.mistakes = saved.mistakes;
+        self.actions = saved.actions;
+    }
+
+    // intercepts closing the window in the middle of a game, and asks whether to quit, save and quit, or keep playing
+    fn quit_dialog(&mut self, ctx: &Context) {
+        if ctx.input(|input| input.viewport().close_requested())
+            && self.settings.confirm_quit && !self.allow_close && self.game_in_progress() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_quit = true;
+        }
+        if !self.confirm_quit {
+            return;
+        }
+
+        egui::Window::new("Quit mid-game?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("This game isn't finished yet.");
+                ui.horizontal(|ui| {
+                    if ui.button("Quit and lose progress").clicked() {
+                        self.allow_close = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Save and quit").clicked() {
+                        match self.save_game() {
+                            Ok(()) => {
+                                self.allow_close = true;
+                                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                            }
+                            Err(err) => self.show_toast(err),
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_quit = false;
+                    }
+                });
+            });
+    }
+}
+
+// the background of a cell with a parity mark: the cell's fill, with a shaded square for even or a circle for odd
+fn parity_marker(rect: Rect, parity: char, fill: Color32, dark_mode: bool) -> egui::Shape {
+    let mark = if dark_mode { Color32::from_gray(100) } else { Color32::from_gray(210) };
+    let mut shapes = vec![egui::Shape::rect_filled(rect, 4.0, fill)];
+    if parity == 'e' {
+        shapes.push(egui::Shape::rect_filled(rect.shrink(rect.width() * 0.15), 4.0, mark));
+    }
+    else {
+        shapes.push(egui::Shape::circle_stroke(rect.center(), rect.width() * 0.36, egui::Stroke::new(3.0, mark)));
+    }
+    egui::Shape::Vec(shapes)
+}
+
+// the DigitAction enum is what pressing a digit key does on the board
+    // Tag is the color tag for Ctrl + 1/2/3, or None for the other digits, which don't have one
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DigitAction {
+    Sweep,
+    Tag(Option<ColorTag>),
+    Note,
+    Enter,
+}
+
+// what a digit key does, given the modifiers held -- Alt sweeps, Ctrl (Cmd on mac) tags, and Shift turns a note on or off
+    // notes mode makes a plain digit turn a note on or off too, so both go through Sudoku::toggle_note and share one undo history
+fn digit_key_action(modifiers: egui::Modifiers, notes_mode: bool, digit: char) -> DigitAction {
+    if modifiers.alt {
+        DigitAction::Sweep
+    }
+    else if modifiers.command {
+        DigitAction::Tag(match digit {
+            '1' => Some(ColorTag::Yellow),
+            '2' => Some(ColorTag::Green),
+            '3' => Some(ColorTag::Purple),
+            _ => None,
+        })
+    }
+    else if modifiers.shift || notes_mode {
+        DigitAction::Note
+    }
+    else {
+        DigitAction::Enter
+    }
+}
+
+// the path of one of the save files (given as its *_PATH constant) in dir instead of the working directory
+fn save_path(dir: &std::path::Path, path: &str) -> String {
+    dir.join(std::path::Path::new(path).file_name().unwrap_or_default()).to_string_lossy().into_owned()
+}
+
+// returns true if a digit typed into cell at now should be dropped, because the last digit went into the same cell less than
+    // debounce ago (see Sudoku::digit_pressed) -- a debounce of 0 never drops anything
+fn is_bounce(last_press: Option<((usize, usize), Instant)>, cell: (usize, usize), now: Instant, debounce: Duration) -> bool {
+    last_press.is_some_and(|(last_cell, at)| last_cell == cell && now.saturating_duration_since(at) < debounce)
+}
+
+// shows a small "Unranked" badge when the game isn't being recorded
+fn unranked_badge(ui: &mut egui::Ui, ranked: bool) {
+    if !ranked {
+        ui.label(RichText::new("Unranked").small().color(Color32::GRAY));
+    }
+}
+
+// shows one combo box per option override, where "Default" means the option isn't overridden
+    // id_prefix keeps the combo boxes unique when several sets of them are shown at once
+    // returns true if any of the overrides were changed
+fn override_combos(ui: &mut egui::Ui, id_prefix: &str, overrides: &mut OptionOverrides) -> bool {
+    let mut changed = false;
+    let strike_text = overrides.strike_limit.map_or("Default".to_string(), |limit| limit.to_string());
+    egui::ComboBox::from_id_salt(format!("{}_strikes", id_prefix))
+        .selected_text(strike_text)
+        .show_ui(ui, |ui| {
+            changed |= ui.selectable_value(&mut overrides.strike_limit, None, "Default").changed();
+            for limit in 1..=9 {
+                changed |= ui.selectable_value(&mut overrides.strike_limit, Some(limit), limit.to_string()).changed();
+            }
+        });
+    for (name, value) in [
+        ("errors", &mut overrides.highlight_errors),
+        ("counters", &mut overrides.show_unit_counters),
+        ("arcade", &mut overrides.arcade),
+    ] {
+        let text = match value {
+            None => "Default",
+            Some(true) => "On",
+            Some(false) => "Off",
+        };
+        egui::ComboBox::from_id_salt(format!("{}_{}", id_prefix, name))
+            .selected_text(text)
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(value, None, "Default").changed();
+                changed |= ui.selectable_value(value, Some(true), "On").changed();
+                changed |= ui.selectable_value(value, Some(false), "Off").changed();
+            });
+    }
+    changed
+}
+
+// a faint accent panel with a dot pattern, drawn behind the grid when the grid_background setting is on
+    // both colors are close to the window's background, so the cells stay as legible as without it
+fn grid_background(rect: Rect, dark_mode: bool) -> egui::Shape {
+    let (panel, dots) = if dark_mode {
+        (Color32::from_rgb(38, 42, 52), Color32::from_rgb(52, 57, 70))
+    }
+    else {
+        (Color32::from_rgb(236, 241, 249), Color32::from_rgb(220, 228, 242))
+    };
+    let mut shapes = vec![egui::Shape::rect_filled(rect, 12.0, panel)];
+    let spacing = 14.0;
+    let mut y = rect.top() + spacing / 2.0;
+    while y < rect.bottom() {
+        let mut x = rect.left() + spacing / 2.0;
+        while x < rect.right() {
+            shapes.push(egui::Shape::circle_filled(Pos2::new(x, y), 1.5, dots));
+            x += spacing;
+        }
+        y += spacing;
+    }
+    egui::Shape::Vec(shapes)
+}
+
+// formats a number of seconds as minutes and seconds, e.g. 585 becomes "9:45"
+fn format_time(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// handles the command-line mode, which runs without the GUI
+    // --solve <81-char-string> prints the solution of the puzzle, and --generate <difficulty> prints a generated puzzle and its solution
+        // (--generate Samurai prints a samurai puzzle as an entry for the samurai puzzle file, see SamuraiPuzzle)
+    // verify-pools checks every bundled puzzle file (see verify.rs), for maintainers to run before a release
+    // returns the exit code if the arguments asked for a command-line mode, or None if the GUI should be launched
+fn run_cli(args: &[String]) -> Option<i32> {
+    match args {
+        [flag, puzzle] if flag == "--solve" => {
+            // the puzzle has to be 81 cells, each a digit 1-9 or '.' for an empty cell
+            if puzzle.chars().count() != 81 || !puzzle.chars().all(|c| c == '.' || ('1'..='9').contains(&c)) {
+                eprintln!("The puzzle must be 81 characters, using 1-9 for givens and '.' for empty cells");
+                return Some(1);
+            }
+            match solver::solve(&board::string_to_grid(puzzle)) {
+                Some(solution) => {
+                    println!("{}", board::grid_to_string(&solution));
+                    Some(0)
+                }
+                None => {
+                    eprintln!("The puzzle has no solution");
+                    Some(1)
+                }
+            }
+        }
+        [flag, difficulty] if flag == "--generate" && difficulty == "Samurai" => {
+            let (puzzle, solution) = samurai::generate();
+            let id = board::puzzle_hash(&samurai::board_to_string(&puzzle));
+            match serde_json::to_string(&SamuraiPuzzle::from_boards(&id, &puzzle, &solution)) {
+                Ok(json) => {
+                    println!("{}", json);
+                    Some(0)
+                }
+                Err(err) => {
+                    eprintln!("Failed to write the puzzle: {}", err);
+                    Some(1)
+                }
+            }
+        }
+        [flag, difficulty] if flag == "--generate" => {
+            let (puzzle, solution) = generator::generate(difficulty);
+            println!("{}", board::grid_to_string(&puzzle));
+            println!("{}", board::grid_to_string(&solution));
+            Some(0)
+        }
+        [command] if command == "verify-pools" => Some(verify::verify_pools()),
+        [flag, ..] if flag == "--solve" || flag == "--generate" || flag == "verify-pools" => {
+            eprintln!("Usage: sudoku [--solve <81-char-puzzle> | --generate <difficulty> | verify-pools]");
+            Some(1)
+        }
+        _ => None,
     }
 }
 
 fn main() {
+    // if the app was started with command-line arguments for solving or generating, handle them and exit without the GUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = run_cli(&args) {
+        std::process::exit(code);
+    }
+
+    // the Sudoku struct is made before the window, so the window can open where it was last closed (see window.rs)
+    let mut app = Sudoku::new("John".into(), 2);
+
     // create a NativeOptions struct to pass to the eframe app
-    // the viewport member varialbe is specified here because we wont a maximized window
+    // the viewport member varialbe is specified here because we want the window's stored geometry, or a maximized window
     let native_options = NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_maximized(true).with_resizable(false),
+        viewport: window::viewport(app.settings.window),
         ..Default::default()
     };
 
@@ -531,6 +3914,483 @@ fn main() {
     let _ = eframe::run_native( // Start Vapor
         "Sudoku", // Set the app title
         native_options, 
-        Box::new(|_cc| Ok(Box::new(Sudoku::new("John".into(), 2)))),
+        Box::new(move |cc| {
+            // pick the starting theme from the settings, or from the system's light/dark preference
+            app.apply_theme(&cc.egui_ctx);
+            fonts::install(&cc.egui_ctx, app.settings.digit_style);
+            app.start_update_check(&cc.egui_ctx);
+            Ok(Box::new(app))
+        }),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Modifiers;
+
+    // plays every puzzle in a pool through the game the way a player would: each empty cell gets the solution's digit through
+        // place_digit, the same call a key press ends up in, and every puzzle has to end in a win without a strike
+        // each puzzle is played as it is and once transformed, since both are how a game can start
+    fn play_pool(difficulty: &str) {
+        let puzzles = Puzzles::load(difficulty).unwrap().puzzles;
+        assert!(!puzzles.is_empty(), "{} has no puzzles", difficulty);
+        let mut game = test_app();
+        for (index, entry) in puzzles.iter().enumerate() {
+            let id = entry.id();
+            for transform in [false, true] {
+                let puzzle = Puzzle {
+                    id: entry.id.clone(),
+                    puzzle: entry.puzzle.clone(),
+                    solution: entry.solution.clone(),
+                    parity: entry.parity.clone(),
+                    metadata: entry.metadata.clone(),
+                };
+                game.reset_gamestate();
+                game.difficulty = difficulty.to_string();
+                game.start_puzzle(puzzle, transform);
+                for cell in 0..81 {
+                    let (row, col) = (cell / 9, cell % 9);
+                    if game.player_grid[row][col] == '.' {
+                        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+                    }
+                }
+                assert_eq!(game.strikes, 0, "{} #{} ({}) struck while playing its solution", difficulty, index, id);
+                assert!(!game.struck_out(), "{} #{} ({}) was lost", difficulty, index, id);
+                assert!(game.is_won(), "{} #{} ({}) didn't end in a win", difficulty, index, id);
+            }
+        }
+    }
+
+    // the app with default settings and empty stats, without reading or writing any of the files in the working directory
+    fn test_app() -> Sudoku {
+        Sudoku::with_data("Tester".to_string(), 0, Settings::default(), Stats::default(), false)
+    }
+
+    // a game of the first Beginner puzzle with a 3 strike limit
+    fn beginner_game() -> Sudoku {
+        let mut game = test_app();
+        game.difficulty = "Beginner".to_string();
+        game.start_puzzle(Puzzle::at("Beginner", 0).unwrap(), false);
+        game.options.strike_limit = 3;
+        game.options.points = None;
+        game.options.validation = Validation::Solution;
+        game
+    }
+
+    // the empty cells of the game's board, in reading order
+    fn empty_cells(game: &Sudoku) -> Vec<(usize, usize)> {
+        (0..81).map(|i| (i / 9, i % 9)).filter(|&(row, col)| game.player_grid[row][col] == '.').collect()
+    }
+
+    // a digit that is wrong in the cell at (row, col)
+    fn wrong_digit(game: &Sudoku, row: usize, col: usize) -> char {
+        *board::DIGITS.iter().find(|&&digit| digit != game.solution_grid[row][col]).unwrap()
+    }
+
+    #[test]
+    fn sandbox_entries_are_free_until_applied() {
+        let mut game = beginner_game();
+        game.enter_sandbox();
+        for (row, col) in empty_cells(&game).into_iter().take(2) {
+            game.place_digit(row, col, wrong_digit(&game, row, col), Duration::ZERO);
+        }
+        assert_eq!(game.strikes, 0);
+        assert!(game.actions.is_empty());
+
+        game.leave_sandbox(true);
+        assert_eq!(game.strikes, 2);
+        assert_eq!(game.mistakes.len(), 2);
+    }
+
+    #[test]
+    fn discarding_the_sandbox_puts_the_real_game_back() {
+        let mut game = beginner_game();
+        let before = game.player_grid;
+        game.enter_sandbox();
+        let (row, col) = empty_cells(&game)[0];
+        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        game.leave_sandbox(false);
+        assert_eq!(game.player_grid, before);
+        assert_eq!(game.strikes, 0);
+    }
+
+    #[test]
+    fn applying_stops_once_a_strike_ends_the_game() {
+        let mut game = beginner_game();
+        game.enter_sandbox();
+        let cells = empty_cells(&game);
+        for &(row, col) in cells.iter().take(5) {
+            game.place_digit(row, col, wrong_digit(&game, row, col), Duration::ZERO);
+        }
+        game.leave_sandbox(true);
+        assert_eq!(game.strikes, 3);
+        assert!(game.struck_out());
+        // the first three were placed (and the third lost the game), and the last two never were
+        assert!(cells[..3].iter().all(|&(row, col)| game.player_grid[row][col] != '.'));
+        assert!(cells[3..5].iter().all(|&(row, col)| game.player_grid[row][col] == '.'));
+    }
+
+    #[test]
+    fn applying_correct_entries_is_progress_without_strikes() {
+        let mut game = beginner_game();
+        game.enter_sandbox();
+        for (row, col) in empty_cells(&game) {
+            game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        }
+        assert!(!game.is_won());
+        game.leave_sandbox(true);
+        assert_eq!(game.strikes, 0);
+        assert!(game.is_won());
+    }
+
+    #[test]
+    fn press_soon_after_in_the_same_cell_is_a_bounce() {
+        let start = Instant::now();
+        let debounce = Duration::from_millis(150);
+        let last = Some(((2, 3), start));
+        assert!(is_bounce(last, (2, 3), start + Duration::from_millis(10), debounce));
+        assert!(!is_bounce(last, (2, 3), start + debounce, debounce));
+        assert!(!is_bounce(last, (2, 4), start + Duration::from_millis(10), debounce));
+        assert!(!is_bounce(None, (2, 3), start, debounce));
+        assert!(!is_bounce(last, (2, 3), start, Duration::ZERO));
+    }
+
+    #[test]
+    fn two_rapid_presses_place_one_digit() {
+        let mut game = beginner_game();
+        game.settings.entry_debounce_ms = 150;
+        game.settings.ghost_preview = false;
+        let (row, col) = empty_cells(&game)[0];
+        let wrong = wrong_digit(&game, row, col);
+        game.digit_pressed(row, col, wrong, Duration::ZERO);
+        game.digit_pressed(row, col, wrong, Duration::ZERO);
+        assert_eq!(game.strikes, 1);
+        assert_eq!(game.history.len(), 1);
+        assert_eq!(game.actions.len(), 1);
+    }
+
+    #[test]
+    fn nothing_is_unsaved_at_launch() {
+        let game = test_app();
+        assert!(!game.stats_unsaved);
+        assert!(!game.settings_unsaved);
+    }
+
+    #[test]
+    fn a_cleaned_up_saved_name_is_left_to_be_saved_on_exit() {
+        let settings = Settings { username: Some("a\u{0}b".to_string()), ..Default::default() };
+        let game = Sudoku::with_data("Tester".to_string(), 0, settings, Stats::default(), false);
+        assert_eq!(game.username.as_str(), "ab");
+        assert_eq!(game.settings.username.as_deref(), Some("ab"));
+        assert!(game.settings_unsaved);
+        assert!(game.toast.is_some());
+    }
+
+    #[test]
+    fn unranked_play_leaves_the_stats_saved() {
+        let mut game = test_app();
+        game.ranked = false;
+        game.count_play_time(true);
+        game.count_play_time(true);
+        assert!(!game.stats_unsaved);
+        assert!(game.play_tick.is_none());
+    }
+
+    #[test]
+    fn unfocused_play_leaves_the_stats_saved() {
+        let mut game = test_app();
+        game.count_play_time(false);
+        game.count_play_time(false);
+        assert!(!game.stats_unsaved);
+    }
+
+    #[test]
+    fn ranked_play_time_has_to_be_saved() {
+        let mut game = test_app();
+        // the first focused frame only starts the count, so nothing has been added yet
+        game.count_play_time(true);
+        assert!(!game.stats_unsaved);
+        game.count_play_time(true);
+        assert!(game.stats_unsaved);
+    }
+
+    #[test]
+    fn plain_digits_are_entered() {
+        assert_eq!(digit_key_action(Modifiers::NONE, false, '5'), DigitAction::Enter);
+    }
+
+    #[test]
+    fn shift_and_notes_mode_both_toggle_notes() {
+        assert_eq!(digit_key_action(Modifiers::SHIFT, false, '5'), DigitAction::Note);
+        assert_eq!(digit_key_action(Modifiers::NONE, true, '5'), DigitAction::Note);
+        // Shift in notes mode still toggles a note rather than entering the digit
+        assert_eq!(digit_key_action(Modifiers::SHIFT, true, '5'), DigitAction::Note);
+    }
+
+    #[test]
+    fn alt_and_ctrl_win_over_notes() {
+        assert_eq!(digit_key_action(Modifiers::ALT | Modifiers::SHIFT, true, '5'), DigitAction::Sweep);
+        assert_eq!(digit_key_action(Modifiers::COMMAND | Modifiers::SHIFT, true, '2'), DigitAction::Tag(Some(ColorTag::Green)));
+    }
+
+    #[test]
+    fn only_ctrl_1_to_3_have_a_color_tag() {
+        assert_eq!(digit_key_action(Modifiers::COMMAND, false, '1'), DigitAction::Tag(Some(ColorTag::Yellow)));
+        assert_eq!(digit_key_action(Modifiers::COMMAND, false, '3'), DigitAction::Tag(Some(ColorTag::Purple)));
+        assert_eq!(digit_key_action(Modifiers::COMMAND, false, '4'), DigitAction::Tag(None));
+    }
+
+    #[test]
+    fn notes_share_the_undo_history_with_entries() {
+        let mut game = beginner_game();
+        let cells = empty_cells(&game);
+        let (row, col) = cells[0];
+        let (other_row, other_col) = cells[1];
+        game.toggle_note(row, col, '3');
+        game.place_digit(other_row, other_col, game.solution_grid[other_row][other_col], Duration::ZERO);
+        game.toggle_note(row, col, '7');
+        assert_eq!(game.notes[row][col], notes::bit('3') | notes::bit('7'));
+        assert_eq!(game.history.len(), 3);
+
+        game.undo();
+        assert_eq!(game.notes[row][col], notes::bit('3'));
+        game.undo();
+        assert_eq!(game.player_grid[other_row][other_col], '.');
+        game.undo();
+        assert_eq!(game.notes[row][col], 0);
+    }
+
+    #[test]
+    fn notes_are_not_toggled_in_givens_or_filled_cells() {
+        let mut game = beginner_game();
+        let given = (0..81).map(|i| (i / 9, i % 9)).find(|&(row, col)| game.starting_grid[row][col] != '.').unwrap();
+        let (row, col) = empty_cells(&game)[0];
+        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        let history = game.history.len();
+        game.toggle_note(given.0, given.1, '1');
+        game.toggle_note(row, col, '1');
+        assert_eq!(game.notes[given.0][given.1], 0);
+        assert_eq!(game.notes[row][col], 0);
+        assert_eq!(game.history.len(), history);
+    }
+
+    // plays an unranked game of the first Beginner puzzle to its end, a win or a loss, and returns the stats as they would be saved
+        // from before and after it -- play time is counted for every frame, the same as while the game is on screen
+    fn unranked_stats_bytes(won: bool) -> (Vec<u8>, Vec<u8>) {
+        let mut game = test_app();
+        game.difficulty = "Beginner".to_string();
+        game.setup.unranked = true;
+        game.start_puzzle(Puzzle::at("Beginner", 0).unwrap(), false);
+        game.options.strike_limit = 3;
+        game.options.validation = Validation::Solution;
+        assert!(!game.ranked);
+        let before = serde_json::to_vec(&game.stats).unwrap();
+
+        for (row, col) in empty_cells(&game) {
+            game.count_play_time(true);
+            let digit = if won { game.solution_grid[row][col] } else { wrong_digit(&game, row, col) };
+            game.place_digit(row, col, digit, Duration::ZERO);
+            if game.struck_out() {
+                break;
+            }
+        }
+        assert_eq!(game.is_won(), won);
+        game.record_game_end(won, game.ranked);
+        assert!(!game.stats_unsaved);
+        (before, serde_json::to_vec(&game.stats).unwrap())
+    }
+
+    #[test]
+    fn an_unranked_win_leaves_the_stats_as_they_were() {
+        let (before, after) = unranked_stats_bytes(true);
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn an_unranked_loss_leaves_the_stats_as_they_were() {
+        let (before, after) = unranked_stats_bytes(false);
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn reverting_to_a_checkpoint_drops_the_logs_since() {
+        let mut game = beginner_game();
+        let cells = empty_cells(&game);
+        let (row, col) = cells[0];
+        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        game.save_checkpoint();
+        let board = game.player_grid;
+
+        let (wrong_row, wrong_col) = cells[1];
+        game.place_digit(wrong_row, wrong_col, wrong_digit(&game, wrong_row, wrong_col), Duration::ZERO);
+        let (right_row, right_col) = cells[2];
+        game.place_digit(right_row, right_col, game.solution_grid[right_row][right_col], Duration::ZERO);
+        assert_eq!((game.strikes, game.mistakes.len(), game.actions.len()), (1, 1, 3));
+
+        game.revert_to_checkpoint();
+        assert_eq!(game.player_grid, board);
+        assert_eq!((game.strikes, game.mistakes.len(), game.actions.len()), (0, 0, 1));
+        assert_eq!((game.actions[0].row, game.actions[0].col), (row, col));
+    }
+
+    #[test]
+    fn a_checkpoint_can_be_reverted_to_more_than_once() {
+        let mut game = beginner_game();
+        game.save_checkpoint();
+        for _ in 0..2 {
+            let (row, col) = empty_cells(&game)[0];
+            game.place_digit(row, col, wrong_digit(&game, row, col), Duration::ZERO);
+            game.revert_to_checkpoint();
+            assert_eq!((game.strikes, game.mistakes.len(), game.actions.len()), (0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn a_lost_drill_isnt_recorded() {
+        let mut game = beginner_game();
+        let (row, col) = empty_cells(&game)[0];
+        game.place_digit(row, col, wrong_digit(&game, row, col), Duration::ZERO);
+        game.drill_mistakes();
+        assert!(!game.ranked);
+        assert_eq!(empty_cells(&game), vec![(row, col)]);
+
+        while !game.struck_out() {
+            game.place_digit(row, col, wrong_digit(&game, row, col), Duration::ZERO);
+            game.player_grid[row][col] = '.';
+            game.last_digit_press = None;
+        }
+        game.record_game_end(false, game.ranked);
+        assert!(game.stats.last_lost.is_none());
+        assert!(game.stats.games.is_empty());
+        assert!(game.stats.mistakes.is_empty());
+    }
+
+    #[test]
+    fn clearing_the_color_tags_can_be_undone() {
+        let mut game = beginner_game();
+        game.color_tags[0][1] = Some(ColorTag::Green);
+        game.color_tags[8][8] = Some(ColorTag::Purple);
+        let tags = game.color_tags;
+        game.clear_color_tags();
+        assert!(game.color_tags.iter().flatten().all(Option::is_none));
+        game.undo();
+        assert!(game.color_tags == tags);
+        assert!(game.history.is_empty());
+    }
+
+    #[test]
+    fn clearing_no_color_tags_isnt_an_undo_step() {
+        let mut game = beginner_game();
+        game.clear_color_tags();
+        assert!(game.history.is_empty());
+    }
+
+    // the names of the files in dir, sorted
+    fn files_in(dir: &std::path::Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        names.sort();
+        names
+    }
+
+    fn read_saved<T: serde::de::DeserializeOwned>(dir: &std::path::Path, path: &str) -> T {
+        serde_json::from_str(&fs::read_to_string(save_path(dir, path)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn exiting_with_every_store_dirty_writes_the_latest_of_each() {
+        let dir = persist::scratch_dir("main-flush-dirty");
+        let mut game = beginner_game();
+        let (row, col) = empty_cells(&game)[0];
+        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        // the clock starts on the first frame the board is drawn
+        game.timer_start = Some(Instant::now());
+        assert!(game.game_in_progress());
+        game.stats.played_puzzles.insert("flushed".to_string());
+        game.stats_unsaved = true;
+        game.settings.username = Some("Flushed".to_string());
+        game.settings_unsaved = true;
+
+        assert_eq!(game.flush(&dir), vec!["stats", "settings", "game in progress"]);
+        assert_eq!(files_in(&dir), vec!["savegame.json", "settings.json", "stats.json"]);
+        assert!(read_saved::<Stats>(&dir, stats::STATS_PATH).played_puzzles.contains("flushed"));
+        assert_eq!(read_saved::<Settings>(&dir, settings::SETTINGS_PATH).username.as_deref(), Some("Flushed"));
+        let saved: SavedGame = read_saved(&dir, savegame::SAVEGAME_PATH);
+        assert_eq!(saved.player, board::grid_to_string(&game.player_grid));
+    }
+
+    #[test]
+    fn exiting_with_nothing_dirty_writes_nothing() {
+        let dir = persist::scratch_dir("main-flush-clean");
+        assert_eq!(test_app().flush(&dir), vec!["nothing"]);
+        assert!(files_in(&dir).is_empty());
+    }
+
+    #[test]
+    fn a_game_the_quit_dialog_answered_for_isnt_saved_again() {
+        let dir = persist::scratch_dir("main-flush-quit");
+        let mut game = beginner_game();
+        let (row, col) = empty_cells(&game)[0];
+        game.place_digit(row, col, game.solution_grid[row][col], Duration::ZERO);
+        game.timer_start = Some(Instant::now());
+        game.stats_unsaved = true;
+        game.allow_close = true;
+        assert_eq!(game.flush(&dir), vec!["stats"]);
+        assert_eq!(files_in(&dir), vec!["stats.json"]);
+    }
+
+    #[test]
+    fn settings_arent_written_while_setup_is_open() {
+        let dir = persist::scratch_dir("main-flush-setup");
+        let mut game = test_app();
+        game.onboarding = Some(Onboarding::new("Tester"));
+        game.settings_unsaved = true;
+        assert_eq!(game.flush(&dir), vec!["nothing"]);
+        assert!(files_in(&dir).is_empty());
+    }
+
+    #[test]
+    fn a_marathon_between_puzzles_is_saved() {
+        let dir = persist::scratch_dir("main-flush-marathon");
+        let mut game = test_app();
+        game.marathon = Some(Marathon {
+            difficulty: "Beginner".to_string(),
+            queue: vec!["first".to_string(), "second".to_string()],
+            strike_rule: StrikeRule::Shared,
+            results: vec![marathon::PuzzleResult { secs: 90, strikes: 1 }],
+            lost: false,
+            game: None,
+        });
+        assert_eq!(game.flush(&dir), vec!["marathon"]);
+        let saved: Marathon = read_saved(&dir, marathon::MARATHON_PATH);
+        assert_eq!(saved.results.len(), 1);
+        assert!(saved.game.is_none());
+    }
+
+    #[test]
+    #[ignore = "plays all of the bundled puzzles, run with cargo test -- --ignored before a release"]
+    fn bundled_pools_play_to_a_win() {
+        for difficulty in verify::POOLS {
+            play_pool(difficulty);
+        }
+    }
+
+    #[test]
+    #[ignore = "plays all of the bundled puzzles, run with cargo test -- --ignored before a release"]
+    fn line_delimited_pool_plays_to_a_win() {
+        // the bundled pools are all .json, so the Test pool is written out one puzzle per line to go through the .jsonl reader
+        let name = "PlayThroughTest";
+        let path = format!("./puzzles/{}.{}", name, puzzle_index::LINES_EXTENSION);
+        let lines: Vec<String> = Puzzles::load("Test").unwrap().puzzles.iter()
+            .map(|puzzle| serde_json::to_string(puzzle).unwrap())
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+        assert!(puzzle_index::is_line_delimited(&Puzzles::path(name)));
+        let result = std::panic::catch_unwind(|| {
+            assert_eq!(Puzzle::at(name, 0).unwrap().id(), Puzzle::at("Test", 0).unwrap().id());
+            play_pool(name);
+        });
+        fs::remove_file(&path).unwrap();
+        result.unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::persist;
+use crate::savegame::SavedGame;
+use crate::{Puzzle, Puzzles};
+
+// a marathon that was stopped part of the way through is kept next to the saved game, and deleted once it is resumed
+pub const MARATHON_PATH: &str = "./marathon.json";
+
+// the number of puzzles a marathon can be
+pub const LENGTHS: [usize; 3] = [3, 5, 10];
+
+// the StrikeRule enum is how strikes work over a marathon -- PerPuzzle gives every puzzle the full strike limit, and Shared
+    // spreads one strike limit over the whole marathon, so every strike made is one fewer for the puzzles after it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum StrikeRule {
+    #[default]
+    PerPuzzle,
+    Shared,
+}
+
+impl StrikeRule {
+    // the name shown on the start screen and in the marathon records
+    pub fn name(self) -> &'static str {
+        match self {
+            StrikeRule::PerPuzzle => "Strikes per puzzle",
+            StrikeRule::Shared => "Shared strikes",
+        }
+    }
+}
+
+// the MarathonSetup struct is what is picked on the start screen for the next marathon
+#[derive(Clone)]
+pub struct MarathonSetup {
+    pub difficulty: String,
+    pub length: usize,
+    pub strike_rule: StrikeRule,
+}
+
+impl Default for MarathonSetup {
+    fn default() -> Self {
+        Self {
+            difficulty: "Beginner".to_string(),
+            length: LENGTHS[0],
+            strike_rule: StrikeRule::default(),
+        }
+    }
+}
+
+// the PuzzleResult struct is how one puzzle of a marathon went -- secs is how long it took, and strikes how many were made in it
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PuzzleResult {
+    pub secs: u64,
+    pub strikes: u32,
+}
+
+/*
+    The Marathon struct is a run of puzzles at one difficulty, played back to back -- the games themselves are still played
+        (and recorded) one at a time by the Sudoku struct, and this keeps what connects them
+    queue is the ids of every puzzle in the marathon, picked when it starts so none of them come up twice
+    results has one entry for each puzzle that has been finished, so the puzzle being played is queue[results.len()]
+    lost is set once a puzzle is lost, which ends the marathon -- the lost puzzle is the last entry in results
+    game is the puzzle in progress when the marathon was stopped and saved, or None if it was saved between puzzles
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Marathon {
+    pub difficulty: String,
+    pub queue: Vec<String>,
+    pub strike_rule: StrikeRule,
+    pub results: Vec<PuzzleResult>,
+    pub lost: bool,
+    #[serde(default)]
+    pub game: Option<SavedGame>,
+}
+
+impl Marathon {
+    // picks the puzzles for a new marathon from the difficulty's puzzle file -- puzzles that haven't been played yet are picked first
+        // returns why if the file can't be read or doesn't have enough different puzzles
+    pub fn new(setup: &MarathonSetup, played: &HashSet<String>) -> Result<Self, String> {
+        let puzzles = Puzzles::load(&setup.difficulty)?;
+        let mut ids: Vec<String> = Vec::new();
+        for id in puzzles.puzzles.iter().map(Puzzle::id) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if ids.len() < setup.length {
+            return Err(format!("{} only has {} different puzzles, so it can't be a {} puzzle marathon", setup.difficulty, ids.len(), setup.length));
+        }
+
+        // shuffle, then move the puzzles that haven't been played to the front (the sort is stable, so they stay shuffled)
+        ids.shuffle(&mut rand::thread_rng());
+        ids.sort_by_key(|id| played.contains(id));
+        ids.truncate(setup.length);
+        Ok(Self {
+            difficulty: setup.difficulty.clone(),
+            queue: ids,
+            strike_rule: setup.strike_rule,
+            results: Vec::new(),
+            lost: false,
+            game: None,
+        })
+    }
+
+    // reads the saved marathon (or its backup, see persist.rs), or None if there isn't one
+    pub fn load() -> Option<Self> {
+        persist::read_json(MARATHON_PATH)
+    }
+
+    // writes the marathon so it can be resumed later -- returns the error so the user can be told it wasn't saved
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(MARATHON_PATH)
+    }
+
+    // writes the marathon to path instead of MARATHON_PATH
+    pub fn save_to(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to save the marathon: {}", err))?;
+        persist::write_atomic(path, json.as_bytes()).map_err(|err| format!("Failed to save the marathon: {}", err))
+    }
+
+    // deletes the saved marathon once it has been resumed, so it can't be resumed twice
+    pub fn delete() {
+        if let Err(err) = persist::remove(MARATHON_PATH) {
+            println!("Failed to delete the saved marathon: {}", err);
+        }
+    }
+
+    // the puzzle to play next, read from the difficulty's puzzle file by its id
+    pub fn next_puzzle(&self) -> Result<Puzzle, String> {
+        let id = self.queue.get(self.results.len()).ok_or("The marathon is already over")?;
+        Puzzles::load(&self.difficulty)?.puzzles.into_iter()
+            .find(|puzzle| &puzzle.id() == id)
+            .ok_or(format!("Puzzle {} isn't in the {} puzzle file any more", id, self.difficulty))
+    }
+
+    // records how the puzzle being played went -- losing it ends the marathon
+    pub fn finish_puzzle(&mut self, secs: u64, strikes: u32, won: bool) {
+        self.results.push(PuzzleResult { secs, strikes });
+        self.lost |= !won;
+    }
+
+    // returns true once every puzzle has been solved, or one was lost
+    pub fn is_over(&self) -> bool {
+        self.lost || self.results.len() >= self.queue.len()
+    }
+
+    // how many of the puzzles have been solved
+    pub fn solved(&self) -> usize {
+        self.results.len() - self.lost as usize
+    }
+
+    // the time and strikes of every finished puzzle added up
+    pub fn total_secs(&self) -> u64 {
+        self.results.iter().map(|result| result.secs).sum()
+    }
+
+    pub fn total_strikes(&self) -> u32 {
+        self.results.iter().map(|result| result.strikes).sum()
+    }
+
+    // the strike limit for the next puzzle, given the limit a single game would have
+        // with shared strikes it is what is left of the limit, which is never 0 since making the last one loses the marathon
+    pub fn strike_limit(&self, limit: u8) -> u8 {
+        match self.strike_rule {
+            StrikeRule::PerPuzzle => limit,
+            StrikeRule::Shared => (limit as u32).saturating_sub(self.total_strikes()).max(1) as u8,
+        }
+    }
+
+    // the name the marathon's results are recorded under -- marathons are only compared with ones of the same difficulty, length, and strike rule
+    pub fn record_key(&self) -> String {
+        format!("{} x{}, {}", self.difficulty, self.queue.len(), self.strike_rule.name().to_lowercase())
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use crate::board::{self, Grid};
+
+// the notes (pencil marks) of every cell, using the same layout as a grid
+    // each cell's notes are a bitmask of digits, where bit 0 is the digit 1 and bit 8 is the digit 9
+pub type Notes = [[u16; 9]; 9];
+
+// the bit for a digit in a cell's notes, or 0 if it isn't a digit from 1 to 9
+pub fn bit(digit: char) -> u16 {
+    crate::actions::digit_index(digit).map_or(0, |index| 1 << index)
+}
+
+// the text drawn in a cell for its notes: three lines of three digits, with a space for each digit that isn't noted
+    // the labels for all 512 masks are built the first time one is asked for, so drawing the notes doesn't rebuild them every frame
+pub fn label(mask: u16) -> &'static str {
+    static LABELS: OnceLock<Vec<String>> = OnceLock::new();
+    let labels = LABELS.get_or_init(|| {
+        (0..512u16).map(|mask| {
+            let lines: Vec<String> = board::DIGITS.chunks(3)
+                .map(|line| line.iter().map(|&digit| if mask & bit(digit) != 0 { digit } else { ' ' }).map(String::from).collect::<Vec<_>>().join(" "))
+                .collect();
+            lines.join("\n")
+        }).collect()
+    });
+    &labels[(mask & 511) as usize]
+}
+
+// the candidates of a cell as a notes bitmask -- a filled cell has none
+pub fn candidate_mask(grid: &Grid, row: usize, col: usize) -> u16 {
+    board::candidates(grid, row, col).into_iter().fold(0, |mask, digit| mask | bit(digit))
+}
+
+// the notes for a whole board with every cell's candidates filled in
+pub fn fill(grid: &Grid) -> Notes {
+    std::array::from_fn(|row| std::array::from_fn(|col| candidate_mask(grid, row, col)))
+}
+
+// the 20 cells that share a row, column, or box with (row, col)
+pub fn peers(row: usize, col: usize) -> Vec<(usize, usize)> {
+    let (box_row, box_col) = ((row / 3) * 3, (col / 3) * 3);
+    let mut peers = Vec::with_capacity(20);
+    for i in 0..9 {
+        if i != col {
+            peers.push((row, i));
+        }
+        if i != row {
+            peers.push((i, col));
+        }
+        let (r, c) = (box_row + i / 3, box_col + i % 3);
+        if r != row && c != col {
+            peers.push((r, c));
+        }
+    }
+    peers
+}
+
+// updates automatic notes after the cell at (row, col) changed from previous to whatever grid has there now
+    // only the cell and its peers are touched: a placed digit is taken out of the peers' notes, and a removed digit goes back
+    // into the notes of every empty peer where it is legal again
+pub fn apply_change(notes: &mut Notes, grid: &Grid, row: usize, col: usize, previous: char) {
+    let current = grid[row][col];
+    notes[row][col] = candidate_mask(grid, row, col);
+    for (r, c) in peers(row, col) {
+        if grid[r][c] != '.' {
+            continue;
+        }
+        if previous != '.' && board::is_legal(grid, r, c, previous) {
+            notes[r][c] |= bit(previous);
+        }
+        notes[r][c] &= !bit(current);
+    }
+}
+
+// how long the marks of a note hint stay on the board before they fade away
+pub const NOTE_HINT_TIME: Duration = Duration::from_secs(10);
+
+// the NoteHint struct is a set of temporary marks showing every cell a digit could still go in
+    // the marks aren't part of the notes until the user keeps them (see keep), and they disappear after NOTE_HINT_TIME
+    // shown is when the hint was given, so the marks can fade out
+pub struct NoteHint {
+    pub digit: char,
+    pub cells: Vec<(usize, usize)>,
+    pub shown: Instant,
+}
+
+impl NoteHint {
+    // a hint for the digit with the fewest legal cells left (ignoring digits that are already placed everywhere),
+        // or None if no digit has anywhere left to go
+    pub fn fewest_placements(grid: &Grid) -> Option<Self> {
+        board::DIGITS.iter()
+            .map(|&digit| (digit, board::placements(grid, digit)))
+            .filter(|(_, cells)| !cells.is_empty())
+            .min_by_key(|(_, cells)| cells.len())
+            .map(|(digit, cells)| Self { digit, cells, shown: Instant::now() })
+    }
+
+    // how much of the hint's time is left, from 1 when it was just shown down to 0 when it has expired
+    pub fn remaining(&self) -> f32 {
+        1.0 - (self.shown.elapsed().as_secs_f32() / NOTE_HINT_TIME.as_secs_f32()).min(1.0)
+    }
+
+    // drops the marks the board has made wrong since the hint was shown: cells that have been filled in,
+        // and cells the digit can't go in anymore -- returns false once there are no marks left
+    pub fn retain_valid(&mut self, grid: &Grid) -> bool {
+        let digit = self.digit;
+        self.cells.retain(|&(row, col)| grid[row][col] == '.' && board::is_legal(grid, row, col, digit));
+        !self.cells.is_empty()
+    }
+
+    // turns the marks into real notes
+    pub fn keep(&self, notes: &mut Notes) {
+        for &(row, col) in &self.cells {
+            notes[row][col] |= bit(self.digit);
+        }
+    }
+}
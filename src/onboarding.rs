@@ -0,0 +1,152 @@
+use eframe::egui::{self, CentralPanel, Context, FontFamily, FontId, RichText, Vec2};
+use crate::settings::AssistPreset;
+use crate::layout;
+use crate::username::Username;
+use crate::warmup::WarmUp;
+use crate::Sudoku;
+
+// the steps of the setup, in order
+#[derive(Clone, Copy, PartialEq)]
+enum OnboardingStep {
+    Welcome,
+    Name,
+    Theme,
+    Assists,
+    FirstGame,
+}
+
+impl OnboardingStep {
+    // the step after this one, or None after the last step
+    fn next(self) -> Option<Self> {
+        match self {
+            OnboardingStep::Welcome => Some(OnboardingStep::Name),
+            OnboardingStep::Name => Some(OnboardingStep::Theme),
+            OnboardingStep::Theme => Some(OnboardingStep::Assists),
+            OnboardingStep::Assists => Some(OnboardingStep::FirstGame),
+            OnboardingStep::FirstGame => None,
+        }
+    }
+}
+
+/*
+    The Onboarding struct is the setup shown on the first launch (when there is no settings file), and again from "Run setup again"
+    step is the page being shown, name is what's typed in the name box, and preset is the assist preset picked so far
+    each step writes straight into the settings, and the settings are saved when the setup is finished or skipped --
+        once the settings file exists, the setup doesn't come back on its own
+*/
+pub struct Onboarding {
+    step: OnboardingStep,
+    name: String,
+    preset: AssistPreset,
+}
+
+impl Onboarding {
+    pub fn new(name: &str) -> Self {
+        Self {
+            step: OnboardingStep::Welcome,
+            name: name.to_string(),
+            preset: AssistPreset::Standard,
+        }
+    }
+}
+
+impl Sudoku {
+    // displays the current step of the setup, with a "Skip setup" button on every step
+        // skipping keeps whatever the earlier steps chose, and the defaults for the rest
+    pub(crate) fn onboarding_screen(&mut self, ctx: &Context) {
+        let Some(onboarding) = &mut self.onboarding else { return };
+        let mut next = false;
+        let mut finish = false;
+        let mut theme_changed = false;
+        let mut play_now = false;
+
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() * 0.15);
+                match onboarding.step {
+                    OnboardingStep::Welcome => {
+                        ui.heading(RichText::new("Welcome to Sudoku")
+                            .font(FontId::new(30.0, FontFamily::Proportional)));
+                        ui.label("Fill the board so every row, column, and 3x3 box has the digits 1 to 9 exactly once.");
+                        ui.label("A few quick questions will set the game up the way you like it.");
+                    }
+                    OnboardingStep::Name => {
+                        ui.heading("What should we call you?");
+                        ui.add(egui::TextEdit::singleline(&mut onboarding.name).hint_text("Your name"));
+                        // the name is checked as it is typed, and Next waits until it is valid
+                        if let Err(err) = Username::new(&onboarding.name) {
+                            ui.colored_label(ui.visuals().error_fg_color, err);
+                        }
+                    }
+                    OnboardingStep::Theme => {
+                        // the theme is applied as soon as it is picked, so this page is the preview
+                        ui.heading("Light or dark?");
+                        layout::centered_row(ui, "onboarding_theme", |ui| {
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, None, "Follow system").changed();
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, Some(false), "Light").changed();
+                            theme_changed |= ui.selectable_value(&mut self.settings.dark_mode, Some(true), "Dark").changed();
+                        });
+                    }
+                    OnboardingStep::Assists => {
+                        ui.heading("How much help do you want?");
+                        for preset in AssistPreset::ALL {
+                            ui.add_space(10.0);
+                            if ui.selectable_label(onboarding.preset == preset, RichText::new(preset.name()).strong()).clicked() {
+                                onboarding.preset = preset;
+                                preset.apply(&mut self.settings);
+                            }
+                            ui.label(preset.description());
+                        }
+                        ui.add_space(10.0);
+                        ui.label("Everything can be changed later in Settings.");
+                    }
+                    OnboardingStep::FirstGame => {
+                        ui.heading("You're all set");
+                        ui.label("Want to start with a Beginner game? It opens with a quick 4x4 warm-up to learn the rules on.");
+                        ui.add_space(10.0);
+                        play_now = ui.add(egui::Button::new("Play a Beginner game").min_size(Vec2::new(180.0, 30.0))).clicked();
+                    }
+                }
+
+                ui.add_space(30.0);
+                layout::centered_row(ui, "onboarding_buttons", |ui| {
+                    let last = onboarding.step.next().is_none();
+                    let valid = onboarding.step != OnboardingStep::Name || Username::new(&onboarding.name).is_ok();
+                    if ui.add_enabled(valid, egui::Button::new(if last { "Finish" } else { "Next" })).clicked() {
+                        next = true;
+                    }
+                    if !last && ui.button("Skip setup").clicked() {
+                        finish = true;
+                    }
+                });
+            });
+        });
+
+        if theme_changed {
+            self.apply_theme(ctx);
+        }
+        let Some(onboarding) = &mut self.onboarding else { return };
+        // a name that isn't valid when the setup is skipped is left alone, and the current name is kept
+        if (next && onboarding.step == OnboardingStep::Name) || finish || play_now {
+            if let Ok(name) = Username::new(&onboarding.name) {
+                self.settings.username = Some(name.as_str().to_string());
+                self.username = name;
+            }
+        }
+        if next {
+            match onboarding.step.next() {
+                Some(step) => onboarding.step = step,
+                None => finish = true,
+            }
+        }
+        if finish || play_now {
+            self.onboarding = None;
+            self.settings.save();
+        }
+        if play_now {
+            self.difficulty = "Beginner".to_string();
+            self.get_puzzle();
+            self.warm_up = Some(WarmUp::random());
+        }
+    }
+}
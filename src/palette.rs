@@ -0,0 +1,117 @@
+use eframe::egui::ecolor::Hsva;
+use eframe::egui::Color32;
+
+// the largest difference in brightness between the two checkerboard shades, at full contrast
+    // dark mode uses a smaller range, since small steps are easier to see on a dark background
+const LIGHT_RANGE: f32 = 50.0;
+const DARK_RANGE: f32 = 30.0;
+
+// the brightest the boxes get in light mode, and the darkest they get in dark mode
+const LIGHT_BASE: f32 = 255.0;
+const DARK_BASE: f32 = 60.0;
+
+// the background of a 3x3 box (box_index 0 to 8, left to right and top to bottom), before any highlight is layered on top
+    // the boxes alternate like a checkerboard, and contrast (0 to 1) is how far apart the two shades are -- 0 makes them the same
+    // with pastel on, each box also gets its own faint hue, spread evenly around the color wheel
+pub fn box_color(box_index: usize, contrast: f32, pastel: bool, dark_mode: bool) -> Color32 {
+    let contrast = contrast.clamp(0.0, 1.0);
+    let shaded = (box_index / 3 + box_index % 3).is_multiple_of(2);
+    let gray = match (dark_mode, shaded) {
+        (false, true) => LIGHT_BASE - contrast * LIGHT_RANGE,
+        (false, false) => LIGHT_BASE,
+        (true, true) => DARK_BASE,
+        (true, false) => DARK_BASE + contrast * DARK_RANGE,
+    };
+    let base = Color32::from_gray(gray.round() as u8);
+    if !pastel {
+        return base;
+    }
+    let hue = box_index as f32 / 9.0;
+    let tint = Color32::from(Hsva::new(hue, 0.6, if dark_mode { 0.55 } else { 1.0 }, 1.0));
+    layer(base, tint, 0.15 + 0.25 * contrast)
+}
+
+// lays a highlight color over a base color, where strength (0 to 1) is how much of the highlight shows
+    // highlights are always layered on the box color instead of replacing it, so each kind of highlight still looks different
+    // on every box, and the base's brightness carries through
+pub fn layer(base: Color32, highlight: Color32, strength: f32) -> Color32 {
+    let strength = strength.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * strength).round() as u8;
+    Color32::from_rgb(mix(base.r(), highlight.r()), mix(base.g(), highlight.g()), mix(base.b(), highlight.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORNERS_AND_CENTER: [usize; 5] = [0, 2, 4, 6, 8];
+    const EDGES: [usize; 4] = [1, 3, 5, 7];
+
+    #[test]
+    fn default_contrast_keeps_the_original_checkerboard() {
+        // the corner and center boxes are the darker shade and the edge boxes the lighter one, in both themes,
+            // with the same grays the checkerboard had before the contrast setting
+        for dark_mode in [false, true] {
+            let (shaded, plain) = if dark_mode { (60, 75) } else { (230, 255) };
+            for index in CORNERS_AND_CENTER {
+                assert_eq!(box_color(index, 0.5, false, dark_mode), Color32::from_gray(shaded));
+            }
+            for index in EDGES {
+                assert_eq!(box_color(index, 0.5, false, dark_mode), Color32::from_gray(plain));
+            }
+        }
+    }
+
+    #[test]
+    fn no_contrast_makes_every_box_the_same() {
+        for dark_mode in [false, true] {
+            let first = box_color(0, 0.0, false, dark_mode);
+            assert!((0..9).all(|index| box_color(index, 0.0, false, dark_mode) == first));
+        }
+    }
+
+    #[test]
+    fn full_contrast_uses_the_whole_range() {
+        assert_eq!(box_color(0, 1.0, false, false), Color32::from_gray((LIGHT_BASE - LIGHT_RANGE) as u8));
+        assert_eq!(box_color(1, 1.0, false, false), Color32::from_gray(LIGHT_BASE as u8));
+        assert_eq!(box_color(0, 1.0, false, true), Color32::from_gray(DARK_BASE as u8));
+        assert_eq!(box_color(1, 1.0, false, true), Color32::from_gray((DARK_BASE + DARK_RANGE) as u8));
+    }
+
+    #[test]
+    fn contrast_outside_the_slider_is_clamped() {
+        for dark_mode in [false, true] {
+            for index in 0..9 {
+                assert_eq!(box_color(index, -3.0, false, dark_mode), box_color(index, 0.0, false, dark_mode));
+                assert_eq!(box_color(index, 7.0, false, dark_mode), box_color(index, 1.0, false, dark_mode));
+            }
+        }
+    }
+
+    #[test]
+    fn pastel_gives_every_box_its_own_color() {
+        for (contrast, dark_mode) in [(0.0, false), (1.0, false), (0.0, true), (1.0, true)] {
+            let colors: Vec<Color32> = (0..9).map(|index| box_color(index, contrast, true, dark_mode)).collect();
+            for (i, a) in colors.iter().enumerate() {
+                assert!(colors[i + 1..].iter().all(|b| a != b), "box {} shares its color at contrast {}", i, contrast);
+            }
+        }
+    }
+
+    #[test]
+    fn layer_strength_goes_from_base_to_highlight() {
+        let base = Color32::from_gray(200);
+        let highlight = Color32::from_rgb(255, 0, 100);
+        assert_eq!(layer(base, highlight, 0.0), base);
+        assert_eq!(layer(base, highlight, 1.0), highlight);
+        assert_eq!(layer(base, highlight, 0.5), Color32::from_rgb(228, 100, 150));
+    }
+
+    #[test]
+    fn layer_strength_outside_0_to_1_is_clamped() {
+        let base = Color32::BLACK;
+        let highlight = Color32::WHITE;
+        assert_eq!(layer(base, highlight, -2.0), base);
+        assert_eq!(layer(base, highlight, 9.0), highlight);
+    }
+}
@@ -0,0 +1,179 @@
+use std::fs::{self, File};
+use std::io::Write;
+use serde::de::DeserializeOwned;
+
+/*
+    every file the app saves goes through this module, so a crash or power cut in the middle of saving can't leave a half-written file
+    a file is written to a temporary file next to it first, flushed to disk, and only then renamed over the original --
+        a rename within one directory either happens completely or not at all
+    the previous version of the file is kept as a .bak copy, and the readers fall back to it if the file itself can't be parsed
+*/
+
+// the path of the temporary file a file is staged in before it replaces the original
+fn temp_path(path: &str) -> String {
+    format!("{}.tmp", path)
+}
+
+// the path of the copy of the previous version of a file
+fn backup_path(path: &str) -> String {
+    format!("{}.bak", path)
+}
+
+// writes bytes to the temporary file for path and flushes it to disk, returning the temporary file's path
+    // nothing at path changes until the staged file is committed
+pub fn stage(path: &str, bytes: &[u8]) -> Result<String, String> {
+    let temp = temp_path(path);
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        });
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path, err));
+    }
+    Ok(temp)
+}
+
+// replaces the file at path with a staged file, after copying the current version (if there is one) to the .bak file
+    // the current version is only copied if it parses -- a file that was cut short (which is when the backup gets read) would
+    // otherwise replace the one good copy left, so the old backup is kept instead
+    // failing to make the backup is logged but doesn't stop the write
+pub fn commit(temp: &str, path: &str) -> Result<(), String> {
+    if let Ok(current) = fs::read(path) {
+        if serde_json::from_slice::<serde_json::Value>(&current).is_err() {
+            println!("Not backing up {}, since it can't be parsed -- the previous backup is kept", path);
+        }
+        else if let Err(err) = fs::copy(path, backup_path(path)) {
+            println!("Failed to back up {}: {}", path, err);
+        }
+    }
+    fs::rename(temp, path).map_err(|err| {
+        let _ = fs::remove_file(temp);
+        format!("Failed to write {}: {}", path, err)
+    })
+}
+
+// writes a whole file so that it is either completely replaced or left exactly as it was
+pub fn write_atomic(path: &str, bytes: &[u8]) -> Result<(), String> {
+    let temp = stage(path, bytes)?;
+    commit(&temp, path)
+}
+
+// reads a json file, falling back to its .bak copy if the file is there but can't be read or parsed
+    // returns None (without a warning) if there is no file at all, and None with a warning if neither copy can be used
+pub fn read_json<T: DeserializeOwned>(path: &str) -> Option<T> {
+    let parse = |path: &str| fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|err| err.to_string()));
+    if fs::metadata(path).is_err() {
+        return None;
+    }
+    match parse(path) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            println!("Warning: {} couldn't be read ({}), trying the backup", path, err);
+            match parse(&backup_path(path)) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    println!("Warning: the backup of {} couldn't be read either ({}), using defaults", path, err);
+                    None
+                }
+            }
+        }
+    }
+}
+
+// puts the .bak copy of a file back in its place, to undo a commit (see data::write_files)
+    // the backup is the version the readers were using -- a current version that can't be parsed isn't backed up, but it was
+    // being read from the backup anyway
+pub fn restore_backup(path: &str) -> Result<(), String> {
+    let bytes = fs::read(backup_path(path)).map_err(|err| format!("Failed to read the backup of {}: {}", path, err))?;
+    let temp = stage(path, &bytes)?;
+    fs::rename(&temp, path).map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        format!("Failed to restore {}: {}", path, err)
+    })
+}
+
+// deletes a file along with its backup, so a deleted file can't come back from the .bak copy
+pub fn remove(path: &str) -> Result<(), String> {
+    let _ = fs::remove_file(backup_path(path));
+    fs::remove_file(path).map_err(|err| err.to_string())
+}
+
+// a fresh, empty directory for one test to save files in, so the tests can't see each other's files (or the app's)
+    // name has to be different for every test, so it starts with the test's module
+#[cfg(test)]
+pub fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("sudoku-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the path of a file in a fresh directory (see scratch_dir)
+    fn scratch(name: &str) -> String {
+        scratch_dir(&format!("persist-{}", name)).join("file.json").to_string_lossy().into_owned()
+    }
+
+    // cuts the file in half, the way a crash in the middle of a write without this module would leave it
+    fn truncate(path: &str) {
+        let contents = fs::read(path).unwrap();
+        fs::write(path, &contents[..contents.len() / 2]).unwrap();
+    }
+
+    #[test]
+    fn missing_file_reads_as_none() {
+        let path = scratch("missing");
+        assert_eq!(read_json::<Vec<u32>>(&path), None);
+    }
+
+    #[test]
+    fn truncated_file_falls_back_to_the_previous_version() {
+        let path = scratch("truncated");
+        write_atomic(&path, b"[1, 2, 3]").unwrap();
+        write_atomic(&path, b"[4, 5, 6]").unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&path), Some(vec![4, 5, 6]));
+        truncate(&path);
+        assert_eq!(read_json::<Vec<u32>>(&path), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn truncated_file_doesnt_replace_the_backup() {
+        let path = scratch("rotate");
+        write_atomic(&path, b"[1, 2, 3]").unwrap();
+        write_atomic(&path, b"[4, 5, 6]").unwrap();
+        truncate(&path);
+
+        // saving over the cut short file keeps the good backup, so if the new file is cut short too there is still something to read
+        write_atomic(&path, b"[7, 8, 9]").unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&backup_path(&path)), Some(vec![1, 2, 3]));
+        truncate(&path);
+        assert_eq!(read_json::<Vec<u32>>(&path), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn neither_copy_readable_is_none() {
+        let path = scratch("both");
+        write_atomic(&path, b"[1, 2, 3]").unwrap();
+        write_atomic(&path, b"[4, 5, 6]").unwrap();
+        truncate(&path);
+        truncate(&backup_path(&path));
+        assert_eq!(read_json::<Vec<u32>>(&path), None);
+    }
+
+    #[test]
+    fn removed_file_doesnt_come_back_from_the_backup() {
+        let path = scratch("remove");
+        write_atomic(&path, b"[1, 2, 3]").unwrap();
+        write_atomic(&path, b"[4, 5, 6]").unwrap();
+        remove(&path).unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&path), None);
+        assert!(fs::metadata(backup_path(&path)).is_err());
+    }
+}
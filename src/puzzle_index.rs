@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use crate::Puzzle;
+
+// the extension of a line-delimited puzzle file, which has one puzzle object per line instead of a "puzzles" array
+pub const LINES_EXTENSION: &str = "jsonl";
+
+// returns true if the puzzle file has one puzzle per line -- a .jsonl file always does, and any other file does if its
+    // first line is a whole puzzle on its own (the first line of an array file is only the start of the object around the array)
+pub fn is_line_delimited(path: &str) -> bool {
+    if Path::new(path).extension().is_some_and(|extension| extension == LINES_EXTENSION) {
+        return true;
+    }
+    let Ok(file) = File::open(path) else { return false };
+    let mut first_line = String::new();
+    BufReader::new(file).read_line(&mut first_line).is_ok() && serde_json::from_str::<Puzzle>(first_line.trim()).is_ok()
+}
+
+/*
+    The LineIndex struct is where each puzzle starts in a line-delimited puzzle file, so a single puzzle can be read without parsing the others
+    offsets are the byte offsets of every line that isn't blank -- building them reads through the file once, but only looks for
+        the ends of the lines, and nothing but the offsets is kept
+*/
+pub struct LineIndex {
+    path: String,
+    reader: BufReader<File>,
+    offsets: Vec<u64>,
+}
+
+impl LineIndex {
+    // opens a line-delimited puzzle file and finds where each of its puzzles starts
+    pub fn open(path: &str) -> Result<Self, String> {
+        let read_error = |err: std::io::Error| format!("Failed to read {}: {}", path, err);
+        let mut reader = BufReader::new(File::open(path).map_err(read_error)?);
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).map_err(read_error)?;
+            if read == 0 {
+                break;
+            }
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                offsets.push(offset);
+            }
+            offset += read as u64;
+        }
+        Ok(Self { path: path.to_string(), reader, offsets })
+    }
+
+    // the number of puzzles in the file
+    pub fn count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    // reads and parses the puzzle at index (counting from 0, and skipping blank lines) -- only that line is read from the file
+    pub fn read(&mut self, index: usize) -> Result<Puzzle, String> {
+        let offset = *self.offsets.get(index).ok_or(format!("{} has no puzzle {}", self.path, index))?;
+        let mut line = String::new();
+        self.reader.seek(SeekFrom::Start(offset))
+            .and_then(|_| self.reader.read_line(&mut line))
+            .map_err(|err| format!("Failed to read {}: {}", self.path, err))?;
+        serde_json::from_str(line.trim()).map_err(|err| format!("{} is corrupt at puzzle {}: {}", self.path, index, err))
+    }
+}
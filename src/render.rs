@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use eframe::egui::{Context, Style};
+use serde::{Deserialize, Serialize};
+use crate::settings::Settings;
+
+// how often to repaint while something in the game runs on the clock, when the clock's own repaints are turned off
+const TIMED_REPAINT: Duration = Duration::from_secs(1);
+
+// the TimerGranularity enum is how often the clock on screen ticks -- Off means the clock only updates when there is input
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TimerGranularity {
+    Second,
+    Tenth,
+    Off,
+}
+
+/*
+    The RenderBudget struct is how much repainting the app is allowed to do, worked out from the performance settings
+    the game code asks the budget instead of checking the settings itself, so battery saver only has to be handled here
+    timer_interval is how often to repaint so the clock keeps ticking (None means only repaint on input), and animations is
+    whether egui's widget animations are on
+*/
+#[derive(Clone, Copy, PartialEq)]
+pub struct RenderBudget {
+    pub timer_interval: Option<Duration>,
+    pub animations: bool,
+}
+
+impl RenderBudget {
+    // battery saver overrides the other settings, turning off animations and the clock's repaints
+    pub fn from_settings(settings: &Settings) -> Self {
+        if settings.battery_saver {
+            return Self {
+                timer_interval: None,
+                animations: false,
+            };
+        }
+        Self {
+            timer_interval: match settings.timer_granularity {
+                TimerGranularity::Second => Some(Duration::from_secs(1)),
+                TimerGranularity::Tenth => Some(Duration::from_millis(100)),
+                TimerGranularity::Off => None,
+            },
+            animations: settings.animations,
+        }
+    }
+
+    // asks egui to repaint in time for the clock's next tick
+        // timed is set while something in the game runs on the clock (e.g. a blitz countdown), which still gets a repaint every
+        // TIMED_REPAINT when the clock's repaints are off -- otherwise it would only move when the user gives input
+    pub fn schedule_timer_repaint(&self, ctx: &Context, timed: bool) {
+        match self.timer_interval {
+            Some(interval) => ctx.request_repaint_after(interval),
+            None if timed => ctx.request_repaint_after(TIMED_REPAINT),
+            None => {}
+        }
+    }
+
+    // turns egui's animations on or off -- the style is only touched when it needs to change
+    pub fn apply_animations(&self, ctx: &Context) {
+        let animation_time = if self.animations { Style::default().animation_time } else { 0.0 };
+        if ctx.style().animation_time != animation_time {
+            ctx.style_mut(|style| style.animation_time = animation_time);
+        }
+    }
+
+    // returns true if the clock should show tenths of a second
+    pub fn shows_tenths(&self) -> bool {
+        self.timer_interval.is_some_and(|interval| interval < Duration::from_secs(1))
+    }
+}
+
+// the FrameCounter struct counts the frames rendered in the last 10 seconds, to check how much the performance settings save
+#[derive(Default)]
+pub struct FrameCounter {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameCounter {
+    // records a frame, and forgets the frames that are more than 10 seconds old
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while self.frames.front().is_some_and(|&frame| now.duration_since(frame) > Duration::from_secs(10)) {
+            self.frames.pop_front();
+        }
+    }
+
+    // the frames rendered in the last 10 seconds -- only shown in dev-tools builds
+    #[cfg(feature = "dev-tools")]
+    pub fn count(&self) -> usize {
+        self.frames.len()
+    }
+
+    // the frames rendered in the last second -- frames are only drawn when something changes, so this drops when the app is idle
+    pub fn fps(&self) -> usize {
+        let now = Instant::now();
+        self.frames.iter().rev().take_while(|&&frame| now.duration_since(frame) <= Duration::from_secs(1)).count()
+    }
+
+    // the time between the last two frames, or None before there have been two
+    pub fn frame_time(&self) -> Option<Duration> {
+        let mut recent = self.frames.iter().rev();
+        match (recent.next(), recent.next()) {
+            (Some(&last), Some(&before)) => Some(last.duration_since(before)),
+            _ => None,
+        }
+    }
+}
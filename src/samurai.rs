@@ -0,0 +1,636 @@
+use std::collections::HashSet;
+use std::fs;
+use std::time::{Duration, Instant};
+use eframe::egui::{self, Align2, CentralPanel, Color32, Context, FontFamily, FontId, Key, Pos2, Rect, RichText, ScrollArea, Sense, Stroke, Ui, Vec2};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::board::{self, Grid, NO_PARITY};
+use crate::{clock, generator, layout, persist, solver, Sudoku};
+
+// the samurai board is 21 cells across -- five 9x9 grids, where each corner grid shares one 3x3 box with the center grid
+pub const SIZE: usize = 21;
+
+// the top left cell of each of the five grids: the four corners, then the center
+pub const GRID_ORIGINS: [(usize, usize); 5] = [(0, 0), (0, 12), (12, 0), (12, 12), (6, 6)];
+
+// a samurai game allows more strikes than a normal game, since there are five times as many cells to get wrong
+pub const STRIKE_LIMIT: u32 = 5;
+
+// generation stops removing cells once this many givens are left
+const TARGET_GIVENS: usize = 150;
+
+// the bundled samurai puzzles (see SamuraiPuzzle for the format)
+pub const PUZZLES_PATH: &str = "./puzzles/Samurai.json";
+
+// a samurai game that was closed part of the way through is kept next to the saved game, and deleted once it is resumed
+pub const SAVE_PATH: &str = "./samurai.json";
+
+// a samurai board is stored as one 21x21 grid, so the cells two grids share are stored once
+    // cells that aren't part of any of the five grids are ' '
+pub type SamuraiGrid = [[char; SIZE]; SIZE];
+
+// returns true if (row, col) is a cell of at least one of the five grids
+pub fn in_board(row: usize, col: usize) -> bool {
+    GRID_ORIGINS.iter().any(|&(r, c)| (r..r + 9).contains(&row) && (c..c + 9).contains(&col))
+}
+
+// copies one of the five grids out of the samurai board
+fn sub_grid(board: &SamuraiGrid, index: usize) -> Grid {
+    let (r, c) = GRID_ORIGINS[index];
+    std::array::from_fn(|row| std::array::from_fn(|col| board[r + row][c + col]))
+}
+
+// writes one of the five grids back into the samurai board
+fn set_sub_grid(board: &mut SamuraiGrid, index: usize, grid: &Grid) {
+    let (r, c) = GRID_ORIGINS[index];
+    for row in 0..9 {
+        for col in 0..9 {
+            board[r + row][c + col] = grid[row][col];
+        }
+    }
+}
+
+// returns true if (row, col) is a cell of the grid at index
+fn grid_contains(index: usize, row: usize, col: usize) -> bool {
+    let (r, c) = GRID_ORIGINS[index];
+    (r..r + 9).contains(&row) && (c..c + 9).contains(&col)
+}
+
+// the indexes of the grids that contain (row, col) -- one for most cells, two for the shared boxes
+fn grids_containing(row: usize, col: usize) -> Vec<usize> {
+    (0..5).filter(|&i| grid_contains(i, row, col)).collect()
+}
+
+// returns true if cell is in the same row or column as selected within one of selected's grids -- these are the cells
+    // the selected cell's digit has to be different from, so a row that carries on into the next grid over isn't highlighted there
+fn shares_line(selected: (usize, usize), cell: (usize, usize)) -> bool {
+    (selected.0 == cell.0 || selected.1 == cell.1)
+        && grids_containing(selected.0, selected.1).into_iter().any(|i| grid_contains(i, cell.0, cell.1))
+}
+
+// writes a samurai board as one string, row by row (21 rows of 21 cells), with the gaps between the grids as spaces
+pub fn board_to_string(board: &SamuraiGrid) -> String {
+    board.iter().flatten().collect()
+}
+
+// reads a board written by board_to_string, or None if the string isn't the right length
+fn string_to_board(string: &str) -> Option<SamuraiGrid> {
+    let cells: Vec<char> = string.chars().collect();
+    (cells.len() == SIZE * SIZE).then(|| std::array::from_fn(|row| std::array::from_fn(|col| cells[row * SIZE + col])))
+}
+
+// an empty samurai board, with '.' in every cell of the five grids and ' ' in the gaps between them
+fn empty_board() -> SamuraiGrid {
+    std::array::from_fn(|row| std::array::from_fn(|col| if in_board(row, col) { '.' } else { ' ' }))
+}
+
+/*
+    The SubPuzzle struct is one of the five grids of a samurai puzzle, as 81 character strings like the ones in the normal puzzle files
+*/
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SubPuzzle {
+    pub puzzle: String,
+    pub solution: String,
+}
+
+/*
+    The SamuraiPuzzle struct is one puzzle from the samurai puzzle file, stored as its five grids in the order of GRID_ORIGINS
+        (the four corners, then the center)
+    each corner shares a box with the center, so that box is written in both grids -- the two copies have to match
+    id is the puzzle's stable id, which is how playing it is remembered (see Stats::played_puzzles)
+*/
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SamuraiPuzzle {
+    pub id: String,
+    pub grids: [SubPuzzle; 5],
+}
+
+// the samurai puzzle file is a "puzzles" array, like the normal puzzle files
+#[derive(Serialize, Deserialize)]
+struct SamuraiPuzzles {
+    puzzles: Vec<SamuraiPuzzle>,
+}
+
+impl SamuraiPuzzle {
+    // reads every puzzle in the bundled samurai puzzle file
+    pub fn load_all() -> Result<Vec<Self>, String> {
+        let contents = fs::read_to_string(PUZZLES_PATH).map_err(|err| format!("Failed to read {}: {}", PUZZLES_PATH, err))?;
+        serde_json::from_str::<SamuraiPuzzles>(&contents)
+            .map(|file| file.puzzles)
+            .map_err(|err| format!("{} is corrupt: {}", PUZZLES_PATH, err))
+    }
+
+    // splits a samurai board and its solution into the five grids of the file format
+    pub fn from_boards(id: &str, puzzle: &SamuraiGrid, solution: &SamuraiGrid) -> Self {
+        Self {
+            id: id.to_string(),
+            grids: std::array::from_fn(|i| SubPuzzle {
+                puzzle: board::grid_to_string(&sub_grid(puzzle, i)),
+                solution: board::grid_to_string(&sub_grid(solution, i)),
+            }),
+        }
+    }
+
+    // puts the five grids together into one board, returning the puzzle and its solution
+        // returns why if a grid isn't 81 digits and '.'s, a given doesn't match the solution, or two grids disagree about a shared cell
+    pub fn to_boards(&self) -> Result<(SamuraiGrid, SamuraiGrid), String> {
+        let mut puzzle = empty_board();
+        let mut solution = empty_board();
+        for (index, grid) in self.grids.iter().enumerate() {
+            let cells_ok = |string: &str, allow_empty: bool| string.chars().count() == 81
+                && string.chars().all(|c| board::DIGITS.contains(&c) || (allow_empty && c == '.'));
+            if !cells_ok(&grid.puzzle, true) || !cells_ok(&grid.solution, false) {
+                return Err(format!("grid {} of samurai puzzle {} isn't 81 cells of digits", index, self.id));
+            }
+            let (r, c) = GRID_ORIGINS[index];
+            for (cell, (given, digit)) in grid.puzzle.chars().zip(grid.solution.chars()).enumerate() {
+                let (row, col) = (r + cell / 9, c + cell % 9);
+                if given != '.' && given != digit {
+                    return Err(format!("a given in grid {} of samurai puzzle {} doesn't match the solution", index, self.id));
+                }
+                // a cell already written by an earlier grid is in a shared box, and has to be the same in this one
+                if solution[row][col] != '.' && (puzzle[row][col], solution[row][col]) != (given, digit) {
+                    return Err(format!("grid {} of samurai puzzle {} doesn't match the grid it shares a box with", index, self.id));
+                }
+                puzzle[row][col] = given;
+                solution[row][col] = digit;
+            }
+        }
+        Ok((puzzle, solution))
+    }
+}
+
+/*
+    The SavedSamurai struct stores a samurai game in progress, so it can be picked back up after the app is closed
+    the boards are stored as 441 character strings (see board_to_string), and elapsed_secs is how long the game had been played
+    id is the bundled puzzle being played, or None if the board was generated
+*/
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedSamurai {
+    pub id: Option<String>,
+    pub starting: String,
+    pub player: String,
+    pub solution: String,
+    pub strikes: u32,
+    pub elapsed_secs: u64,
+    pub ranked: bool,
+}
+
+impl SavedSamurai {
+    // reads the saved samurai game (or its backup, see persist.rs), or None if there isn't one
+    pub fn load() -> Option<Self> {
+        persist::read_json(SAVE_PATH)
+    }
+
+    // writes the saved samurai game to path (SAVE_PATH in the app's folder) -- returns the error so the user can be told the game wasn't saved
+    pub fn save_to(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to save the samurai game: {}", err))?;
+        persist::write_atomic(path, json.as_bytes()).map_err(|err| format!("Failed to save the samurai game: {}", err))
+    }
+
+    // deletes the saved samurai game once it has been resumed, so it can't be resumed twice
+    pub fn delete() {
+        if let Err(err) = persist::remove(SAVE_PATH) {
+            println!("Failed to delete the saved samurai game: {}", err);
+        }
+    }
+}
+
+// generates a samurai puzzle, and returns it along with its solution
+    // the center grid is a random complete board, and each corner grid is solved around the box it shares with the center
+    // cells are only removed while every one of the five grids still has exactly one solution on its own, so the whole board does too
+pub fn generate() -> (SamuraiGrid, SamuraiGrid) {
+    let mut rng = rand::thread_rng();
+    let mut solution = empty_board();
+    set_sub_grid(&mut solution, 4, &generator::random_solution(&mut rng));
+    for corner in 0..4 {
+        let solved = solver::solve(&sub_grid(&solution, corner)).expect("a grid with only one box filled always has a solution");
+        set_sub_grid(&mut solution, corner, &solved);
+    }
+
+    let mut puzzle = solution;
+    let mut cells: Vec<(usize, usize)> = (0..SIZE * SIZE).map(|index| (index / SIZE, index % SIZE)).filter(|&(row, col)| in_board(row, col)).collect();
+    cells.shuffle(&mut rng);
+    let mut givens = cells.len();
+    for (row, col) in cells {
+        if givens <= TARGET_GIVENS {
+            break;
+        }
+        let digit = puzzle[row][col];
+        puzzle[row][col] = '.';
+        if grids_containing(row, col).into_iter().all(|i| solver::count_solutions_with_parity(&sub_grid(&puzzle, i), &NO_PARITY, 2) == 1) {
+            givens -= 1;
+        }
+        else {
+            puzzle[row][col] = digit;
+        }
+    }
+
+    (puzzle, solution)
+}
+
+/*
+    The Samurai struct is a game of samurai sudoku, which is played on its own screen
+    id is the bundled puzzle being played (see SamuraiPuzzle), or None if the board was generated
+    starting, player, and solution work like the grids on the Sudoku struct, but cover the whole 21x21 board
+    selected is the cell the user has clicked on, if any, and cell_size is the zoom level
+    started is when the game was started or resumed, played_before is how long it was played before being saved (0 for a new game),
+        and finished is how long it took once it has been won or lost
+    ranked is a bool that represents whether the result is recorded in stats
+*/
+pub struct Samurai {
+    pub id: Option<String>,
+    pub starting: SamuraiGrid,
+    pub player: SamuraiGrid,
+    pub solution: SamuraiGrid,
+    pub selected: Option<(usize, usize)>,
+    pub strikes: u32,
+    pub cell_size: f32,
+    pub started: Instant,
+    pub played_before: Duration,
+    pub finished: Option<Duration>,
+    pub ranked: bool,
+}
+
+impl Samurai {
+    // starts a samurai game -- a bundled puzzle that hasn't been played yet comes first, and once all of them have been played
+        // (or the puzzle file can't be read) a new board is generated
+    pub fn new(ranked: bool, played: &HashSet<String>) -> Self {
+        let bundled = SamuraiPuzzle::load_all()
+            .and_then(|puzzles| {
+                let fresh: Vec<SamuraiPuzzle> = puzzles.into_iter().filter(|puzzle| !played.contains(&puzzle.id)).collect();
+                let Some(puzzle) = fresh.choose(&mut rand::thread_rng()) else { return Ok(None) };
+                puzzle.to_boards().map(|boards| Some((puzzle.id.clone(), boards)))
+            })
+            .unwrap_or_else(|err| {
+                println!("{}, generating a samurai board instead", err);
+                None
+            });
+        let (id, (puzzle, solution)) = match bundled {
+            Some((id, boards)) => (Some(id), boards),
+            None => (None, generate()),
+        };
+        Self {
+            id,
+            starting: puzzle,
+            player: puzzle,
+            solution,
+            selected: None,
+            strikes: 0,
+            cell_size: 36.0,
+            started: Instant::now(),
+            played_before: Duration::ZERO,
+            finished: None,
+            ranked,
+        }
+    }
+
+    // picks a saved samurai game back up, with its clock and strikes -- returns why if the save's boards are the wrong size
+    pub fn from_saved(saved: SavedSamurai) -> Result<Self, String> {
+        let board = |string: &str| string_to_board(string).ok_or("The saved samurai game is corrupt".to_string());
+        Ok(Self {
+            id: saved.id,
+            starting: board(&saved.starting)?,
+            player: board(&saved.player)?,
+            solution: board(&saved.solution)?,
+            selected: None,
+            strikes: saved.strikes,
+            cell_size: 36.0,
+            started: Instant::now(),
+            played_before: Duration::from_secs(saved.elapsed_secs),
+            finished: None,
+            ranked: saved.ranked,
+        })
+    }
+
+    // the game as a saved samurai game
+    pub fn to_saved(&self) -> SavedSamurai {
+        SavedSamurai {
+            id: self.id.clone(),
+            starting: board_to_string(&self.starting),
+            player: board_to_string(&self.player),
+            solution: board_to_string(&self.solution),
+            strikes: self.strikes,
+            elapsed_secs: self.elapsed().as_secs(),
+            ranked: self.ranked,
+        }
+    }
+
+    // how long the game has been played, including before it was saved -- once it is over, how long it took
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| clock::add_elapsed(self.played_before, self.started.elapsed()))
+    }
+
+    // returns true until the game has been won or lost
+    pub fn in_progress(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    // enters digit into the cell at (row, col) -- a wrong digit is a strike, just like the normal game
+        // typing the digit that is already in the cell changes nothing, so the same wrong digit can't be a strike twice
+    pub fn enter_digit(&mut self, row: usize, col: usize, digit: char) {
+        if self.starting[row][col] != '.' || self.player[row][col] == digit {
+            return;
+        }
+        self.player[row][col] = digit;
+        if digit != self.solution[row][col] {
+            self.strikes = self.strikes.saturating_add(1);
+        }
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.player == self.solution
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.strikes >= STRIKE_LIMIT
+    }
+}
+
+impl Sudoku {
+    // displays the samurai game, opened from the difficulty screen
+        // the board is drawn with the painter instead of buttons, since it has over 300 cells, and it scrolls when zoomed in
+    pub(crate) fn samurai_screen(&mut self, ctx: &Context) {
+        let Some(game) = &mut self.samurai else { return };
+        let mut quit = false;
+
+        // the first frame after the game ends, stop the clock and record the result
+        if game.finished.is_none() && (game.is_won() || game.is_lost()) {
+            game.finished = Some(game.elapsed());
+            if game.ranked {
+                if let Some(id) = &game.id {
+                    self.stats.played_puzzles.insert(id.clone());
+                }
+                if game.is_won() {
+                    let seconds = game.elapsed().as_secs();
+                    self.stats.record_win("Samurai", seconds, crate::score::compute("Samurai", seconds, game.strikes, 0), game.strikes == 0, false);
+                }
+                else {
+                    self.stats.record_loss("Samurai", false);
+                }
+                self.stats.save();
+            }
+        }
+
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(RichText::new("Samurai").font(FontId::new(30.0, FontFamily::Proportional)));
+                crate::unranked_badge(ui, game.ranked);
+                ui.label(format!("Time elapsed: {}    Strikes: {} / {}", crate::format_time(game.elapsed().as_secs()), game.strikes, STRIKE_LIMIT));
+                if game.is_won() {
+                    ui.heading(RichText::new("You Win!").color(Color32::from_rgb(0, 124, 255)));
+                }
+                else if game.is_lost() {
+                    ui.heading(RichText::new("Game over!").color(Color32::from_rgb(255, 60, 110)));
+                }
+                layout::centered_row(ui, "samurai_controls", |ui| {
+                    ui.label("Zoom:");
+                    ui.add(egui::Slider::new(&mut game.cell_size, 24.0..=60.0).show_value(false));
+                    if ui.button(if game.finished.is_some() { "Back to menu" } else { "Quit to menu" }).clicked() {
+                        quit = true;
+                    }
+                });
+                ui.add_space(10.0);
+
+                ScrollArea::both().show(ui, |ui| draw_board(ui, game));
+            });
+
+            // digits fill the selected cell, backspace clears it, and the arrow keys move the selection
+            if game.finished.is_none() {
+                handle_keys(ui, game);
+            }
+        });
+
+        if quit {
+            self.samurai = None;
+        }
+    }
+}
+
+// draws the samurai board and selects the cell that was clicked
+fn draw_board(ui: &mut Ui, game: &mut Samurai) {
+    let cell_size = game.cell_size;
+    let (response, painter) = ui.allocate_painter(Vec2::splat(cell_size * SIZE as f32), Sense::click());
+    let origin = response.rect.min;
+    let dark_mode = ui.visuals().dark_mode;
+    let cell_rect = |row: usize, col: usize| Rect::from_min_size(origin + Vec2::new(col as f32 * cell_size, row as f32 * cell_size), Vec2::splat(cell_size));
+
+    if response.clicked() {
+        if let Some((row, col)) = response.interact_pointer_pos().and_then(|pos| cell_at(origin, cell_size, pos)) {
+            if in_board(row, col) {
+                game.selected = Some((row, col));
+            }
+        }
+    }
+
+    let background = if dark_mode { Color32::from_gray(45) } else { Color32::WHITE };
+    let highlight = if dark_mode { Color32::from_gray(70) } else { Color32::from_rgb(225, 232, 250) };
+    let selected_fill = if dark_mode { Color32::from_rgb(60, 60, 120) } else { Color32::from_rgb(200, 200, 255) };
+    let line = if dark_mode { Color32::from_gray(90) } else { Color32::from_gray(200) };
+    let box_line = if dark_mode { Color32::from_gray(200) } else { Color32::from_gray(60) };
+    let font = crate::fonts::digit_font(cell_size * 0.65);
+
+    for row in 0..SIZE {
+        for col in 0..SIZE {
+            if !in_board(row, col) {
+                continue;
+            }
+            // the selected cell is filled, and the rest of its row and column are highlighted within the grids it is in
+            let fill = match game.selected {
+                Some(selected) if selected == (row, col) => selected_fill,
+                Some(selected) if shares_line(selected, (row, col)) => highlight,
+                _ => background,
+            };
+            let rect = cell_rect(row, col);
+            painter.rect_filled(rect, 0.0, fill);
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, line));
+
+            let num = game.player[row][col];
+            if num != '.' {
+                let color = if game.starting[row][col] != '.' {
+                    ui.visuals().text_color()
+                }
+                else if num == game.solution[row][col] {
+                    Color32::from_rgb(0, 124, 255)
+                }
+                else {
+                    Color32::from_rgb(255, 60, 110)
+                };
+                painter.text(rect.center(), Align2::CENTER_CENTER, num, font.clone(), color);
+            }
+        }
+    }
+
+    // every grid's origin is a multiple of 3, so all of the 3x3 boxes line up on one 7x7 pattern of boxes
+    for box_row in 0..SIZE / 3 {
+        for box_col in 0..SIZE / 3 {
+            if in_board(box_row * 3, box_col * 3) {
+                let rect = Rect::from_min_max(cell_rect(box_row * 3, box_col * 3).min, cell_rect(box_row * 3 + 2, box_col * 3 + 2).max);
+                painter.rect_stroke(rect, 0.0, Stroke::new(2.0, box_line));
+            }
+        }
+    }
+}
+
+// handles the keyboard for the samurai board (see Samurai::enter_digit for what a digit does)
+fn handle_keys(ui: &Ui, game: &mut Samurai) {
+    let Some((row, col)) = game.selected else { return };
+
+    for (key, (d_row, d_col)) in [(Key::ArrowUp, (-1, 0)), (Key::ArrowDown, (1, 0)), (Key::ArrowLeft, (0, -1)), (Key::ArrowRight, (0, 1))] {
+        if ui.input(|input| input.key_pressed(key)) {
+            // skip over the gaps between the grids
+            let mut next = (row as i32 + d_row, col as i32 + d_col);
+            while (0..SIZE as i32).contains(&next.0) && (0..SIZE as i32).contains(&next.1) {
+                if in_board(next.0 as usize, next.1 as usize) {
+                    game.selected = Some((next.0 as usize, next.1 as usize));
+                    break;
+                }
+                next = (next.0 + d_row, next.1 + d_col);
+            }
+        }
+    }
+
+    if game.starting[row][col] != '.' {
+        return;
+    }
+    for (i, key) in [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9].into_iter().enumerate() {
+        if ui.input(|input| input.key_pressed(key)) {
+            game.enter_digit(row, col, board::DIGITS[i]);
+        }
+    }
+    if ui.input(|input| input.key_pressed(Key::Backspace)) {
+        game.player[row][col] = '.';
+    }
+}
+
+// the cell under a point on the board, or None if the point is outside the 21x21 area
+fn cell_at(origin: Pos2, cell_size: f32, pos: Pos2) -> Option<(usize, usize)> {
+    let (row, col) = (((pos.y - origin.y) / cell_size).floor(), ((pos.x - origin.x) / cell_size).floor());
+    (row >= 0.0 && col >= 0.0 && (row as usize) < SIZE && (col as usize) < SIZE).then_some((row as usize, col as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled() -> SamuraiPuzzle {
+        SamuraiPuzzle::load_all().unwrap().remove(0)
+    }
+
+    // a game of the first bundled puzzle, as if it had just been started
+    fn game() -> Samurai {
+        let (puzzle, solution) = bundled().to_boards().unwrap();
+        Samurai::from_saved(SavedSamurai {
+            id: Some(bundled().id),
+            starting: board_to_string(&puzzle),
+            player: board_to_string(&puzzle),
+            solution: board_to_string(&solution),
+            strikes: 0,
+            elapsed_secs: 0,
+            ranked: true,
+        }).unwrap()
+    }
+
+    // the first empty cell of the board, along with a digit that is wrong there
+    fn empty_cell(game: &Samurai) -> (usize, usize, char) {
+        let (row, col) = (0..SIZE * SIZE).map(|i| (i / SIZE, i % SIZE)).find(|&(row, col)| game.player[row][col] == '.').unwrap();
+        let wrong = *board::DIGITS.iter().find(|&&digit| digit != game.solution[row][col]).unwrap();
+        (row, col, wrong)
+    }
+
+    #[test]
+    fn bundled_puzzles_have_one_solution_in_every_grid() {
+        let puzzles = SamuraiPuzzle::load_all().unwrap();
+        assert!(!puzzles.is_empty());
+        for puzzle in puzzles {
+            let (board, solution) = puzzle.to_boards().unwrap();
+            for i in 0..5 {
+                assert_eq!(solver::solutions_with_parity(&sub_grid(&board, i), &NO_PARITY, 2), vec![sub_grid(&solution, i)], "grid {} of {}", i, puzzle.id);
+            }
+        }
+    }
+
+    #[test]
+    fn grids_split_and_join_back_the_same() {
+        let puzzle = bundled();
+        let (board, solution) = puzzle.to_boards().unwrap();
+        assert_eq!(SamuraiPuzzle::from_boards(&puzzle.id, &board, &solution), puzzle);
+    }
+
+    #[test]
+    fn grids_that_disagree_about_a_shared_box_are_rejected() {
+        let mut puzzle = bundled();
+        // the center grid's top left cell is in the box it shares with the top left grid
+        let center = &mut puzzle.grids[4];
+        let first = center.solution.chars().next().unwrap();
+        let other = if first == '1' { '2' } else { '1' };
+        center.solution.replace_range(0..1, &other.to_string());
+        center.puzzle.replace_range(0..1, ".");
+        assert!(puzzle.to_boards().is_err());
+    }
+
+    #[test]
+    fn grids_that_arent_81_cells_are_rejected() {
+        let mut puzzle = bundled();
+        puzzle.grids[2].puzzle.pop();
+        assert!(puzzle.to_boards().is_err());
+    }
+
+    #[test]
+    fn highlight_stays_within_the_selected_cells_grids() {
+        // a cell of the top left grid: its row carries on into the top right grid, which isn't highlighted
+        assert!(shares_line((0, 4), (0, 8)));
+        assert!(!shares_line((0, 4), (0, 12)));
+        assert!(!shares_line((0, 4), (1, 5)));
+        // a cell of a shared box is in two grids, so both of its grids' lines are highlighted
+        assert!(shares_line((6, 6), (6, 14)));
+        assert!(shares_line((6, 6), (14, 6)));
+        assert!(shares_line((6, 6), (0, 6)));
+        // the bottom left grid has column 6 too, but doesn't contain (6, 6)
+        assert!(!shares_line((6, 6), (15, 6)));
+    }
+
+    #[test]
+    fn same_wrong_digit_twice_is_one_strike() {
+        let mut game = game();
+        let (row, col, wrong) = empty_cell(&game);
+        game.enter_digit(row, col, wrong);
+        game.enter_digit(row, col, wrong);
+        assert_eq!(game.strikes, 1);
+        game.enter_digit(row, col, game.solution[row][col]);
+        assert_eq!(game.strikes, 1);
+        assert_eq!(game.player[row][col], game.solution[row][col]);
+    }
+
+    #[test]
+    fn givens_cant_be_changed() {
+        let mut game = game();
+        let (row, col) = (0..SIZE * SIZE).map(|i| (i / SIZE, i % SIZE)).find(|&(row, col)| game.starting[row][col] != '.' && game.starting[row][col] != ' ').unwrap();
+        let given = game.player[row][col];
+        game.enter_digit(row, col, if given == '1' { '2' } else { '1' });
+        assert_eq!(game.player[row][col], given);
+        assert_eq!(game.strikes, 0);
+    }
+
+    #[test]
+    fn saved_game_comes_back_the_same() {
+        let mut game = game();
+        let (row, col, wrong) = empty_cell(&game);
+        game.enter_digit(row, col, wrong);
+        game.played_before = Duration::from_secs(125);
+        let saved = game.to_saved();
+        let json = serde_json::to_string(&saved).unwrap();
+        let restored = Samurai::from_saved(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(restored.to_saved(), saved);
+        assert_eq!(restored.player, game.player);
+        assert_eq!(restored.strikes, 1);
+        assert_eq!(restored.elapsed().as_secs(), 125);
+    }
+
+    #[test]
+    fn corrupt_save_is_rejected() {
+        let mut saved = game().to_saved();
+        saved.player.pop();
+        assert!(Samurai::from_saved(saved).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use crate::board::Grid;
+use crate::notes::Notes;
+use crate::{ColorTag, Edit};
+
+/*
+    The Sandbox struct is the real game, put aside while the user tries things out on a copy of its board
+    the copy is played in the Sudoku struct's own fields like any other game, so this only keeps the parts of the real game the copy
+        can change: its board, notes, color tags, undo history, and strikes
+    nothing done in the sandbox is a strike or is logged, so the real game's mistakes and actions don't need to be kept here
+*/
+pub struct Sandbox {
+    pub player_grid: Grid,
+    pub notes: Notes,
+    pub color_tags: [[Option<ColorTag>; 9]; 9],
+    pub history: Vec<Edit>,
+    pub strikes: u32,
+}
+
+// the entries to apply back to the real game from the sandbox: every cell that is empty on the real board but was filled in the sandbox
+    // cells the real game already had a digit in are left alone, even if the sandbox changed or erased them
+    // the entries are in reading order (row by row), which is the order they are placed and judged in
+pub fn additions(real: &Grid, sandbox: &Grid) -> Vec<(usize, usize, char)> {
+    (0..81)
+        .map(|i| (i / 9, i % 9))
+        .filter(|&(row, col)| real[row][col] == '.' && sandbox[row][col] != '.')
+        .map(|(row, col)| (row, col, sandbox[row][col]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board;
+
+    const REAL: &str = "1.4.28...3.815...7265.7.4.17438..15...2.4.73...97.162..3.......8.1..6....263.7.4.";
+
+    #[test]
+    fn nothing_changed_adds_nothing() {
+        let real = board::string_to_grid(REAL);
+        assert!(additions(&real, &real).is_empty());
+    }
+
+    #[test]
+    fn filled_cells_are_added_in_reading_order() {
+        let real = board::string_to_grid(REAL);
+        let mut sandbox = real;
+        sandbox[4][0] = '6';
+        sandbox[0][1] = '7';
+        sandbox[0][3] = '6';
+        assert_eq!(additions(&real, &sandbox), vec![(0, 1, '7'), (0, 3, '6'), (4, 0, '6')]);
+    }
+
+    #[test]
+    fn cells_the_real_game_already_had_are_left_alone() {
+        let real = board::string_to_grid(REAL);
+        let mut sandbox = real;
+        // cells that already had a digit on the real board, changed in the sandbox
+        sandbox[0][0] = '9';
+        sandbox[0][4] = '5';
+        assert!(additions(&real, &sandbox).is_empty());
+    }
+
+    #[test]
+    fn erased_cells_are_ignored() {
+        let real = board::string_to_grid(REAL);
+        let mut sandbox = real;
+        sandbox[0][0] = '.';
+        sandbox[1][0] = '.';
+        sandbox[0][1] = '7';
+        assert_eq!(additions(&real, &sandbox), vec![(0, 1, '7')]);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use crate::actions::Action;
+use crate::persist;
+use crate::settings::GameOptions;
+use crate::stats::MistakeRecord;
+use crate::PuzzleMetadata;
+
+// the saved game is kept next to the settings file, and deleted once it is resumed
+pub const SAVEGAME_PATH: &str = "./savegame.json";
+
+/*
+    The SavedGame struct stores a game in progress, so it can be picked back up after the app is closed
+    the grids are stored as 81 character strings, the same format as the puzzle files
+    elapsed_secs is how long the game had been played, and time_budget_secs is the blitz countdown (if it was a blitz game)
+    mistakes and actions are the game's logs so far, so the game is recorded the same as if it had never been closed
+    variant is set if the board is a transformed version of the puzzle (saves from before it was added are treated as untransformed)
+    hints_left is how many note hints the game had left -- saves from before the hint budget get the difficulty's budget again
+    hints_used is how many note hints the game had shown, which older saves don't have (they are taken to have shown none)
+    metadata is the puzzle's credits (see PuzzleMetadata), which older saves don't have
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedGame {
+    pub difficulty: String,
+    pub puzzle_id: String,
+    pub starting: String,
+    pub player: String,
+    pub solution: String,
+    pub parity: String,
+    pub strikes: u32,
+    pub elapsed_secs: u64,
+    pub time_budget_secs: Option<u64>,
+    pub options: GameOptions,
+    pub ranked: bool,
+    pub is_retry: bool,
+    pub mistakes: Vec<MistakeRecord>,
+    pub actions: Vec<Action>,
+    #[serde(default)]
+    pub variant: bool,
+    #[serde(default)]
+    pub hints_left: Option<u8>,
+    #[serde(default)]
+    pub hints_used: u32,
+    #[serde(default)]
+    pub metadata: PuzzleMetadata,
+}
+
+impl SavedGame {
+    // reads the saved game (or its backup, see persist.rs), or None if there isn't one (or neither can be parsed)
+    pub fn load() -> Option<Self> {
+        persist::read_json(SAVEGAME_PATH)
+    }
+
+    // writes the saved game to path (SAVEGAME_PATH in the app's folder) -- returns the error so the user can be told the game wasn't saved
+    pub fn save_to(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("Failed to save the game: {}", err))?;
+        persist::write_atomic(path, json.as_bytes()).map_err(|err| format!("Failed to save the game: {}", err))
+    }
+
+    // deletes the saved game (and its backup) once it has been resumed, so it can't be resumed twice
+    pub fn delete() {
+        if let Err(err) = persist::remove(SAVEGAME_PATH) {
+            println!("Failed to delete the saved game: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Validation;
+
+    const PUZZLE: &str = "1.4.28...3.815...7265.7.4.17438..15...2.4.73...97.162..3.......8.1..6....263.7.4.";
+    const SOLUTION: &str = "174628593398154267265973481743862159612549738589731624437285916851496372926317845";
+
+    fn saved(variant: bool) -> SavedGame {
+        SavedGame {
+            difficulty: "Beginner".to_string(),
+            puzzle_id: "b-1".to_string(),
+            starting: PUZZLE.to_string(),
+            player: PUZZLE.replacen('.', "7", 1),
+            solution: SOLUTION.to_string(),
+            parity: ".".repeat(81),
+            strikes: 1,
+            elapsed_secs: 95,
+            time_budget_secs: Some(600),
+            options: GameOptions {
+                strike_limit: 3,
+                highlight_errors: true,
+                show_unit_counters: false,
+                arcade: false,
+                points: None,
+                validation: Validation::Solution,
+            },
+            ranked: true,
+            is_retry: false,
+            mistakes: Vec::new(),
+            actions: Vec::new(),
+            variant,
+            hints_left: Some(2),
+            hints_used: 1,
+            metadata: PuzzleMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn saved_game_round_trips() {
+        for variant in [false, true] {
+            let json = serde_json::to_value(saved(variant)).unwrap();
+            let back: SavedGame = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&back).unwrap(), json);
+            assert_eq!(back.variant, variant);
+            assert_eq!(back.puzzle_id, "b-1");
+        }
+    }
+
+    #[test]
+    fn save_from_before_variants_loads_untransformed() {
+        let mut json = serde_json::to_value(saved(true)).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        for field in ["variant", "hints_left", "hints_used", "metadata"] {
+            fields.remove(field);
+        }
+        let old: SavedGame = serde_json::from_value(json).unwrap();
+        assert!(!old.variant);
+        assert_eq!(old.hints_left, None);
+        assert_eq!(old.hints_used, 0);
+    }
+}
@@ -0,0 +1,64 @@
+// the scoring constants -- tweak these to rebalance the score
+
+// the score a win starts from at each difficulty, before any penalties
+    // difficulties without a set level have no score at all -- Test, and saved collections, which can mix puzzles of any level
+pub fn base_score(difficulty: &str) -> Option<i64> {
+    match difficulty {
+        "Beginner" => Some(1000),
+        "Intermediate" => Some(2000),
+        "Advanced" => Some(3000),
+        "Samurai" => Some(6000),
+        _ => None,
+    }
+}
+
+// points lost for every second on the clock
+pub const TIME_PENALTY_PER_SEC: i64 = 1;
+
+// points lost for every strike
+pub const STRIKE_PENALTY: i64 = 150;
+
+// points lost for every note hint shown
+pub const HINT_PENALTY: i64 = 100;
+
+// works out the score of a win: score = base(difficulty) - time_penalty * seconds - strike_penalty * strikes - hint_penalty * hints
+    // the score stops at zero, so a win slow or messy enough to lose every point scores 0 instead of going negative
+    // returns None for a difficulty that has no score (see base_score)
+pub fn compute(difficulty: &str, seconds: u64, strikes: u32, hints: u32) -> Option<i64> {
+    let base = base_score(difficulty)?;
+    Some((base - TIME_PENALTY_PER_SEC * seconds as i64 - STRIKE_PENALTY * strikes as i64 - HINT_PENALTY * hints as i64).max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_instant_win_scores_the_base() {
+        assert_eq!(compute("Beginner", 0, 0, 0), Some(1000));
+        assert_eq!(compute("Samurai", 0, 0, 0), Some(6000));
+    }
+
+    #[test]
+    fn every_penalty_comes_off() {
+        let expected = 2000 - 100 * TIME_PENALTY_PER_SEC - 2 * STRIKE_PENALTY - 3 * HINT_PENALTY;
+        assert_eq!(compute("Intermediate", 100, 2, 3), Some(expected));
+    }
+
+    #[test]
+    fn hints_cost_points() {
+        assert!(compute("Advanced", 60, 0, 1) < compute("Advanced", 60, 0, 0));
+    }
+
+    #[test]
+    fn score_stops_at_zero() {
+        assert_eq!(compute("Beginner", 5000, 0, 0), Some(0));
+        assert_eq!(compute("Beginner", 0, u32::MAX, u32::MAX), Some(0));
+    }
+
+    #[test]
+    fn difficulties_without_a_level_have_no_score() {
+        assert_eq!(compute("Test", 10, 0, 0), None);
+        assert_eq!(compute("My collection", 10, 0, 0), None);
+    }
+}
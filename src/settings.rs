@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::fonts::DigitStyle;
+use crate::persist;
+use crate::render::TimerGranularity;
+use crate::window::WindowGeometry;
+
+// the settings are saved next to the puzzles folder so they persist between launches
+pub const SETTINGS_PATH: &str = "./settings.json";
+
+/*
+    The Settings struct stores the user's preferences
+    username is the name the user gave during setup -- None means it was skipped, so the name passed to Sudoku::new is used
+    dark_mode is the user's theme choice -- None means follow the system's light/dark preference
+    digit_style is the font used for the digits on the board
+    transform_puzzles makes get_puzzle apply a random rotation/reflection/relabeling to each new puzzle
+    transform_repeats does the same only for puzzles that have been played before, once every puzzle of a difficulty has been played
+    show_unit_counters shows the number of empty cells left in each row and column along the edges of the board
+    sweep_forced_digits lets Alt + a digit place that digit in every cell where it is forced
+    auto_notes keeps every empty cell's notes filled with its candidates, updating them whenever a digit is placed or erased
+    ghost_preview shows a typed digit faintly first, and only enters it (and risks a strike) when it is confirmed
+    entry_debounce_ms is how soon after a digit another digit in the same cell is ignored, so key repeat can't enter it twice (0 turns it off)
+    row_typing moves the selection to the next empty cell in the row after a correct digit, so a row can be typed quickly
+    enter_advances makes Enter move the selection to the next empty cell in reading order (it still confirms a previewed digit first)
+    stuck_nudge tells the user a single is available when they haven't made progress for stuck_minutes minutes (it never says where)
+    confirm_quit asks before closing the window in the middle of a game, with the option to save it for later
+    quick_start skips the game options panel after a difficulty is picked and starts the game straight away
+        (shift-clicking a difficulty does the same for one game)
+    highlight_errors colors wrong entries red as soon as they are entered
+    auto_erase shows a wrong entry in red for a moment and then erases it -- the strike still counts (see AUTO_ERASE)
+    sandbox_apply lets the entries made in a sandbox be applied back to the real game, where each one is judged like a normal entry
+        (see Sudoku::leave_sandbox) -- when it is off, a sandbox can only be thrown away
+    validation decides what makes an entry wrong (the solution, or only the rules) -- see Validation
+    show_strikes shows the strike boxes in the header -- when it is off, strikes still end the game, they just aren't shown
+    show_clean_indicator shows "Clean so far" in the header until the user's first strike
+    show_tutorial shows the tutorial callouts on the next launch -- it is turned on for the first launch, and off once the tutorial
+        is finished or the user asks not to see it again
+    show_pb_pace shows a toast once per game when the clock passes the user's best time for the difficulty
+    show_time_rank shows how a win's time ranks against the user's earlier wins at the difficulty on the win screen
+    record_loss_details records the survival time and fill percentage of each lost game, for players who want more than wins tracked
+    warm_up plays a quick 4x4 warm-up puzzle before each new game (retries skip it)
+    check_for_updates opts in to checking GitHub for a newer release, at most once a day
+    last_update_check is when the last update check happened, in seconds since the unix epoch
+    arcade_mode turns on arcade mode, where a strike is given back after every arcade_recovery_secs seconds of error-free play
+    strike_limit is the number of strikes that ends a game, unless a difficulty or the game itself overrides it
+    points_mode replaces the strike limit with a pool of starting_points, where each wrong entry costs points_per_mistake
+        and the game is lost when the points run out
+    warn_strikes_left is how close to losing (in strikes left) a strike starts warning the user, or 0 for no warnings --
+        strike_toast and strike_flash pick how the warning is given (a toast saying how many are left, and a red flash over the window)
+    grid_background draws a faint pattern behind the grid so the play area stands out from the window
+    digit_chart shows a small bar chart beside the grid of how many of each digit are on the board
+    crosshair draws faint lines across the whole grid through the selected cell, so the selection is easy to find on a busy board
+    show_candidates shows the digits that could legally go in the selected empty cell, if it doesn't have any notes
+    mini_timer keeps a small always-on-top clock on screen while the window is minimized during a game (see Sudoku::mini_timer)
+    box_contrast is how different the two checkerboard shades of the 3x3 boxes are (0 to 1), and pastel_boxes tints each box its own faint color
+    cell_gap is the space between cells inside a 3x3 box, and box_gap is the space between the boxes (wider gaps look like a printed sudoku)
+    blitz_minutes is the countdown for each difficulty in blitz mode, in minutes
+    timer_granularity is how often the clock ticks on screen, animations turns egui's widget animations on or off,
+        and battery_saver turns off both animations and the clock's repaints (see RenderBudget)
+    fps_overlay shows the frame rate and frame time in the top right corner (F3 toggles it)
+    window is the window's geometry from the last time the app was closed, or None to open maximized (see WindowGeometry)
+    difficulty_defaults stores the per-difficulty overrides of the game options (e.g. Beginner allows 5 strikes)
+    #[serde(default)] lets an older settings file that is missing newer fields still load -- the missing fields get their default value
+*/
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub username: Option<String>,
+    pub dark_mode: Option<bool>,
+    pub digit_style: DigitStyle,
+    pub transform_puzzles: bool,
+    pub transform_repeats: bool,
+    pub show_unit_counters: bool,
+    pub sweep_forced_digits: bool,
+    pub auto_notes: bool,
+    pub ghost_preview: bool,
+    pub entry_debounce_ms: u64,
+    pub row_typing: bool,
+    pub enter_advances: bool,
+    pub stuck_nudge: bool,
+    pub stuck_minutes: u64,
+    pub confirm_quit: bool,
+    pub quick_start: bool,
+    pub highlight_errors: bool,
+    pub auto_erase: bool,
+    pub sandbox_apply: bool,
+    pub validation: Validation,
+    pub show_strikes: bool,
+    pub show_clean_indicator: bool,
+    pub show_tutorial: bool,
+    pub show_pb_pace: bool,
+    pub show_time_rank: bool,
+    pub record_loss_details: bool,
+    pub warm_up: bool,
+    pub check_for_updates: bool,
+    pub last_update_check: Option<u64>,
+    pub arcade_mode: bool,
+    pub arcade_recovery_secs: u64,
+    pub strike_limit: u8,
+    pub points_mode: bool,
+    pub starting_points: u32,
+    pub points_per_mistake: u32,
+    pub warn_strikes_left: u8,
+    pub strike_toast: bool,
+    pub strike_flash: bool,
+    pub grid_background: bool,
+    pub crosshair: bool,
+    pub digit_chart: bool,
+    pub show_candidates: bool,
+    pub mini_timer: bool,
+    pub box_contrast: f32,
+    pub pastel_boxes: bool,
+    pub cell_gap: f32,
+    pub box_gap: f32,
+    pub blitz_minutes: HashMap<String, u64>,
+    pub timer_granularity: TimerGranularity,
+    pub animations: bool,
+    pub battery_saver: bool,
+    pub fps_overlay: bool,
+    pub window: Option<WindowGeometry>,
+    pub difficulty_defaults: HashMap<String, OptionOverrides>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            username: None,
+            dark_mode: None,
+            digit_style: DigitStyle::Standard,
+            transform_puzzles: false,
+            transform_repeats: true,
+            show_unit_counters: false,
+            sweep_forced_digits: false,
+            auto_notes: false,
+            ghost_preview: false,
+            entry_debounce_ms: 120,
+            row_typing: false,
+            enter_advances: false,
+            stuck_nudge: true,
+            stuck_minutes: 3,
+            confirm_quit: true,
+            quick_start: false,
+            highlight_errors: true,
+            auto_erase: false,
+            sandbox_apply: false,
+            validation: Validation::Solution,
+            show_strikes: true,
+            show_clean_indicator: true,
+            show_tutorial: false,
+            show_pb_pace: true,
+            show_time_rank: true,
+            record_loss_details: true,
+            warm_up: false,
+            check_for_updates: false,
+            last_update_check: None,
+            arcade_mode: false,
+            arcade_recovery_secs: 60,
+            strike_limit: 3,
+            points_mode: false,
+            starting_points: 100,
+            points_per_mistake: 20,
+            warn_strikes_left: 1,
+            strike_toast: true,
+            strike_flash: true,
+            grid_background: false,
+            crosshair: false,
+            digit_chart: false,
+            show_candidates: false,
+            mini_timer: false,
+            box_contrast: 0.5,
+            pastel_boxes: false,
+            cell_gap: 5.0,
+            box_gap: 5.0,
+            blitz_minutes: default_blitz_minutes(),
+            timer_granularity: TimerGranularity::Second,
+            animations: true,
+            battery_saver: false,
+            fps_overlay: false,
+            window: None,
+            difficulty_defaults: default_difficulty_overrides(),
+        }
+    }
+}
+
+/*
+    The GameOptions struct stores the options that can change from one game to the next
+    they are resolved once when a game starts (see resolve_options), so changing a setting mid-game doesn't change the current game
+    points is the points rule when the game uses points instead of the strike limit, or None for a normal game
+        (it has a serde default so games saved before points existed still load)
+    validation is how entries are checked in this game -- games saved before it existed are checked against the solution
+*/
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct GameOptions {
+    pub strike_limit: u8,
+    pub highlight_errors: bool,
+    pub show_unit_counters: bool,
+    pub arcade: bool,
+    #[serde(default)]
+    pub points: Option<PointsRule>,
+    #[serde(default)]
+    pub validation: Validation,
+}
+
+// the PointsRule struct is how many points a points game starts with, and how many each wrong entry costs
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct PointsRule {
+    pub start: u32,
+    pub per_mistake: u32,
+}
+
+impl PointsRule {
+    // the points left after some number of strikes -- never below zero
+    pub fn remaining(&self, strikes: u32) -> u32 {
+        self.start.saturating_sub(strikes.saturating_mul(self.per_mistake))
+    }
+}
+
+// the Validation enum is what an entry is checked against, which decides both strikes and which entries are colored red
+    // Solution marks every entry that doesn't match the puzzle's solution, and Rules only marks an entry that repeats a digit in its
+    // row, column, or box (or breaks its parity mark) -- either way, the game is only won once the board matches the solution
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Validation {
+    #[default]
+    Solution,
+    Rules,
+}
+
+impl Validation {
+    // the name shown in the settings
+    pub fn name(self) -> &'static str {
+        match self {
+            Validation::Solution => "The solution",
+            Validation::Rules => "The rules only",
+        }
+    }
+}
+
+/*
+    The GameSetup struct is everything picked on the game options panel for the next game (see Sudoku::choose_difficulty)
+    overrides are one-off overrides of the game options -- they are cleared once a game starts with them
+    unranked starts games without recording them, even_odd starts them as the even/odd variant, and blitz starts them with a countdown --
+        these three stay picked from one game to the next
+*/
+#[derive(Clone, Default)]
+pub struct GameSetup {
+    pub overrides: OptionOverrides,
+    pub unranked: bool,
+    pub even_odd: bool,
+    pub blitz: bool,
+}
+
+// the OptionOverrides struct stores overrides for some of the game options -- None means "don't override this one"
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OptionOverrides {
+    pub strike_limit: Option<u8>,
+    pub highlight_errors: Option<bool>,
+    pub show_unit_counters: Option<bool>,
+    pub arcade: Option<bool>,
+}
+
+impl OptionOverrides {
+    // returns the options with every override that is set applied on top
+    pub fn apply(&self, options: GameOptions) -> GameOptions {
+        GameOptions {
+            strike_limit: self.strike_limit.unwrap_or(options.strike_limit),
+            highlight_errors: self.highlight_errors.unwrap_or(options.highlight_errors),
+            show_unit_counters: self.show_unit_counters.unwrap_or(options.show_unit_counters),
+            arcade: self.arcade.unwrap_or(options.arcade),
+            points: options.points,
+            validation: options.validation,
+        }
+    }
+}
+
+/*
+    The AssistPreset enum is a bundle of the assist settings, picked during setup so a new user doesn't have to go through every setting
+    Standard is the default settings, Relaxed turns every assist on and allows more strikes, and Purist turns the assists off
+    Relaxed and Purist apply to every difficulty, so they clear the per-difficulty overrides
+*/
+#[derive(Clone, Copy, PartialEq)]
+pub enum AssistPreset {
+    Relaxed,
+    Standard,
+    Purist,
+}
+
+impl AssistPreset {
+    pub const ALL: [AssistPreset; 3] = [AssistPreset::Relaxed, AssistPreset::Standard, AssistPreset::Purist];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AssistPreset::Relaxed => "Relaxed",
+            AssistPreset::Standard => "Standard",
+            AssistPreset::Purist => "Purist",
+        }
+    }
+
+    // the explanation shown when choosing a preset
+    pub fn description(self) -> &'static str {
+        match self {
+            AssistPreset::Relaxed => "Every game allows 5 strikes. Wrong entries turn red straight away, each row and column shows how many cells are left, \
+                and you get a nudge when you've been stuck for a while. A good choice if you're new to sudoku.",
+            AssistPreset::Standard => "Beginner games are forgiving and Advanced games are strict, with 3 strikes and wrong entries colored in between. \
+                You get a nudge when you've been stuck for a while.",
+            AssistPreset::Purist => "Every game allows 3 strikes and nothing gives anything away: wrong entries aren't colored, \
+                there are no counters and no nudges. You only find out about a mistake when it costs a strike.",
+        }
+    }
+
+    // writes the preset's assist settings into settings, leaving everything else alone
+    pub fn apply(self, settings: &mut Settings) {
+        let defaults = Settings::default();
+        let (strike_limit, assists) = match self {
+            AssistPreset::Relaxed => (5, true),
+            AssistPreset::Standard => (defaults.strike_limit, defaults.highlight_errors),
+            AssistPreset::Purist => (3, false),
+        };
+        settings.strike_limit = strike_limit;
+        settings.highlight_errors = assists;
+        settings.show_unit_counters = self == AssistPreset::Relaxed;
+        settings.stuck_nudge = assists;
+        settings.difficulty_defaults = match self {
+            AssistPreset::Standard => default_difficulty_overrides(),
+            _ => HashMap::new(),
+        };
+    }
+}
+
+// Beginner starts generous and Advanced starts strict, but the user can change both in settings
+fn default_difficulty_overrides() -> HashMap<String, OptionOverrides> {
+    let mut defaults = HashMap::new();
+    defaults.insert("Beginner".to_string(), OptionOverrides {
+        strike_limit: Some(5),
+        highlight_errors: Some(true),
+        show_unit_counters: Some(true),
+        ..Default::default()
+    });
+    defaults.insert("Advanced".to_string(), OptionOverrides {
+        strike_limit: Some(3),
+        highlight_errors: Some(false),
+        show_unit_counters: Some(false),
+        ..Default::default()
+    });
+    defaults
+}
+
+// the blitz countdowns start at 5 minutes for Beginner, with more time for the harder difficulties
+fn default_blitz_minutes() -> HashMap<String, u64> {
+    HashMap::from([
+        ("Beginner".to_string(), 5),
+        ("Intermediate".to_string(), 10),
+        ("Advanced".to_string(), 15),
+    ])
+}
+
+// works out the options for a game at the given difficulty
+    // the resolution order is: the per-game override, then the per-difficulty default, then the global setting
+pub fn resolve_options(settings: &Settings, difficulty: &str, game_overrides: &OptionOverrides) -> GameOptions {
+    let global = GameOptions {
+        strike_limit: settings.strike_limit,
+        highlight_errors: settings.highlight_errors,
+        show_unit_counters: settings.show_unit_counters,
+        arcade: settings.arcade_mode,
+        points: settings.points_mode.then_some(PointsRule {
+            start: settings.starting_points.max(1),
+            per_mistake: settings.points_per_mistake.max(1),
+        }),
+        validation: settings.validation,
+    };
+    let per_difficulty = match settings.difficulty_defaults.get(difficulty) {
+        Some(overrides) => overrides.apply(global),
+        None => global,
+    };
+    game_overrides.apply(per_difficulty)
+}
+
+impl Settings {
+    // the blitz countdown for a difficulty -- difficulties without one (like Test) get 10 minutes
+    pub fn blitz_budget(&self, difficulty: &str) -> Duration {
+        Duration::from_secs(self.blitz_minutes.get(difficulty).copied().unwrap_or(10) * 60)
+    }
+
+    // reads the settings file (or its backup, see persist.rs) -- if neither can be used, the default settings are used instead
+    pub fn load() -> Self {
+        persist::read_json(SETTINGS_PATH).unwrap_or_default()
+    }
+
+    // writes the settings file -- failing to save is not fatal, the settings just won't persist
+    pub fn save(&self) {
+        self.save_to(SETTINGS_PATH);
+    }
+
+    // writes the settings to path instead of SETTINGS_PATH
+    pub fn save_to(&self, path: &str) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            if let Err(err) = persist::write_atomic(path, json.as_bytes()) {
+                println!("Failed to save settings: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the default settings, with every global option changed from its default so each layer can be told apart
+    fn settings() -> Settings {
+        Settings {
+            strike_limit: 4,
+            highlight_errors: false,
+            show_unit_counters: false,
+            arcade_mode: true,
+            points_mode: true,
+            starting_points: 50,
+            points_per_mistake: 10,
+            validation: Validation::Rules,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_difficulty_without_defaults_uses_the_global_settings() {
+        let options = resolve_options(&settings(), "Intermediate", &OptionOverrides::default());
+        assert_eq!(options, GameOptions {
+            strike_limit: 4,
+            highlight_errors: false,
+            show_unit_counters: false,
+            arcade: true,
+            points: Some(PointsRule { start: 50, per_mistake: 10 }),
+            validation: Validation::Rules,
+        });
+    }
+
+    #[test]
+    fn the_difficulty_defaults_beat_the_global_settings() {
+        let options = resolve_options(&settings(), "Beginner", &OptionOverrides::default());
+        assert_eq!(options.strike_limit, 5);
+        assert!(options.highlight_errors);
+        assert!(options.show_unit_counters);
+        // Beginner doesn't override arcade mode, so the global setting is kept
+        assert!(options.arcade);
+    }
+
+    #[test]
+    fn the_game_overrides_beat_the_difficulty_defaults() {
+        let overrides = OptionOverrides { strike_limit: Some(1), show_unit_counters: Some(false), ..Default::default() };
+        let options = resolve_options(&settings(), "Beginner", &overrides);
+        assert_eq!(options.strike_limit, 1);
+        assert!(!options.show_unit_counters);
+        // not overridden for the game, so the difficulty's default is kept
+        assert!(options.highlight_errors);
+    }
+
+    #[test]
+    fn the_game_overrides_beat_the_global_settings() {
+        let overrides = OptionOverrides { arcade: Some(false), highlight_errors: Some(true), ..Default::default() };
+        let options = resolve_options(&settings(), "Intermediate", &overrides);
+        assert!(!options.arcade);
+        assert!(options.highlight_errors);
+        assert_eq!(options.strike_limit, 4);
+    }
+
+    #[test]
+    fn points_and_validation_only_come_from_the_global_settings() {
+        let mut settings = settings();
+        settings.starting_points = 0;
+        settings.points_per_mistake = 0;
+        let overrides = OptionOverrides { strike_limit: Some(2), ..Default::default() };
+        let options = resolve_options(&settings, "Advanced", &overrides);
+        // a points game can't start with nothing or make wrong entries free, so both are at least 1
+        assert_eq!(options.points, Some(PointsRule { start: 1, per_mistake: 1 }));
+        assert_eq!(options.validation, Validation::Rules);
+
+        settings.points_mode = false;
+        assert_eq!(resolve_options(&settings, "Advanced", &overrides).points, None);
+    }
+}
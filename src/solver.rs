@@ -0,0 +1,72 @@
+use crate::board::{self, Grid};
+
+// solves the grid and returns its solution, or None if it has no solution
+    // if the grid has more than one solution, the first one found is returned
+pub fn solve(grid: &Grid) -> Option<Grid> {
+    solutions(grid, 1).pop()
+}
+
+// finds up to limit solutions of the grid using backtracking
+    // a grid whose filled cells already break the rules has no solutions
+pub fn solutions(grid: &Grid, limit: usize) -> Vec<Grid> {
+    solutions_with_parity(grid, &board::NO_PARITY, limit)
+}
+
+// counts the solutions of the grid, but stops counting once limit is reached
+    // e.g. count_solutions_with_parity(grid, &board::NO_PARITY, 2) == 1 means a normal puzzle has exactly one solution
+    // every digit also has to meet its cell's parity in the mask, for the even/odd variant
+pub fn count_solutions_with_parity(grid: &Grid, mask: &Grid, limit: usize) -> usize {
+    solutions_with_parity(grid, mask, limit).len()
+}
+
+// the same as solutions, but every digit also has to meet its cell's parity in the mask (see board::NO_PARITY)
+pub fn solutions_with_parity(grid: &Grid, mask: &Grid, limit: usize) -> Vec<Grid> {
+    let mut found = Vec::new();
+    for row in 0..9 {
+        for col in 0..9 {
+            let digit = grid[row][col];
+            if digit != '.' && !(board::is_legal(grid, row, col, digit) && board::parity_allows(mask, row, col, digit)) {
+                return found;
+            }
+        }
+    }
+    let mut grid = *grid;
+    search(&mut grid, mask, limit, &mut found);
+    found
+}
+
+// fills the most constrained empty cell with each of its candidates in turn, and recurses until the grid is full
+    // picking the cell with the fewest candidates first keeps the search small, even for hard puzzles
+fn search(grid: &mut Grid, mask: &Grid, limit: usize, found: &mut Vec<Grid>) {
+    if found.len() >= limit {
+        return;
+    }
+
+    let mut best: Option<(usize, usize, Vec<char>)> = None;
+    for row in 0..9 {
+        for col in 0..9 {
+            if grid[row][col] == '.' {
+                let mut candidates = board::candidates(grid, row, col);
+                candidates.retain(|&digit| board::parity_allows(mask, row, col, digit));
+                if best.as_ref().is_none_or(|(_, _, most)| candidates.len() < most.len()) {
+                    best = Some((row, col, candidates));
+                }
+            }
+        }
+    }
+
+    match best {
+        // no empty cells are left, so the grid is solved
+        None => found.push(*grid),
+        Some((row, col, candidates)) => {
+            for digit in candidates {
+                grid[row][col] = digit;
+                search(grid, mask, limit, found);
+                if found.len() >= limit {
+                    break;
+                }
+            }
+            grid[row][col] = '.';
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use crate::board::{self, Grid};
+use crate::solver;
+
+// the environment variable that names the OCR command, e.g. SUDOKU_OCR_COMMAND=./ocr-sudoku
+    // the command is run with the image path as its only argument, and must print the grid (see parse_grid)
+pub const OCR_COMMAND_VAR: &str = "SUDOKU_OCR_COMMAND";
+
+// the fewest givens a sudoku can have and still have a unique solution
+const MIN_GIVENS: usize = 17;
+
+// the PuzzleSource trait is anything that can turn a file into a puzzle grid, so the app doesn't care how the grid was read
+    // the grid it returns hasn't been checked yet -- validate does that before it is played
+pub trait PuzzleSource {
+    // reads a puzzle from the file at path
+    fn read(&self, path: &Path) -> Result<Grid, String>;
+}
+
+// reads a photo of a puzzle by running an external OCR program (see OCR_COMMAND_VAR), so real OCR never has to be built into the app
+pub struct CommandOcr {
+    command: String,
+}
+
+impl PuzzleSource for CommandOcr {
+    fn read(&self, path: &Path) -> Result<Grid, String> {
+        let output = Command::new(&self.command)
+            .arg(path)
+            .output()
+            .map_err(|err| format!("Couldn't run {}: {}", self.command, err))?;
+        if !output.status.success() {
+            return Err(format!("{} couldn't read the photo: {}", self.command, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        parse_grid(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+// the source used when no OCR program is set up -- it always fails with a message saying how to set one up
+pub struct NoOcr;
+
+impl PuzzleSource for NoOcr {
+    fn read(&self, _path: &Path) -> Result<Grid, String> {
+        Err(format!("Importing from a photo needs an OCR program -- set {} to a command that prints the grid", OCR_COMMAND_VAR))
+    }
+}
+
+// the photo source to use: the OCR program from the environment if there is one, otherwise NoOcr
+pub fn photo_source() -> Box<dyn PuzzleSource> {
+    match env::var(OCR_COMMAND_VAR) {
+        Ok(command) if !command.trim().is_empty() => Box::new(CommandOcr { command: command.trim().to_string() }),
+        _ => Box::new(NoOcr),
+    }
+}
+
+// reads a grid from text: 81 cells row by row, where '1'-'9' are givens and '.' or '0' are empty cells
+    // whitespace and '|', '-', '+' (as used in drawn grids) are ignored, so both one line and nine lines of output work
+pub fn parse_grid(text: &str) -> Result<Grid, String> {
+    let cells: Vec<char> = text.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '|' | '-' | '+'))
+        .map(|c| if c == '0' { '.' } else { c })
+        .collect();
+    if let Some(bad) = cells.iter().find(|&&c| c != '.' && !board::DIGITS.contains(&c)) {
+        return Err(format!("The grid contains '{}', which isn't a digit or an empty cell", bad));
+    }
+    if cells.len() != 81 {
+        return Err(format!("The grid has {} cells instead of 81", cells.len()));
+    }
+    Ok(board::string_to_grid(&cells.into_iter().collect::<String>()))
+}
+
+// checks that a grid read from a source can be played, and returns its solution
+    // the givens must not break any rule, and the solver must find exactly one solution
+pub fn validate(grid: &Grid) -> Result<Grid, String> {
+    if let Some(conflict) = board::conflicts(grid).first() {
+        return Err(format!("The puzzle breaks the rules: {}", conflict.explanation().to_lowercase()));
+    }
+    let givens = grid.iter().flatten().filter(|&&c| c != '.').count();
+    if givens < MIN_GIVENS {
+        return Err(format!("The puzzle only has {} givens, but a sudoku needs at least {}", givens, MIN_GIVENS));
+    }
+    match solver::solutions(grid, 2).as_slice() {
+        [solution] => Ok(*solution),
+        [] => Err("The puzzle has no solution -- a digit may have been misread".to_string()),
+        _ => Err("The puzzle has more than one solution -- a digit may have been missed".to_string()),
+    }
+}
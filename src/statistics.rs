@@ -0,0 +1,186 @@
+use eframe::egui::{self, Align2, CentralPanel, Color32, Context, FontId, Grid, RichText, ScrollArea, Sense, Ui, Vec2};
+use crate::{chart, clock, layout};
+use crate::thumbnail::{self, ThumbnailColors};
+use crate::stats::{self, DigitStats, Stats};
+use crate::Sudoku;
+
+impl Sudoku {
+    // displays the statistics screen, which is opened from the difficulty screen
+        // it shows the results for each difficulty, and a heatmap of where the user's mistakes happen across all of their games
+    pub(crate) fn statistics_screen(&mut self, ctx: &Context) {
+        CentralPanel::default().show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.heading(RichText::new("Statistics").size(30.0));
+                    if ui.button("Back").clicked() {
+                        self.show_statistics = false;
+                    }
+                    ui.add_space(20.0);
+
+                    week_recap(ui, &self.stats);
+                    ui.add_space(30.0);
+
+                    // one row per difficulty
+                    Grid::new("stats_table").striped(true).show(ui, |ui| {
+                        for heading in ["", "Played", "Wins", "Losses", "Flawless", "Best time", "Best score", "Longest loss", "Best fill on a loss"] {
+                            ui.label(RichText::new(heading).strong());
+                        }
+                        ui.end_row();
+                        for difficulty in ["Beginner", "Intermediate", "Advanced", "Samurai"] {
+                            let stats = self.stats.difficulties.get(difficulty).cloned().unwrap_or_default();
+                            ui.label(difficulty);
+                            ui.label(stats.games_played().to_string());
+                            ui.label(stats.wins.to_string());
+                            ui.label(stats.losses.to_string());
+                            ui.label(stats.flawless_wins.to_string());
+                            ui.label(stats.best_time.map_or("-".to_string(), crate::format_time));
+                            ui.label(stats.best_score.map_or("-".to_string(), |score| score.to_string()));
+                            match self.stats.best_loss(difficulty) {
+                                Some((secs, fill)) => {
+                                    ui.label(crate::format_time(secs));
+                                    ui.label(format!("{}%", fill));
+                                }
+                                None => {
+                                    ui.label("-");
+                                    ui.label("-");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                    // the mistake heatmap, either by position on the board or by the digit that should have been entered
+                    ui.add_space(30.0);
+                    ui.heading("Where your mistakes happen");
+                    layout::centered_row(ui, "heatmap_mode", |ui| {
+                        ui.selectable_value(&mut self.heatmap_by_digit, false, "By cell");
+                        ui.selectable_value(&mut self.heatmap_by_digit, true, "By digit");
+                    });
+                    ui.add_space(10.0);
+                    if self.stats.mistakes.is_empty() {
+                        ui.label("No mistakes recorded yet");
+                    }
+                    else if self.heatmap_by_digit {
+                        let counts = self.stats.mistakes_by_digit();
+                        thumbnail::draw_digit_heatmap(ui, &counts, 40.0, &ThumbnailColors::standard());
+                        thumbnail::draw_heat_legend(ui, counts.iter().copied().max().unwrap_or(0));
+                    }
+                    else {
+                        let counts = self.stats.mistakes_by_cell();
+                        thumbnail::draw_heatmap(ui, &counts, 36.0, &ThumbnailColors::standard());
+                        thumbnail::draw_heat_legend(ui, counts.iter().flatten().copied().max().unwrap_or(0));
+                    }
+
+                    // how accurately and how quickly each digit gets entered
+                    ui.add_space(30.0);
+                    ui.heading("Digit accuracy");
+                    digit_table(ui, &self.stats.digits);
+                });
+            });
+        });
+    }
+}
+
+// the weekday names of the 7 days ending on last_day, oldest first, to go under the bars of Stats::week
+    // days before the unix epoch (only when the clock is set before 1970, see clock::today) have no name
+fn week_labels(last_day: u64) -> Vec<String> {
+    (0..7)
+        .map(|i| (last_day + i).checked_sub(6).map_or(String::new(), |day| clock::weekday_name(day).to_string()))
+        .collect()
+}
+
+// shows the "This week" card: a bar per day of play time, plus the totals compared to the week before
+fn week_recap(ui: &mut Ui, stats: &Stats) {
+    let today = clock::today();
+    let this_week = stats.week(today);
+    let last_week = stats.week(today.saturating_sub(7));
+    let total = |week: &[stats::DayRecord; 7]| (week.iter().map(|day| day.play_secs).sum::<f64>(), week.iter().map(|day| day.games).sum::<u32>());
+    let (this_secs, this_games) = total(&this_week);
+    let (last_secs, last_games) = total(&last_week);
+
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.heading("This week");
+
+        // the bars are scaled so the longest day fills the chart
+        let values: Vec<f32> = this_week.iter().map(|day| day.play_secs as f32).collect();
+        chart::draw_bar_chart(ui, &values, &week_labels(today), 30.0, 80.0);
+
+        ui.label(format!("{} played, {} games finished", format_play_time(this_secs), this_games));
+        let change = this_secs - last_secs;
+        let sign = if change < 0.0 { "-" } else { "+" };
+        ui.label(format!("{}{} and {:+} games compared to last week", sign, format_play_time(change.abs()), this_games as i64 - last_games as i64));
+    });
+}
+
+// formats play time as hours and minutes, e.g. 3900 seconds becomes "1h 05m"
+fn format_play_time(secs: f64) -> String {
+    let minutes = (secs / 60.0) as u64;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+    else {
+        format!("{}m", minutes)
+    }
+}
+
+// shows a row per digit with its accuracy and average time to commit, then the confusion matrix of wrong entries
+fn digit_table(ui: &mut Ui, digits: &DigitStats) {
+    Grid::new("digit_table").striped(true).show(ui, |ui| {
+        for heading in ["Digit", "Entered", "Accuracy", "Avg. time"] {
+            ui.label(RichText::new(heading).strong());
+        }
+        ui.end_row();
+        for i in 0..9 {
+            ui.label((i + 1).to_string());
+            ui.label((digits.correct[i] + digits.wrong[i]).to_string());
+            ui.label(digits.accuracy(i).map_or("-".to_string(), |accuracy| format!("{:.0}%", accuracy)));
+            ui.label(digits.average_think_secs(i).map_or("-".to_string(), |secs| format!("{:.1}s", secs)));
+            ui.end_row();
+        }
+    });
+
+    if let Some((entered, expected, count)) = digits.most_confused() {
+        ui.add_space(10.0);
+        ui.label(format!("You most often enter {} where {} belongs ({} times)", entered + 1, expected + 1, count));
+    }
+
+    // the confusion matrix -- each row is the digit entered, and each column is the digit that should have been there
+    ui.add_space(10.0);
+    ui.label("Entered (rows) vs. correct digit (columns)");
+    let max = digits.confusion.iter().flatten().copied().max().unwrap_or(0);
+    Grid::new("confusion_matrix").spacing([2.0, 2.0]).show(ui, |ui| {
+        ui.label("");
+        for expected in 1..=9 {
+            ui.label(RichText::new(expected.to_string()).strong());
+        }
+        ui.end_row();
+        for (entered, row) in digits.confusion.iter().enumerate() {
+            ui.label(RichText::new((entered + 1).to_string()).strong());
+            for &count in row {
+                let (rect, _) = ui.allocate_exact_size(Vec2::splat(24.0), Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, thumbnail::heat_color(count, max));
+                if count > 0 {
+                    ui.painter().text(rect.center(), Align2::CENTER_CENTER, count.to_string(), FontId::proportional(12.0), Color32::BLACK);
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_end_on_the_last_day() {
+        // day 6 was a Wednesday, so the week ending on it starts with the epoch's Thursday
+        assert_eq!(week_labels(6), ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]);
+    }
+
+    #[test]
+    fn days_before_the_epoch_have_no_label() {
+        assert_eq!(week_labels(0), ["", "", "", "", "", "", "Thu"]);
+        assert_eq!(week_labels(2), ["", "", "", "", "Thu", "Fri", "Sat"]);
+    }
+}
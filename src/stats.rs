@@ -0,0 +1,614 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::actions::{self, Action};
+use crate::clock::{self, SECS_PER_DAY};
+use crate::persist;
+
+// the stats are saved next to the settings file so they persist between launches
+pub const STATS_PATH: &str = "./stats.json";
+
+// how many win times are kept per difficulty -- the oldest are dropped first, so the ranking follows the user's recent form
+const MAX_WIN_TIMES: usize = 500;
+
+// the longest note the user can attach to a puzzle, in characters
+pub const MAX_PUZZLE_NOTE: usize = 200;
+
+// how many earlier wins a difficulty needs before a win time is ranked against them
+pub const MIN_RANKED_WINS: usize = 5;
+
+// the number of note hints a game gets (see Stats::hint_budget) -- DEFAULT_HINT_BUDGET is used until a difficulty has
+    // MIN_BUDGET_GAMES finished games, and the budget never goes outside MIN_HINT_BUDGET to MAX_HINT_BUDGET
+pub const DEFAULT_HINT_BUDGET: u8 = 3;
+pub const MIN_HINT_BUDGET: u8 = 1;
+pub const MAX_HINT_BUDGET: u8 = 5;
+const MIN_BUDGET_GAMES: u32 = 3;
+
+/*
+    The DifficultyStats struct stores the results for a single difficulty
+    wins and losses only count fresh attempts at a puzzle
+    retry_wins and retry_losses count attempts started with "Retry this puzzle", so retries can't pad the record
+    best_time is the fastest win in seconds -- retries never set it
+    flawless_wins counts the wins with no strikes at all
+    best_score is the highest score of a win (see score::compute) -- like best_time, retries never set it, and neither do
+        difficulties without a score
+    win_times stores the times of the most recent wins in seconds (up to MAX_WIN_TIMES), so a new time can be ranked against them
+*/
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DifficultyStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub retry_wins: u32,
+    pub retry_losses: u32,
+    pub best_time: Option<u64>,
+    pub flawless_wins: u32,
+    pub best_score: Option<i64>,
+    pub win_times: Vec<u64>,
+}
+
+// the LostPuzzle struct stores the last puzzle the user lost, so it can still be retried after the app is reopened
+    // id is the puzzle's id -- stats files from before puzzles had ids don't have one
+    // parity is the parity mask if the puzzle was played as the even/odd variant
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LostPuzzle {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub parity: Option<String>,
+    pub difficulty: String,
+    pub puzzle: String,
+    pub solution: String,
+}
+
+/*
+    The MistakeRecord struct stores a single wrong entry
+    row and col are the cell's position, entered is the digit the user typed, and expected is the digit from the solution
+*/
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MistakeRecord {
+    pub row: usize,
+    pub col: usize,
+    pub entered: char,
+    pub expected: char,
+}
+
+/*
+    The LossRecord struct stores the details of a single lost game, so the user can see they are improving even when they lose
+    survival_secs is how long the game lasted, and fill_percent is how much of the board was correct when it ended
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LossRecord {
+    pub difficulty: String,
+    pub survival_secs: u64,
+    pub fill_percent: u8,
+}
+
+/*
+    The GameRecord struct stores the result of a single finished ranked game, for exporting the user's history (see data::export_csv)
+    day is the day the game ended on (see DayRecord), secs is how long it lasted, and won is false if it was lost
+    puzzle_id is the id of the puzzle from its file, and variant is set if the board was shown rotated, reflected, or relabeled --
+        a variant is still recorded under the original puzzle's id (records from before these were added have neither)
+    hints is how many note hints were shown in the game (records from before it was added have 0)
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameRecord {
+    pub difficulty: String,
+    pub day: u64,
+    pub secs: u64,
+    pub strikes: u32,
+    #[serde(default)]
+    pub hints: u32,
+    pub won: bool,
+    pub retry: bool,
+    #[serde(default)]
+    pub puzzle_id: String,
+    #[serde(default)]
+    pub variant: bool,
+}
+
+/*
+    The MarathonStats struct stores the results of the marathons of one kind (see Marathon::record_key)
+    finished counts the marathons where every puzzle was solved, and failed the ones that were lost or given up
+    best_secs is the fastest total time of a finished marathon, and fewest_strikes the fewest strikes made over one
+*/
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct MarathonStats {
+    pub finished: u32,
+    pub failed: u32,
+    pub best_secs: Option<u64>,
+    pub fewest_strikes: Option<u32>,
+}
+
+/*
+    The DayRecord struct stores what the user did on a single day
+    days are local day numbers (see clock.rs), so a day runs from the user's own midnight to the next -- when the timezone
+        changes, the time already recorded stays on the day it was recorded on, and only time played after that goes by the new midnight
+    play_secs is how long a ranked game was open and focused, and games is how many ranked games were finished
+*/
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct DayRecord {
+    pub play_secs: f64,
+    pub games: u32,
+}
+
+/*
+    The DigitStats struct stores how accurately the user enters each digit, aggregated over all of their games
+    every array is indexed by digit, so index 0 is the digit 1
+    correct and wrong count the entries of each digit, and think_ms adds up how long the user took to commit each digit after selecting a cell
+    confusion[entered][expected] counts how often the user entered one digit where the solution had another
+*/
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DigitStats {
+    pub correct: [u32; 9],
+    pub wrong: [u32; 9],
+    pub think_ms: [u64; 9],
+    pub confusion: [[u32; 9]; 9],
+}
+
+impl DigitStats {
+    // aggregates a game's action log into digit stats
+    pub fn from_actions(actions: &[Action]) -> Self {
+        let mut stats = Self::default();
+        for action in actions {
+            let (Some(entered), Some(expected)) = (actions::digit_index(action.digit), actions::digit_index(action.expected)) else {
+                continue;
+            };
+            if action.correct() {
+                stats.correct[entered] += 1;
+            }
+            else {
+                stats.wrong[entered] += 1;
+                stats.confusion[entered][expected] += 1;
+            }
+            stats.think_ms[entered] += action.think_ms;
+        }
+        stats
+    }
+
+    // adds another set of digit stats into this one
+    pub fn add(&mut self, other: &DigitStats) {
+        for i in 0..9 {
+            self.correct[i] += other.correct[i];
+            self.wrong[i] += other.wrong[i];
+            self.think_ms[i] += other.think_ms[i];
+            for j in 0..9 {
+                self.confusion[i][j] += other.confusion[i][j];
+            }
+        }
+    }
+
+    // the percentage of entries of a digit that were correct, or None if the digit was never entered
+    pub fn accuracy(&self, index: usize) -> Option<f32> {
+        let total = self.correct[index] + self.wrong[index];
+        (total > 0).then(|| self.correct[index] as f32 / total as f32 * 100.0)
+    }
+
+    // the average number of seconds between selecting a cell and entering a digit, or None if the digit was never entered
+    pub fn average_think_secs(&self, index: usize) -> Option<f32> {
+        let total = self.correct[index] + self.wrong[index];
+        (total > 0).then(|| self.think_ms[index] as f32 / total as f32 / 1000.0)
+    }
+
+    // the digit the user most often enters by mistake and the digit that should have gone there, as indexes, with the count
+    pub fn most_confused(&self) -> Option<(usize, usize, u32)> {
+        (0..81)
+            .map(|i| (i / 9, i % 9, self.confusion[i / 9][i % 9]))
+            .filter(|&(_, _, count)| count > 0)
+            .max_by_key(|&(_, _, count)| count)
+    }
+}
+
+/*
+    The Stats struct stores everything that is recorded about the user's games
+    difficulties maps a difficulty name (e.g. "Beginner") to the results for that difficulty
+    last_lost is the most recently lost puzzle, or None if it has since been won
+    mistakes stores every wrong entry from every finished game, so mistakes can be looked at across games
+    digits stores how accurately each digit is entered, across all finished games
+    losses stores the survival time and fill of every lost game (only recorded when the record_loss_details setting is on)
+    days maps a day (see DayRecord) to the play time and games finished on that day
+    games stores every finished ranked game, oldest first -- stats files from before it was added start with an empty history
+    played_puzzles stores the id of every puzzle finished in a ranked game, so new games can pick puzzles that haven't been played yet
+    puzzle_notes maps a puzzle id to the note the user attached to that puzzle -- ids are stable (from the puzzle file,
+        or a hash of the puzzle), so a note comes back whenever the same puzzle does
+    marathons maps a kind of marathon (see Marathon::record_key) to its results -- the games in a marathon are also recorded one by one
+*/
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Stats {
+    pub difficulties: HashMap<String, DifficultyStats>,
+    pub last_lost: Option<LostPuzzle>,
+    pub mistakes: Vec<MistakeRecord>,
+    pub digits: DigitStats,
+    pub losses: Vec<LossRecord>,
+    pub days: BTreeMap<u64, DayRecord>,
+    pub games: Vec<GameRecord>,
+    pub puzzle_notes: HashMap<String, String>,
+    pub played_puzzles: HashSet<String>,
+    pub marathons: HashMap<String, MarathonStats>,
+}
+
+impl Stats {
+    // reads the stats file (or its backup, see persist.rs) -- if neither can be used, empty stats are used instead
+    pub fn load() -> Self {
+        persist::read_json(STATS_PATH).unwrap_or_default()
+    }
+
+    // writes the stats file -- failing to save is not fatal, the results just won't persist
+    pub fn save(&self) {
+        self.save_to(STATS_PATH);
+    }
+
+    // writes the stats to path instead of STATS_PATH
+    pub fn save_to(&self, path: &str) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            if let Err(err) = persist::write_atomic(path, json.as_bytes()) {
+                println!("Failed to save stats: {}", err);
+            }
+        }
+    }
+
+    // records a win at the given difficulty, and updates the best time and flawless count unless the game was a retry
+    pub fn record_win(&mut self, difficulty: &str, seconds: u64, score: Option<i64>, flawless: bool, retry: bool) {
+        let entry = self.difficulties.entry(difficulty.to_string()).or_default();
+        if retry {
+            entry.retry_wins += 1;
+        }
+        else {
+            entry.wins += 1;
+            entry.best_time = Some(entry.best_time.map_or(seconds, |best| best.min(seconds)));
+            if let Some(score) = score {
+                entry.best_score = Some(entry.best_score.map_or(score, |best| best.max(score)));
+            }
+            entry.win_times.push(seconds);
+            if entry.win_times.len() > MAX_WIN_TIMES {
+                entry.win_times.remove(0);
+            }
+            if flawless {
+                entry.flawless_wins += 1;
+            }
+        }
+    }
+
+    // how many note hints a game at the given difficulty gets, from how often the user has won it
+        // a user who never wins gets MAX_HINT_BUDGET and one who always wins gets MIN_HINT_BUDGET, with a 50% win rate landing on
+        // DEFAULT_HINT_BUDGET -- retries don't count, so retrying a puzzle can't be used to change the budget
+    pub fn hint_budget(&self, difficulty: &str) -> u8 {
+        let Some(entry) = self.difficulties.get(difficulty) else { return DEFAULT_HINT_BUDGET };
+        let games = entry.wins + entry.losses;
+        if games < MIN_BUDGET_GAMES {
+            return DEFAULT_HINT_BUDGET;
+        }
+        let win_rate = entry.wins as f32 / games as f32;
+        let budget = MAX_HINT_BUDGET as f32 - win_rate * (MAX_HINT_BUDGET - MIN_HINT_BUDGET) as f32;
+        (budget.round() as u8).clamp(MIN_HINT_BUDGET, MAX_HINT_BUDGET)
+    }
+
+    // adds another set of stats into this one -- counts are added together, and the faster best time is kept
+        // this one's last lost puzzle is kept unless it doesn't have one
+    pub fn merge(&mut self, other: &Stats) {
+        for (difficulty, theirs) in &other.difficulties {
+            let ours = self.difficulties.entry(difficulty.clone()).or_default();
+            ours.wins += theirs.wins;
+            ours.losses += theirs.losses;
+            ours.retry_wins += theirs.retry_wins;
+            ours.retry_losses += theirs.retry_losses;
+            ours.flawless_wins += theirs.flawless_wins;
+            ours.best_time = match (ours.best_time, theirs.best_time) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            ours.best_score = ours.best_score.max(theirs.best_score);
+            ours.win_times.extend(theirs.win_times.iter().copied());
+            let excess = ours.win_times.len().saturating_sub(MAX_WIN_TIMES);
+            ours.win_times.drain(..excess);
+        }
+        if self.last_lost.is_none() {
+            self.last_lost = other.last_lost.clone();
+        }
+        self.mistakes.extend(other.mistakes.iter().copied());
+        self.digits.add(&other.digits);
+        self.losses.extend(other.losses.iter().cloned());
+        for (day, theirs) in &other.days {
+            let ours = self.days.entry(*day).or_default();
+            ours.play_secs += theirs.play_secs;
+            ours.games += theirs.games;
+        }
+        self.games.extend(other.games.iter().cloned());
+        self.games.sort_by_key(|game| game.day);
+        self.played_puzzles.extend(other.played_puzzles.iter().cloned());
+        for (key, theirs) in &other.marathons {
+            let ours = self.marathons.entry(key.clone()).or_default();
+            ours.finished += theirs.finished;
+            ours.failed += theirs.failed;
+            ours.best_secs = match (ours.best_secs, theirs.best_secs) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            ours.fewest_strikes = match (ours.fewest_strikes, theirs.fewest_strikes) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        // when both have a note for the same puzzle, this one's note is kept
+        for (id, note) in &other.puzzle_notes {
+            self.puzzle_notes.entry(id.clone()).or_insert_with(|| note.clone());
+        }
+    }
+
+    // counts the mistakes made in each cell of the board, across all games
+    pub fn mistakes_by_cell(&self) -> [[u32; 9]; 9] {
+        let mut counts = [[0; 9]; 9];
+        for mistake in &self.mistakes {
+            counts[mistake.row][mistake.col] += 1;
+        }
+        counts
+    }
+
+    // counts the mistakes made for each digit, by the digit that should have gone in the cell -- index 0 is the digit 1
+    pub fn mistakes_by_digit(&self) -> [u32; 9] {
+        let mut counts = [0; 9];
+        for mistake in &self.mistakes {
+            if let Some(index) = actions::digit_index(mistake.expected) {
+                counts[index] += 1;
+            }
+        }
+        counts
+    }
+
+    // the longest survival time and the best fill percentage of the recorded losses at a difficulty, or None if there aren't any
+    pub fn best_loss(&self, difficulty: &str) -> Option<(u64, u8)> {
+        self.losses
+            .iter()
+            .filter(|loss| loss.difficulty == difficulty)
+            .fold(None, |best, loss| match best {
+                None => Some((loss.survival_secs, loss.fill_percent)),
+                Some((secs, fill)) => Some((secs.max(loss.survival_secs), fill.max(loss.fill_percent))),
+            })
+    }
+
+    // adds secs of play time that ended at end_secs (in seconds since the unix epoch), on local days (see clock.rs) for a timezone
+        // offset_secs ahead of UTC -- time that crosses the local midnight is split between the two days
+    pub fn add_play_time(&mut self, end_secs: f64, secs: f64, offset_secs: i64) {
+        let mut start = end_secs - secs;
+        while start < end_secs {
+            let day = clock::day_number(start, offset_secs);
+            let midnight = (day as f64 + 1.0) * SECS_PER_DAY - offset_secs as f64;
+            // a time past the last day number has no midnight after it, so the rest of the time goes on that day
+            let day_end = if midnight > start { midnight.min(end_secs) } else { end_secs };
+            self.days.entry(day).or_default().play_secs += day_end - start;
+            start = day_end;
+        }
+    }
+
+    // the records for the 7 days ending on last_day, oldest first -- days with nothing recorded are empty
+    pub fn week(&self, last_day: u64) -> [DayRecord; 7] {
+        std::array::from_fn(|i| {
+            (last_day + i as u64).checked_sub(6)
+                .and_then(|day| self.days.get(&day).copied())
+                .unwrap_or_default()
+        })
+    }
+
+    // records the end of a ranked marathon -- only a finished one can set the best time and fewest strikes
+    pub fn record_marathon(&mut self, key: &str, finished: bool, secs: u64, strikes: u32) {
+        let entry = self.marathons.entry(key.to_string()).or_default();
+        if finished {
+            entry.finished += 1;
+            entry.best_secs = Some(entry.best_secs.map_or(secs, |best| best.min(secs)));
+            entry.fewest_strikes = Some(entry.fewest_strikes.map_or(strikes, |fewest| fewest.min(strikes)));
+        }
+        else {
+            entry.failed += 1;
+        }
+    }
+
+    // records a loss at the given difficulty
+    pub fn record_loss(&mut self, difficulty: &str, retry: bool) {
+        let entry = self.difficulties.entry(difficulty.to_string()).or_default();
+        if retry {
+            entry.retry_losses += 1;
+        }
+        else {
+            entry.losses += 1;
+        }
+    }
+}
+
+impl DifficultyStats {
+    // the number of games played at this difficulty, including retries
+    pub fn games_played(&self) -> u32 {
+        self.wins + self.losses + self.retry_wins + self.retry_losses
+    }
+
+    // the percentage of the recorded win times that seconds is faster than, or None if there are fewer than MIN_RANKED_WINS of them
+    pub fn faster_than(&self, seconds: u64) -> Option<u8> {
+        if self.win_times.len() < MIN_RANKED_WINS {
+            return None;
+        }
+        let slower = self.win_times.iter().filter(|&&time| time > seconds).count();
+        Some((slower * 100 / self.win_times.len()) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // serializes value, reads it back, and serializes it again -- the two have to match for nothing to be lost on the way
+    fn round_trip<T: Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_value(value).unwrap();
+        let back: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+        back
+    }
+
+    fn record(variant: bool) -> GameRecord {
+        GameRecord {
+            difficulty: "Advanced".to_string(),
+            day: 19791,
+            secs: 640,
+            strikes: 2,
+            hints: 1,
+            won: true,
+            retry: false,
+            puzzle_id: "a-417".to_string(),
+            variant,
+        }
+    }
+
+    #[test]
+    fn variant_record_keeps_its_puzzle_id() {
+        let back = round_trip(&record(true));
+        assert_eq!(back.puzzle_id, "a-417");
+        assert!(back.variant);
+        assert!(!round_trip(&record(false)).variant);
+    }
+
+    #[test]
+    fn record_from_before_puzzle_ids_loads() {
+        let json = r#"{"difficulty":"Beginner","day":19000,"secs":300,"strikes":0,"won":true,"retry":false}"#;
+        let old: GameRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(old.puzzle_id, "");
+        assert!(!old.variant);
+        assert_eq!(old.hints, 0);
+    }
+
+    #[test]
+    fn played_puzzles_survive_saving() {
+        let mut stats = Stats::default();
+        stats.played_puzzles.insert("a-417".to_string());
+        stats.games.push(record(true));
+        let back = round_trip(&stats);
+        assert!(back.played_puzzles.contains("a-417"));
+        assert_eq!(back.games.len(), 1);
+    }
+
+    // a digit entered where the solution has expected, after think_ms of having the cell selected
+    fn entry(digit: char, expected: char, think_ms: u64) -> Action {
+        Action { row: 0, col: 0, digit, expected, at_ms: 0, think_ms }
+    }
+
+    #[test]
+    fn actions_are_counted_by_the_digit_entered() {
+        let digits = DigitStats::from_actions(&[entry('3', '3', 1000), entry('3', '8', 3000), entry('8', '8', 500)]);
+        assert_eq!(digits.correct[2], 1);
+        assert_eq!(digits.wrong[2], 1);
+        assert_eq!(digits.correct[7], 1);
+        assert_eq!(digits.wrong[7], 0);
+        // the time is counted for wrong entries too
+        assert_eq!(digits.think_ms[2], 4000);
+        assert_eq!(digits.accuracy(2), Some(50.0));
+        assert_eq!(digits.average_think_secs(2), Some(2.0));
+    }
+
+    #[test]
+    fn wrong_entries_are_counted_against_the_digit_expected() {
+        let digits = DigitStats::from_actions(&[entry('3', '8', 0), entry('3', '8', 0), entry('6', '9', 0)]);
+        assert_eq!(digits.confusion[2][7], 2);
+        assert_eq!(digits.confusion[5][8], 1);
+        assert_eq!(digits.confusion[7][2], 0);
+        assert_eq!(digits.most_confused(), Some((2, 7, 2)));
+    }
+
+    #[test]
+    fn actions_without_a_digit_are_skipped() {
+        // only digits 1-9 are placed, so anything else (from a damaged stats file) is left out rather than counted
+        let digits = DigitStats::from_actions(&[entry('.', '4', 2000)]);
+        assert!(digits.correct.iter().chain(&digits.wrong).all(|&count| count == 0));
+        assert_eq!(digits.think_ms[3], 0);
+        assert_eq!(digits.most_confused(), None);
+    }
+
+    #[test]
+    fn unentered_digits_have_no_accuracy() {
+        let digits = DigitStats::default();
+        assert_eq!(digits.accuracy(0), None);
+        assert_eq!(digits.average_think_secs(0), None);
+    }
+
+    #[test]
+    fn games_add_up() {
+        let mut total = DigitStats::from_actions(&[entry('1', '1', 100), entry('2', '5', 200)]);
+        total.add(&DigitStats::from_actions(&[entry('1', '1', 300), entry('1', '7', 400), entry('2', '5', 0)]));
+        assert_eq!(total.correct[0], 2);
+        assert_eq!(total.wrong[0], 1);
+        assert_eq!(total.think_ms[0], 800);
+        assert_eq!(total.confusion[1][4], 2);
+        assert_eq!(total.confusion[0][6], 1);
+        // adding the stats of every game is the same as counting all of their actions at once
+        let all = DigitStats::from_actions(&[entry('1', '1', 100), entry('2', '5', 200), entry('1', '1', 300), entry('1', '7', 400), entry('2', '5', 0)]);
+        assert_eq!(serde_json::to_value(&total).unwrap(), serde_json::to_value(&all).unwrap());
+    }
+
+    // the play time recorded on each day, for the days that have any
+    fn play_days(stats: &Stats) -> Vec<(u64, f64)> {
+        stats.days.iter().map(|(&day, record)| (day, record.play_secs)).collect()
+    }
+
+    #[test]
+    fn play_time_within_a_day_goes_on_that_day() {
+        let mut stats = Stats::default();
+        stats.add_play_time(100.0 * SECS_PER_DAY + 600.0, 300.0, 0);
+        assert_eq!(play_days(&stats), vec![(100, 300.0)]);
+    }
+
+    #[test]
+    fn play_time_is_split_at_the_local_midnight() {
+        // an hour ahead of UTC, the local midnight is at 23:00 UTC, so 22:50 to 23:20 UTC is 10 minutes before it and 20 after
+        let mut stats = Stats::default();
+        stats.add_play_time(100.0 * SECS_PER_DAY - 2400.0, 1800.0, 3600);
+        assert_eq!(play_days(&stats), vec![(99, 600.0), (100, 1200.0)]);
+
+        // the same time, for a timezone behind UTC -- its midnight hasn't come yet, so the time is all on one local day
+        let mut stats = Stats::default();
+        stats.add_play_time(100.0 * SECS_PER_DAY - 2400.0, 1800.0, -5 * 3600);
+        assert_eq!(play_days(&stats), vec![(99, 1800.0)]);
+    }
+
+    #[test]
+    fn play_time_behind_utc_crosses_its_own_midnight() {
+        // five hours behind UTC, the local midnight is at 05:00 UTC
+        let mut stats = Stats::default();
+        stats.add_play_time(100.0 * SECS_PER_DAY + 5.0 * 3600.0 + 60.0, 120.0, -5 * 3600);
+        assert_eq!(play_days(&stats), vec![(99, 60.0), (100, 60.0)]);
+    }
+
+    #[test]
+    fn play_time_past_the_last_day_doesnt_loop() {
+        let mut stats = Stats::default();
+        stats.add_play_time(1e30, 1e15, 0);
+        assert_eq!(stats.days.len(), 1);
+        assert!(stats.days.contains_key(&u64::MAX));
+    }
+
+    #[test]
+    fn a_week_is_the_seven_days_ending_on_the_last_day() {
+        let mut stats = Stats::default();
+        for day in 92..=101 {
+            stats.days.insert(day, DayRecord { play_secs: day as f64, games: 1 });
+        }
+        let week = stats.week(100);
+        assert_eq!(week.map(|day| day.play_secs), [94.0, 95.0, 96.0, 97.0, 98.0, 99.0, 100.0]);
+        // the week before ends the day before this one starts, so no day is counted in both
+        assert_eq!(stats.week(93).map(|day| day.play_secs), [0.0, 0.0, 0.0, 0.0, 0.0, 92.0, 93.0]);
+    }
+
+    #[test]
+    fn a_week_near_the_epoch_has_empty_days_before_it() {
+        let mut stats = Stats::default();
+        stats.days.insert(0, DayRecord { play_secs: 60.0, games: 1 });
+        assert_eq!(stats.week(2).map(|day| day.play_secs), [0.0, 0.0, 0.0, 0.0, 60.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn local_play_time_lands_in_the_local_week() {
+        // played just after the local midnight that starts day 100, an hour ahead of UTC (so still day 99 in UTC)
+        let mut stats = Stats::default();
+        stats.add_play_time(100.0 * SECS_PER_DAY - 3000.0, 300.0, 3600);
+        assert_eq!(stats.week(100)[6].play_secs, 300.0);
+        assert_eq!(stats.week(99)[6].play_secs, 0.0);
+    }
+}
@@ -0,0 +1,143 @@
+use eframe::egui::{Align2, Color32, FontFamily, FontId, Painter, Pos2, Rect, Response, Sense, Stroke, Ui, Vec2};
+use crate::board::Grid;
+
+/*
+    The ThumbnailColors struct is the color scheme for a board thumbnail
+    the thumbnail uses its own colors instead of the live egui theme, so it looks the same in light and dark mode
+    given is the color of the puzzle's starting digits, correct is the color of the user's correct entries, and wrong is the color of incorrect entries
+*/
+pub struct ThumbnailColors {
+    pub background: Color32,
+    pub cell_lines: Color32,
+    pub box_lines: Color32,
+    pub given: Color32,
+    pub correct: Color32,
+    pub wrong: Color32,
+}
+
+impl ThumbnailColors {
+    // the default scheme, matching the colors used on the main board
+    pub fn standard() -> Self {
+        Self {
+            background: Color32::WHITE,
+            cell_lines: Color32::from_gray(200),
+            box_lines: Color32::from_gray(60),
+            given: Color32::BLACK,
+            correct: Color32::from_rgb(0, 124, 255),
+            wrong: Color32::from_rgb(255, 60, 110),
+        }
+    }
+}
+
+// draws a read-only miniature of a board, cell_size pixels per cell, and returns its response
+    // starting decides which digits are givens, player is what gets drawn, and solution decides which entries are wrong
+    // empty cells are left blank
+pub fn draw(ui: &mut Ui, starting: &Grid, player: &Grid, solution: &Grid, cell_size: f32, colors: &ThumbnailColors) -> Response {
+    let (response, painter) = ui.allocate_painter(Vec2::splat(cell_size * 9.0), Sense::hover());
+    let origin = response.rect.min;
+    painter.rect_filled(response.rect, 0.0, colors.background);
+
+    // draw each digit in the center of its cell
+    let font = FontId::new(cell_size * 0.7, FontFamily::Proportional);
+    for row in 0..9 {
+        for col in 0..9 {
+            let num = player[row][col];
+            if num == '.' {
+                continue;
+            }
+            let color = if starting[row][col] != '.' {
+                colors.given
+            }
+            else if num == solution[row][col] {
+                colors.correct
+            }
+            else {
+                colors.wrong
+            };
+            let center = origin + Vec2::new((col as f32 + 0.5) * cell_size, (row as f32 + 0.5) * cell_size);
+            painter.text(center, Align2::CENTER_CENTER, num, font.clone(), color);
+        }
+    }
+
+    draw_grid_lines(&painter, origin, cell_size, colors);
+
+    response
+}
+
+// draws the lines of a 9x9 board whose top left corner is at origin, with thicker lines between the 3x3 boxes
+fn draw_grid_lines(painter: &Painter, origin: Pos2, cell_size: f32, colors: &ThumbnailColors) {
+    for i in 0..=9 {
+        let offset = i as f32 * cell_size;
+        let stroke = if i % 3 == 0 {
+            Stroke::new(2.0, colors.box_lines)
+        }
+        else {
+            Stroke::new(1.0, colors.cell_lines)
+        };
+        painter.line_segment([origin + Vec2::new(offset, 0.0), origin + Vec2::new(offset, cell_size * 9.0)], stroke);
+        painter.line_segment([origin + Vec2::new(0.0, offset), origin + Vec2::new(cell_size * 9.0, offset)], stroke);
+    }
+}
+
+// picks a color on a ramp from white (no mistakes) to deep red (the most mistakes)
+pub fn heat_color(value: u32, max: u32) -> Color32 {
+    let t = if max == 0 { 0.0 } else { value as f32 / max as f32 };
+    let fade = (255.0 * (1.0 - t)) as u8;
+    Color32::from_rgb(255 - (t * 75.0) as u8, fade, fade)
+}
+
+// draws a board-shaped heatmap, coloring each cell by its value and writing the value in the cell
+    // like the thumbnail, it uses its own colors so it looks the same in light and dark mode
+pub fn draw_heatmap(ui: &mut Ui, values: &[[u32; 9]; 9], cell_size: f32, colors: &ThumbnailColors) -> Response {
+    let (response, painter) = ui.allocate_painter(Vec2::splat(cell_size * 9.0), Sense::hover());
+    let origin = response.rect.min;
+    let max = values.iter().flatten().copied().max().unwrap_or(0);
+    let font = FontId::new(cell_size * 0.4, FontFamily::Proportional);
+
+    for row in 0..9 {
+        for col in 0..9 {
+            let cell = Rect::from_min_size(origin + Vec2::new(col as f32 * cell_size, row as f32 * cell_size), Vec2::splat(cell_size));
+            painter.rect_filled(cell, 0.0, heat_color(values[row][col], max));
+            if values[row][col] > 0 {
+                painter.text(cell.center(), Align2::CENTER_CENTER, values[row][col].to_string(), font.clone(), colors.given);
+            }
+        }
+    }
+
+    draw_grid_lines(&painter, origin, cell_size, colors);
+
+    response
+}
+
+// draws a strip of nine cells, one per digit, colored by each digit's value
+    // each cell shows the digit, with its value underneath
+pub fn draw_digit_heatmap(ui: &mut Ui, values: &[u32; 9], cell_size: f32, colors: &ThumbnailColors) -> Response {
+    let (response, painter) = ui.allocate_painter(Vec2::new(cell_size * 9.0, cell_size), Sense::hover());
+    let origin = response.rect.min;
+    let max = values.iter().copied().max().unwrap_or(0);
+    for (i, &value) in values.iter().enumerate() {
+        let cell = Rect::from_min_size(origin + Vec2::new(i as f32 * cell_size, 0.0), Vec2::splat(cell_size));
+        painter.rect_filled(cell, 0.0, heat_color(value, max));
+        painter.rect_stroke(cell, 0.0, Stroke::new(1.0, colors.box_lines));
+        painter.text(cell.center() - Vec2::new(0.0, cell_size * 0.15), Align2::CENTER_CENTER, (i + 1).to_string(),
+            FontId::new(cell_size * 0.45, FontFamily::Proportional), colors.given);
+        painter.text(cell.center() + Vec2::new(0.0, cell_size * 0.28), Align2::CENTER_CENTER, value.to_string(),
+            FontId::new(cell_size * 0.25, FontFamily::Proportional), colors.given);
+    }
+    response
+}
+
+// draws the legend for a heatmap: the color ramp from 0 up to max, labeled at both ends
+pub fn draw_heat_legend(ui: &mut Ui, max: u32) {
+    ui.horizontal(|ui| {
+        ui.label("0");
+        let (response, painter) = ui.allocate_painter(Vec2::new(150.0, 14.0), Sense::hover());
+        let steps = 30;
+        let width = response.rect.width() / steps as f32;
+        for i in 0..steps {
+            let rect = Rect::from_min_size(response.rect.min + Vec2::new(i as f32 * width, 0.0), Vec2::new(width + 0.5, response.rect.height()));
+            painter.rect_filled(rect, 0.0, heat_color(i, steps - 1));
+        }
+        ui.label(max.to_string());
+    });
+}
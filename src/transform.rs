@@ -0,0 +1,138 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::board::{Grid, DIGITS};
+
+/*
+    The Transform enum represents one symmetry of a sudoku board
+    every transform maps a valid puzzle/solution pair to another valid puzzle/solution pair,
+    as long as the exact same transform is applied to both grids
+    Relabel stores the new digit for each old digit -- e.g. mapping[0] is the digit that every '1' becomes
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    ReflectHorizontal,
+    ReflectVertical,
+    Relabel([char; 9]),
+}
+
+impl Transform {
+    // picks one of the transforms at random
+    pub fn random<R: Rng>(rng: &mut R) -> Self {
+        match rng.gen_range(0..6) {
+            0 => Transform::Rotate90,
+            1 => Transform::Rotate180,
+            2 => Transform::Rotate270,
+            3 => Transform::ReflectHorizontal,
+            4 => Transform::ReflectVertical,
+            _ => {
+                // shuffle the digits 1-9 to get a random relabeling
+                let mut mapping = DIGITS;
+                mapping.shuffle(rng);
+                Transform::Relabel(mapping)
+            }
+        }
+    }
+
+    // applies the transform to a grid and returns the transformed grid
+    // empty cells ('.') stay empty, they just move to their new position
+    pub fn apply(&self, grid: &Grid) -> Grid {
+        let mut result = [['.'; 9]; 9];
+        for row in 0..9 {
+            for col in 0..9 {
+                match self {
+                    // a clockwise rotation moves the cell at (row, col) to (col, 8 - row)
+                    Transform::Rotate90 => result[col][8 - row] = grid[row][col],
+                    Transform::Rotate180 => result[8 - row][8 - col] = grid[row][col],
+                    Transform::Rotate270 => result[8 - col][row] = grid[row][col],
+                    // horizontal reflection mirrors the board left to right, vertical mirrors it top to bottom
+                    Transform::ReflectHorizontal => result[row][8 - col] = grid[row][col],
+                    Transform::ReflectVertical => result[8 - row][col] = grid[row][col],
+                    Transform::Relabel(mapping) => {
+                        result[row][col] = match grid[row][col].to_digit(10) {
+                            Some(digit) if digit >= 1 => mapping[digit as usize - 1],
+                            _ => grid[row][col],
+                        };
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::{board, solver};
+
+    const PUZZLE: &str = "1.4.28...3.815...7265.7.4.17438..15...2.4.73...97.162..3.......8.1..6....263.7.4.";
+
+    // one of each kind of transform, with a relabeling that moves every digit
+    fn every_transform() -> Vec<Transform> {
+        vec![
+            Transform::Rotate90,
+            Transform::Rotate180,
+            Transform::Rotate270,
+            Transform::ReflectHorizontal,
+            Transform::ReflectVertical,
+            Transform::Relabel(['9', '1', '2', '3', '4', '5', '6', '7', '8']),
+        ]
+    }
+
+    // checks that the transformed puzzle still has exactly one solution, and that it is the transformed solution
+    fn assert_still_valid(transform: Transform) {
+        let puzzle = board::string_to_grid(PUZZLE);
+        let solution = solver::solve(&puzzle).unwrap();
+        let moved_puzzle = transform.apply(&puzzle);
+        let moved_solution = transform.apply(&solution);
+        assert_eq!(solver::solutions(&moved_puzzle, 2), vec![moved_solution], "{:?}", transform);
+    }
+
+    #[test]
+    fn every_transform_keeps_one_solution() {
+        for transform in every_transform() {
+            assert_still_valid(transform);
+        }
+    }
+
+    #[test]
+    fn random_transforms_keep_one_solution() {
+        let mut rng = StdRng::seed_from_u64(424);
+        for _ in 0..50 {
+            assert_still_valid(Transform::random(&mut rng));
+        }
+    }
+
+    #[test]
+    fn empty_cells_stay_empty() {
+        let puzzle = board::string_to_grid(PUZZLE);
+        let empty = |grid: &Grid| grid.iter().flatten().filter(|&&cell| cell == '.').count();
+        for transform in every_transform() {
+            assert_eq!(empty(&transform.apply(&puzzle)), empty(&puzzle), "{:?}", transform);
+        }
+    }
+
+    #[test]
+    fn rotations_and_reflections_undo_each_other() {
+        let puzzle = board::string_to_grid(PUZZLE);
+        assert_eq!(Transform::Rotate270.apply(&Transform::Rotate90.apply(&puzzle)), puzzle);
+        assert_eq!(Transform::Rotate180.apply(&Transform::Rotate180.apply(&puzzle)), puzzle);
+        assert_eq!(Transform::Rotate90.apply(&Transform::Rotate90.apply(&puzzle)), Transform::Rotate180.apply(&puzzle));
+        assert_eq!(Transform::ReflectHorizontal.apply(&Transform::ReflectHorizontal.apply(&puzzle)), puzzle);
+        assert_eq!(Transform::ReflectVertical.apply(&Transform::ReflectVertical.apply(&puzzle)), puzzle);
+    }
+
+    #[test]
+    fn relabeling_keeps_cells_in_place() {
+        let puzzle = board::string_to_grid(PUZZLE);
+        let moved = Transform::Relabel(['9', '1', '2', '3', '4', '5', '6', '7', '8']).apply(&puzzle);
+        assert_eq!(moved[0][0], '9');
+        assert_eq!(moved[0][2], '3');
+        assert_eq!(moved[0][1], '.');
+    }
+}
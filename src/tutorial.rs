@@ -0,0 +1,95 @@
+use eframe::egui::{self, Align2, Color32, Context, Id, LayerId, Order, Rect, Vec2};
+use crate::Sudoku;
+
+// the steps of the tutorial, in order -- the first step is on the difficulty screen and the rest are in the first game
+#[derive(Clone, Copy, PartialEq)]
+pub enum TutorialStep {
+    Difficulty,
+    SelectCell,
+    EnterDigit,
+    Erase,
+    Strikes,
+}
+
+impl TutorialStep {
+    // the step after this one, or None after the last step
+    fn next(self) -> Option<Self> {
+        match self {
+            TutorialStep::Difficulty => Some(TutorialStep::SelectCell),
+            TutorialStep::SelectCell => Some(TutorialStep::EnterDigit),
+            TutorialStep::EnterDigit => Some(TutorialStep::Erase),
+            TutorialStep::Erase => Some(TutorialStep::Strikes),
+            TutorialStep::Strikes => None,
+        }
+    }
+
+    // what the callout for the step says
+    fn message(self) -> &'static str {
+        match self {
+            TutorialStep::Difficulty => "Pick a difficulty to start a game. Beginner is a good place to start.",
+            TutorialStep::SelectCell => "Click a cell to select it.",
+            TutorialStep::EnterDigit => "Type a digit from 1 to 9 to fill in the selected cell.",
+            TutorialStep::Erase => "Made a mistake? Backspace clears the selected cell, and Ctrl + Z undoes the last change.",
+            TutorialStep::Strikes => "Every wrong digit is a strike. Reach the strike limit and the game is over, so take your time.",
+        }
+    }
+}
+
+impl Sudoku {
+    // moves the tutorial on by itself once the user has done what the current step asks
+        // the erase and strikes steps only move on from their buttons, since there may be nothing to erase or no strike yet
+    pub(crate) fn advance_tutorial(&mut self) {
+        let done = match self.tutorial_step {
+            Some(TutorialStep::Difficulty) => !self.difficulty.is_empty(),
+            Some(TutorialStep::SelectCell) => self.selected[0] < 9 && self.selected[1] < 9,
+            Some(TutorialStep::EnterDigit) => !self.actions.is_empty(),
+            _ => false,
+        };
+        if done {
+            self.tutorial_step = self.tutorial_step.and_then(TutorialStep::next);
+        }
+    }
+
+    // draws the callout for step pointing at target (if the tutorial is on that step), with buttons to move on or stop
+        // "Skip" hides the tutorial until the next launch, and "Don't show again" (or finishing it) turns it off for good
+    pub(crate) fn tutorial_callout(&mut self, ctx: &Context, step: TutorialStep, target: Rect) {
+        if self.tutorial_step != Some(step) || !target.is_positive() {
+            return;
+        }
+        let accent = Color32::from_rgb(60, 190, 220);
+        ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("tutorial_highlight")))
+            .rect_stroke(target.expand(6.0), 6.0, (3.0, accent));
+
+        let mut next = false;
+        let mut skip = false;
+        let mut stop = false;
+        egui::Area::new(Id::new("tutorial_callout"))
+            .order(Order::Foreground)
+            .pivot(Align2::CENTER_TOP)
+            .fixed_pos(target.center_bottom() + Vec2::new(0.0, 12.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).stroke((2.0, accent)).show(ui, |ui| {
+                    ui.set_max_width(320.0);
+                    ui.label(step.message());
+                    ui.horizontal(|ui| {
+                        next = ui.button(if step.next().is_some() { "Next" } else { "Got it" }).clicked();
+                        skip = ui.button("Skip").clicked();
+                        stop = ui.button("Don't show again").clicked();
+                    });
+                });
+            });
+
+        if next {
+            self.tutorial_step = step.next();
+            stop |= self.tutorial_step.is_none();
+        }
+        if skip {
+            self.tutorial_step = None;
+        }
+        if stop {
+            self.tutorial_step = None;
+            self.settings.show_tutorial = false;
+            self.settings.save();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+use eframe::egui::Context;
+use semver::Version;
+use crate::clock;
+
+// the GitHub API endpoint for the newest release, and the page users are sent to when there is one
+const LATEST_RELEASE_API: &str = "https://api.github.com/repos/yung00se/Sudoku_app/releases/latest";
+
+// checks for updates at most this often
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+// the Release struct stores a release that is newer than the running version
+pub struct Release {
+    pub version: String,
+    pub url: String,
+}
+
+// returns true if it has been long enough since the last check (or there never was one)
+    // a last check in the future means the clock was moved back, so a check is due rather than waiting for the clock to catch up
+pub fn check_due(last_checked: Option<u64>) -> bool {
+    last_checked.is_none_or(|last| clock::secs_since(last).is_none_or(|secs| secs >= CHECK_INTERVAL_SECS))
+}
+
+// starts checking for a newer release on a background thread, so startup is never blocked on the network
+    // the receiver only ever gets a message if a newer release was found -- failures are just logged
+    // ctx is used to wake the ui up when the result arrives, even if the user isn't moving the mouse
+pub fn spawn_check(ctx: Context) -> Receiver<Release> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        match fetch_latest() {
+            Ok(Some(release)) => {
+                let _ = sender.send(release);
+                ctx.request_repaint();
+            }
+            Ok(None) => {}
+            Err(err) => println!("Update check failed: {}", err),
+        }
+    });
+    receiver
+}
+
+// asks GitHub for the latest release, and returns it if it is newer than this build
+fn fetch_latest() -> Result<Option<Release>, String> {
+    let response: serde_json::Value = ureq::get(LATEST_RELEASE_API)
+        .set("User-Agent", "sudoku-update-check")
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_json()
+        .map_err(|err| err.to_string())?;
+
+    let tag = response["tag_name"].as_str().ok_or("release has no tag")?;
+    let url = response["html_url"].as_str().ok_or("release has no url")?;
+    if is_newer(tag, env!("CARGO_PKG_VERSION")) {
+        Ok(Some(Release {
+            version: tag.to_string(),
+            url: url.to_string(),
+        }))
+    }
+    else {
+        Ok(None)
+    }
+}
+
+// compares a release tag like "v0.4.0" against the current version using semver rules
+    // a tag that isn't a valid version is never considered newer
+pub fn is_newer(tag: &str, current: &str) -> bool {
+    match (Version::parse(tag.trim_start_matches('v')), Version::parse(current)) {
+        (Ok(tag), Ok(current)) => tag > current,
+        _ => false,
+    }
+}
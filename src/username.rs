@@ -0,0 +1,149 @@
+use unicode_normalization::UnicodeNormalization;
+
+// the shortest and longest a username can be, in characters (after trimming and normalization)
+pub const MIN_LENGTH: usize = 1;
+pub const MAX_LENGTH: usize = 20;
+
+// the name used when a saved name has nothing usable left in it after sanitizing
+const FALLBACK: &str = "Player";
+
+// the Unicode format characters (general category Cf), which are invisible and can reorder or hide the text around them
+    // the zero width joiner and non-joiner (U+200C and U+200D) and the emoji tag characters are left out, since emoji sequences
+        // like flags and families need them, and scripts like Persian use the non-joiner inside words
+const FORMAT_CHARS: [(char, char); 20] = [
+    ('\u{00AD}', '\u{00AD}'),
+    ('\u{0600}', '\u{0605}'),
+    ('\u{061C}', '\u{061C}'),
+    ('\u{06DD}', '\u{06DD}'),
+    ('\u{070F}', '\u{070F}'),
+    ('\u{0890}', '\u{0891}'),
+    ('\u{08E2}', '\u{08E2}'),
+    ('\u{180E}', '\u{180E}'),
+    ('\u{200B}', '\u{200B}'),
+    ('\u{200E}', '\u{200F}'),
+    ('\u{202A}', '\u{202E}'),
+    ('\u{2060}', '\u{2064}'),
+    ('\u{2066}', '\u{206F}'),
+    ('\u{FEFF}', '\u{FEFF}'),
+    ('\u{FFF9}', '\u{FFFB}'),
+    ('\u{110BD}', '\u{110BD}'),
+    ('\u{110CD}', '\u{110CD}'),
+    ('\u{13430}', '\u{1343F}'),
+    ('\u{1BCA0}', '\u{1BCA3}'),
+    ('\u{1D173}', '\u{1D17A}'),
+];
+
+/*
+    The Username struct is a name that is safe to show in the header, write to files, and send to the scores server
+    it is trimmed, has its inner whitespace collapsed to single spaces, is NFC normalized (so the same name always has the same bytes),
+        is MIN_LENGTH to MAX_LENGTH characters long, and has no control or format characters
+    the only way to make one is Username::new or Username::sanitize, so every Username meets these rules
+*/
+#[derive(Clone, PartialEq)]
+pub struct Username(String);
+
+impl Username {
+    // checks a name the user typed, returning the cleaned up name or a message saying what's wrong with it
+    pub fn new(name: &str) -> Result<Self, String> {
+        let name = normalize(name);
+        if let Some(c) = name.chars().find(|&c| is_forbidden(c)) {
+            return Err(format!("Names can't contain the invisible character U+{:04X}", c as u32));
+        }
+        let length = name.chars().count();
+        if length < MIN_LENGTH {
+            return Err("Names can't be empty".to_string());
+        }
+        if length > MAX_LENGTH {
+            return Err(format!("Names can be at most {} characters long", MAX_LENGTH));
+        }
+        Ok(Self(name))
+    }
+
+    // turns any name into a valid one, for names that were saved before they were checked
+        // forbidden characters are removed and the name is cut down to MAX_LENGTH characters -- if nothing is left, FALLBACK is used
+    pub fn sanitize(name: &str) -> Self {
+        let cleaned: String = name.chars().filter(|&c| !is_forbidden(c)).collect();
+        let cleaned: String = normalize(&cleaned).chars().take(MAX_LENGTH).collect();
+        Self::new(&cleaned).unwrap_or_else(|_| Self(FALLBACK.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// trims the name, collapses runs of whitespace into single spaces, and NFC normalizes it
+fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").nfc().collect()
+}
+
+// returns true for control characters and format characters (see FORMAT_CHARS)
+fn is_forbidden(c: char) -> bool {
+    c.is_control() || FORMAT_CHARS.iter().any(|&(start, end)| (start..=end).contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepted(name: &str) -> String {
+        match Username::new(name) {
+            Ok(username) => username.as_str().to_string(),
+            Err(err) => panic!("{:?} was rejected: {}", name, err),
+        }
+    }
+
+    #[test]
+    fn emoji_names_are_kept_whole() {
+        assert_eq!(accepted("🦀 Crab"), "🦀 Crab");
+        // a flag is two regional indicators, and a family is joined with zero width joiners -- both have to stay as they are
+        assert_eq!(accepted("🇳🇿"), "🇳🇿");
+        assert_eq!(accepted("👨\u{200D}👩\u{200D}👧"), "👨\u{200D}👩\u{200D}👧");
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        assert!(Username::new(&"🦀".repeat(MAX_LENGTH)).is_ok());
+        assert!(Username::new(&"🦀".repeat(MAX_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn right_to_left_names_are_allowed() {
+        assert_eq!(accepted("مريم"), "مريم");
+        assert_eq!(accepted("  דוד  כהן "), "דוד כהן");
+        // Persian uses the zero width non-joiner inside words
+        assert_eq!(accepted("می\u{200C}خواهم"), "می\u{200C}خواهم");
+    }
+
+    #[test]
+    fn direction_overrides_are_rejected() {
+        assert!(Username::new("abc\u{202E}fed").is_err());
+        assert!(Username::new("\u{2067}name").is_err());
+        assert_eq!(Username::sanitize("abc\u{202E}fed").as_str(), "abcfed");
+    }
+
+    #[test]
+    fn whitespace_only_names_are_empty() {
+        for name in ["", " ", "   ", "\t\n", "\u{00A0}", "\u{3000}\u{2003}"] {
+            assert!(Username::new(name).is_err(), "{:?} was accepted", name);
+            assert_eq!(Username::sanitize(name).as_str(), FALLBACK);
+        }
+    }
+
+    #[test]
+    fn whitespace_inside_is_collapsed() {
+        assert_eq!(accepted(" Sam \t  Lee "), "Sam Lee");
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        assert_eq!(accepted("Ame\u{0301}lie"), "Am\u{00E9}lie");
+    }
+
+    #[test]
+    fn invisible_characters_are_rejected_or_removed() {
+        assert!(Username::new("Sam\u{200B}").is_err());
+        assert!(Username::new("Sam\u{0007}").is_err());
+        assert_eq!(Username::sanitize("S\u{200B}a\u{FEFF}m").as_str(), "Sam");
+    }
+}
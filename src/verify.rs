@@ -0,0 +1,83 @@
+use crate::board::{self, Grid};
+use crate::{solver, Puzzles};
+
+// the puzzle files that ship with the app, one per difficulty
+pub const POOLS: [&str; 4] = ["Beginner", "Intermediate", "Advanced", "Test"];
+
+// how many failures are printed per difficulty -- the rest are only counted
+const MAX_LISTED_FAILURES: usize = 5;
+
+// checks every puzzle in every bundled puzzle file, prints a table of the results, and returns the exit code
+    // it is meant to be run before a release (sudoku verify-pools), and exits with 1 if any puzzle or file is bad
+pub fn verify_pools() -> i32 {
+    let mut failed = false;
+    println!("{:<14}{:>8}{:>8}{:>8}", "Difficulty", "Puzzles", "OK", "Failed");
+    let mut listed = Vec::new();
+    for difficulty in POOLS {
+        // the file is read the same way the game reads it, so a line-delimited .jsonl pool is checked too
+        let puzzles = match Puzzles::load(difficulty) {
+            Ok(file) => file.puzzles,
+            Err(err) => {
+                println!("{:<14}{}", difficulty, err);
+                failed = true;
+                continue;
+            }
+        };
+        let mut failures = 0;
+        for (index, puzzle) in puzzles.iter().enumerate() {
+            if let Err(err) = verify_puzzle(&puzzle.puzzle, &puzzle.solution, puzzle.parity.as_deref()) {
+                failures += 1;
+                if failures <= MAX_LISTED_FAILURES {
+                    listed.push(format!("{} #{} ({}): {}", difficulty, index, puzzle.id(), err));
+                }
+            }
+        }
+        println!("{:<14}{:>8}{:>8}{:>8}", difficulty, puzzles.len(), puzzles.len() - failures, failures);
+        failed |= failures > 0;
+    }
+    for failure in &listed {
+        println!("{}", failure);
+    }
+    if failed { 1 } else { 0 }
+}
+
+// checks one puzzle file entry, returning what is wrong with it
+    // the strings must be 81 cells, the givens must match the solution, and the solution must be the puzzle's only solution
+    // playing each puzzle through the game itself is left to the pool test in main.rs, since that needs the whole game
+    // (cargo test -- --ignored runs it)
+fn verify_puzzle(puzzle: &str, solution: &str, parity: Option<&str>) -> Result<(), String> {
+    let puzzle_grid = parse(puzzle, true).map_err(|err| format!("puzzle {}", err))?;
+    let solution_grid = parse(solution, false).map_err(|err| format!("solution {}", err))?;
+    let mask = match parity {
+        Some(mask) if mask.chars().count() != 81 || !mask.chars().all(|c| matches!(c, 'e' | 'o' | '.')) => {
+            return Err("the parity mask must be 81 cells of 'e', 'o', or '.'".to_string());
+        }
+        Some(mask) => board::string_to_grid(mask),
+        None => board::NO_PARITY,
+    };
+    for row in 0..9 {
+        for col in 0..9 {
+            let given = puzzle_grid[row][col];
+            if given != '.' && given != solution_grid[row][col] {
+                return Err(format!("the given at row {}, column {} doesn't match the solution", row + 1, col + 1));
+            }
+        }
+    }
+    match solver::solutions_with_parity(&puzzle_grid, &mask, 2).as_slice() {
+        [] => Err("the puzzle has no solution".to_string()),
+        [found] if *found != solution_grid => Err("the solver found a different solution".to_string()),
+        [_] => Ok(()),
+        _ => Err("the puzzle has more than one solution".to_string()),
+    }
+}
+
+// reads an 81 cell puzzle string -- empty cells ('.') are only allowed in a puzzle, not in a solution
+fn parse(string: &str, allow_empty: bool) -> Result<Grid, String> {
+    if string.chars().count() != 81 {
+        return Err(format!("is {} cells long instead of 81", string.chars().count()));
+    }
+    if let Some(bad) = string.chars().find(|&c| !(board::DIGITS.contains(&c) || (allow_empty && c == '.'))) {
+        return Err(format!("contains '{}'", bad));
+    }
+    Ok(board::string_to_grid(string))
+}
@@ -0,0 +1,72 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// a few complete 4x4 boards (2x2 boxes) -- a warm-up puzzle is made by blanking out some cells of one of them
+const SOLUTIONS: [&str; 4] = [
+    "1234341221434321",
+    "2143341212344321",
+    "3412124343212134",
+    "4321124321343412",
+];
+
+// how many cells are left blank in a warm-up puzzle
+const BLANKS: usize = 9;
+
+/*
+    The WarmUp struct is a tiny 4x4 sudoku played before a real game to get the brain going
+    givens stores the starting digits, which can't be changed, and grid stores the board as it is being played
+    empty cells are '.', just like the 9x9 board
+    selected is the cell the user has clicked on, if any
+*/
+pub struct WarmUp {
+    pub givens: [[char; 4]; 4],
+    pub grid: [[char; 4]; 4],
+    pub selected: Option<(usize, usize)>,
+}
+
+impl WarmUp {
+    // makes a random warm-up puzzle
+    pub fn random() -> Self {
+        let mut rng = rand::thread_rng();
+        let solution: Vec<char> = SOLUTIONS[rng.gen_range(0..SOLUTIONS.len())].chars().collect();
+        let mut givens = [['.'; 4]; 4];
+        for (index, &digit) in solution.iter().enumerate() {
+            givens[index / 4][index % 4] = digit;
+        }
+
+        // blank out random cells
+        let mut cells: Vec<usize> = (0..16).collect();
+        cells.shuffle(&mut rng);
+        for &index in cells.iter().take(BLANKS) {
+            givens[index / 4][index % 4] = '.';
+        }
+
+        Self {
+            givens,
+            grid: givens,
+            selected: None,
+        }
+    }
+
+    // the warm-up is solved when every cell is filled and no row, column, or 2x2 box repeats a digit
+        // any valid completion counts, since the warm-up isn't checked against a stored solution
+    pub fn is_solved(&self) -> bool {
+        if self.grid.iter().flatten().any(|&c| c == '.') {
+            return false;
+        }
+        for i in 0..4 {
+            let (box_row, box_col) = ((i / 2) * 2, (i % 2) * 2);
+            let mut row: Vec<char> = (0..4).map(|j| self.grid[i][j]).collect();
+            let mut col: Vec<char> = (0..4).map(|j| self.grid[j][i]).collect();
+            let mut square: Vec<char> = (0..4).map(|j| self.grid[box_row + j / 2][box_col + j % 2]).collect();
+            for unit in [&mut row, &mut col, &mut square] {
+                unit.sort();
+                unit.dedup();
+                if unit.len() != 4 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
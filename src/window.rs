@@ -0,0 +1,145 @@
+use eframe::egui::{Context, Pos2, ViewportBuilder, ViewportCommand, Vec2};
+use serde::{Deserialize, Serialize};
+
+// the smallest window the app opens with, so a stored size can't make the board unusable
+const MIN_SIZE: Vec2 = Vec2::new(640.0, 480.0);
+
+/*
+    The WindowGeometry struct is where the window was and how big it was when the app was last closed, so it reopens the same way
+    position is the top left corner of the window's frame and size is the size of its contents, both in points
+    maximized and fullscreen are stored on their own, so position and size are still the ones to go back to when the window is restored
+    monitor_size is the size of the monitor the window was on, so a changed display setup can be noticed on the next launch
+*/
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WindowGeometry {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub monitor_size: Option<[f32; 2]>,
+}
+
+// the viewport to open the window with -- the stored geometry if there is one, otherwise a maximized window
+pub fn viewport(geometry: Option<WindowGeometry>) -> ViewportBuilder {
+    let builder = ViewportBuilder::default().with_min_inner_size(MIN_SIZE);
+    match geometry {
+        Some(geometry) => builder
+            .with_position(Pos2::from(geometry.position))
+            .with_inner_size(Vec2::from(geometry.size).max(MIN_SIZE))
+            .with_maximized(geometry.maximized)
+            .with_fullscreen(geometry.fullscreen),
+        None => builder.with_maximized(true),
+    }
+}
+
+// reads the window's geometry this frame into geometry, so it can be stored when the app closes -- returns true if it changed
+    // while the window is maximized or fullscreen only those flags are updated, so restoring it later goes back to the old size
+pub fn observe(ctx: &Context, geometry: &mut Option<WindowGeometry>) -> bool {
+    let (outer, inner, maximized, fullscreen, monitor) = ctx.input(|input| {
+        let info = input.viewport();
+        (info.outer_rect, info.inner_rect, info.maximized, info.fullscreen, info.monitor_size)
+    });
+    let (Some(outer), Some(inner)) = (outer, inner) else { return false };
+    let before = *geometry;
+    let maximized = maximized.unwrap_or(false);
+    let fullscreen = fullscreen.unwrap_or(false);
+    let stored = geometry.get_or_insert(WindowGeometry {
+        position: outer.min.into(),
+        size: inner.size().into(),
+        maximized,
+        fullscreen,
+        monitor_size: None,
+    });
+    if !maximized && !fullscreen {
+        stored.position = outer.min.into();
+        stored.size = inner.size().into();
+    }
+    stored.maximized = maximized;
+    stored.fullscreen = fullscreen;
+    stored.monitor_size = monitor.map(Into::into).or(stored.monitor_size);
+    *geometry != before
+}
+
+// moves the window back onto the monitor if it ended up off it (e.g. it was last on an external display that isn't connected any more)
+    // -- a window that is too big is shrunk to fit
+    // this is checked whatever monitor_size was stored, since a disconnected display can be the same size as the one that is left
+    // returns false while the window's monitor isn't known yet, so it can be checked again on the next frame
+pub fn clamp_to_monitor(ctx: &Context, geometry: &WindowGeometry) -> bool {
+    let (outer, inner, monitor) = ctx.input(|input| {
+        let info = input.viewport();
+        (info.outer_rect, info.inner_rect, info.monitor_size)
+    });
+    let (Some(outer), Some(inner), Some(monitor)) = (outer, inner, monitor) else { return false };
+    if geometry.maximized || geometry.fullscreen {
+        return true;
+    }
+
+    let frame = outer.size() - inner.size();
+    let (position, size) = clamp(outer.min, inner.size(), frame, monitor);
+    if size != inner.size() {
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(size));
+    }
+    if position != outer.min {
+        ctx.send_viewport_cmd(ViewportCommand::OuterPosition(position));
+    }
+    true
+}
+
+// where a window has to be and how big its contents can be to fit on a monitor of the given size (which is taken to start at 0, 0)
+    // position is the top left corner of the window's frame, size is the size of its contents, and frame is how much the title bar
+    // and borders add around the contents -- a window that already fits comes back unchanged
+    // a window that is too big shrinks to the monitor (but never below MIN_SIZE), and one that hangs off an edge is moved back
+    // just far enough to be on the monitor, so a window that is only a little off doesn't jump to the corner
+pub fn clamp(position: Pos2, size: Vec2, frame: Vec2, monitor: Vec2) -> (Pos2, Vec2) {
+    let size = size.min(monitor - frame).max(MIN_SIZE);
+    let outer = size + frame;
+    let position = Pos2::new(
+        position.x.clamp(0.0, (monitor.x - outer.x).max(0.0)),
+        position.y.clamp(0.0, (monitor.y - outer.y).max(0.0)),
+    );
+    (position, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Vec2 = Vec2::new(0.0, 30.0);
+    const LAPTOP: Vec2 = Vec2::new(1920.0, 1080.0);
+
+    #[test]
+    fn window_that_fits_is_unchanged() {
+        let position = Pos2::new(100.0, 50.0);
+        let size = Vec2::new(1000.0, 800.0);
+        assert_eq!(clamp(position, size, FRAME, LAPTOP), (position, size));
+    }
+
+    #[test]
+    fn window_off_a_same_size_display_comes_back() {
+        // last on an external 1920x1080 display to the right of the laptop's, which isn't connected any more
+        let (position, size) = clamp(Pos2::new(2020.0, 100.0), Vec2::new(1000.0, 800.0), FRAME, LAPTOP);
+        assert_eq!(position, Pos2::new(920.0, 100.0));
+        assert_eq!(size, Vec2::new(1000.0, 800.0));
+    }
+
+    #[test]
+    fn window_above_or_left_of_the_monitor_comes_back() {
+        let (position, _) = clamp(Pos2::new(-500.0, -40.0), Vec2::new(1000.0, 800.0), FRAME, LAPTOP);
+        assert_eq!(position, Pos2::ZERO);
+    }
+
+    #[test]
+    fn window_too_big_shrinks_to_fit_with_its_frame() {
+        let (position, size) = clamp(Pos2::new(300.0, 200.0), Vec2::new(2560.0, 1440.0), FRAME, LAPTOP);
+        assert_eq!(size, Vec2::new(1920.0, 1050.0));
+        assert_eq!(position, Pos2::ZERO);
+    }
+
+    #[test]
+    fn window_never_shrinks_below_the_minimum() {
+        let tiny = Vec2::new(500.0, 400.0);
+        let (position, size) = clamp(Pos2::new(50.0, 50.0), Vec2::new(800.0, 600.0), FRAME, tiny);
+        assert_eq!(size, MIN_SIZE);
+        assert_eq!(position, Pos2::ZERO);
+    }
+}